@@ -0,0 +1,3 @@
+-- down.sql
+ALTER TABLE clipboard_history DROP COLUMN value_rtf;
+ALTER TABLE clipboard_history DROP COLUMN value_html;
@@ -0,0 +1,3 @@
+-- Rich text flavors captured alongside the plain text value
+ALTER TABLE clipboard_history ADD COLUMN value_html TEXT;
+ALTER TABLE clipboard_history ADD COLUMN value_rtf TEXT;
@@ -0,0 +1,4 @@
+-- down.sql
+DROP INDEX IF EXISTS idx_history_files_history_id;
+DROP TABLE IF EXISTS clipboard_history_files;
+ALTER TABLE clipboard_history DROP COLUMN is_file;
@@ -0,0 +1,18 @@
+-- File lists copied from a file manager
+ALTER TABLE clipboard_history ADD COLUMN is_file BOOLEAN DEFAULT FALSE;
+
+CREATE TABLE clipboard_history_files (
+    file_id VARCHAR(50) PRIMARY KEY NOT NULL,
+    history_id VARCHAR(50) NOT NULL,
+    file_path TEXT NOT NULL,
+    file_name VARCHAR(255),
+    file_size BIGINT,
+    mime_type VARCHAR(255),
+    is_directory BOOLEAN DEFAULT FALSE,
+    order_number INT NOT NULL DEFAULT 0,
+    created_at BIGINT NOT NULL,
+
+    FOREIGN KEY (history_id) REFERENCES clipboard_history(history_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_history_files_history_id ON clipboard_history_files (history_id);
@@ -0,0 +1,4 @@
+-- down.sql
+ALTER TABLE clipboard_history DROP COLUMN expires_at;
+DROP INDEX IF EXISTS idx_capture_rules_order;
+DROP TABLE IF EXISTS capture_rules;
@@ -0,0 +1,20 @@
+CREATE TABLE capture_rules (
+    rule_id VARCHAR(50) PRIMARY KEY NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    is_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    order_number INT NOT NULL DEFAULT 0,
+    match_content_regex TEXT,
+    match_app VARCHAR(255),
+    match_window_title VARCHAR(255),
+    match_language VARCHAR(50),
+    match_content_type VARCHAR(20),
+    action VARCHAR(20) NOT NULL,
+    action_value VARCHAR(255),
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_capture_rules_order ON capture_rules (order_number);
+
+-- Set by the "expire" capture rule action
+ALTER TABLE clipboard_history ADD COLUMN expires_at BIGINT;
@@ -0,0 +1,2 @@
+-- JSON list of secret detectors that matched the captured value
+ALTER TABLE clipboard_history ADD COLUMN detected_secrets TEXT;
@@ -0,0 +1 @@
+CREATE INDEX idx_history_expires_at ON clipboard_history (expires_at) WHERE expires_at IS NOT NULL;
@@ -0,0 +1,2 @@
+-- down.sql
+ALTER TABLE clipboard_history DROP COLUMN source_selection;
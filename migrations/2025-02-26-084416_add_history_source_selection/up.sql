@@ -0,0 +1,2 @@
+-- Which clipboard a history entry was captured from: clipboard or primary (Linux selection)
+ALTER TABLE clipboard_history ADD COLUMN source_selection TEXT;
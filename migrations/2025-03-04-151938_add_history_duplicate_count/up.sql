@@ -0,0 +1,2 @@
+-- How many times the same value was copied again and merged into this entry
+ALTER TABLE clipboard_history ADD COLUMN duplicate_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Encoding of the full resolution image file, NULL for images saved as PNG before this column existed
+ALTER TABLE clipboard_history ADD COLUMN image_format TEXT;
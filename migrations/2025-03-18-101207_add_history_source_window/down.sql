@@ -0,0 +1,4 @@
+-- down.sql
+DROP INDEX IF EXISTS idx_history_copied_from_window_title;
+ALTER TABLE clipboard_history DROP COLUMN copied_from_process_path;
+ALTER TABLE clipboard_history DROP COLUMN copied_from_window_title;
@@ -0,0 +1,5 @@
+-- Title and executable path of the window the content was copied from
+ALTER TABLE clipboard_history ADD COLUMN copied_from_window_title TEXT;
+ALTER TABLE clipboard_history ADD COLUMN copied_from_process_path TEXT;
+
+CREATE INDEX idx_history_copied_from_window_title ON clipboard_history (copied_from_window_title);
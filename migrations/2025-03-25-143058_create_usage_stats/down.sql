@@ -0,0 +1,5 @@
+-- down.sql
+DROP INDEX IF EXISTS idx_usage_stats_frecency_rank;
+DROP INDEX IF EXISTS idx_usage_stats_item_id;
+DROP INDEX IF EXISTS idx_usage_stats_history_id;
+DROP TABLE IF EXISTS usage_stats;
@@ -0,0 +1,15 @@
+-- One row per history entry or clip that was copied or pasted
+CREATE TABLE usage_stats (
+    usage_id VARCHAR(50) PRIMARY KEY NOT NULL,
+    history_id VARCHAR(50),
+    item_id VARCHAR(50),
+    use_count INT NOT NULL DEFAULT 0,
+    last_used_at BIGINT NOT NULL,
+    -- Time independent frecency rank, see services/usage_service.rs
+    frecency_rank DOUBLE NOT NULL DEFAULT 0,
+    created_at BIGINT NOT NULL
+);
+
+CREATE UNIQUE INDEX idx_usage_stats_history_id ON usage_stats (history_id) WHERE history_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_usage_stats_item_id ON usage_stats (item_id) WHERE item_id IS NOT NULL;
+CREATE INDEX idx_usage_stats_frecency_rank ON usage_stats (frecency_rank);
@@ -0,0 +1,5 @@
+-- down.sql
+DROP TRIGGER IF EXISTS clipboard_history_fts_after_update;
+DROP TRIGGER IF EXISTS clipboard_history_fts_after_delete;
+DROP TRIGGER IF EXISTS clipboard_history_fts_after_insert;
+DROP TABLE IF EXISTS clipboard_history_fts;
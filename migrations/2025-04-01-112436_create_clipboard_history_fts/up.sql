@@ -0,0 +1,29 @@
+-- Full-text index over clipboard history, the text itself is read from clipboard_history by rowid
+CREATE VIRTUAL TABLE clipboard_history_fts USING fts5(
+    value,
+    title,
+    copied_from_window_title,
+    content = 'clipboard_history',
+    content_rowid = 'rowid',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER clipboard_history_fts_after_insert AFTER INSERT ON clipboard_history BEGIN
+    INSERT INTO clipboard_history_fts (rowid, value, title, copied_from_window_title)
+    VALUES (new.rowid, new.value, new.title, new.copied_from_window_title);
+END;
+
+CREATE TRIGGER clipboard_history_fts_after_delete AFTER DELETE ON clipboard_history BEGIN
+    INSERT INTO clipboard_history_fts (clipboard_history_fts, rowid, value, title, copied_from_window_title)
+    VALUES ('delete', old.rowid, old.value, old.title, old.copied_from_window_title);
+END;
+
+CREATE TRIGGER clipboard_history_fts_after_update
+AFTER UPDATE OF value, title, copied_from_window_title ON clipboard_history BEGIN
+    INSERT INTO clipboard_history_fts (clipboard_history_fts, rowid, value, title, copied_from_window_title)
+    VALUES ('delete', old.rowid, old.value, old.title, old.copied_from_window_title);
+    INSERT INTO clipboard_history_fts (rowid, value, title, copied_from_window_title)
+    VALUES (new.rowid, new.value, new.title, new.copied_from_window_title);
+END;
+
+INSERT INTO clipboard_history_fts (clipboard_history_fts) VALUES ('rebuild');
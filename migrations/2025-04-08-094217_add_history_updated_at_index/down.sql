@@ -0,0 +1,2 @@
+-- down.sql
+DROP INDEX IF EXISTS idx_history_updated_at;
@@ -0,0 +1 @@
+CREATE INDEX idx_history_updated_at ON clipboard_history (updated_at, history_id);
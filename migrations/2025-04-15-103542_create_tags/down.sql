@@ -0,0 +1,4 @@
+-- down.sql
+DROP TABLE IF EXISTS item_tags;
+DROP TABLE IF EXISTS history_tags;
+DROP TABLE IF EXISTS tags;
@@ -0,0 +1,27 @@
+CREATE TABLE tags (
+    tag_id VARCHAR(50) PRIMARY KEY NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    color VARCHAR(50),
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE UNIQUE INDEX idx_tags_name ON tags (name COLLATE NOCASE);
+
+CREATE TABLE history_tags (
+    history_id VARCHAR(50) NOT NULL,
+    tag_id VARCHAR(50) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (history_id, tag_id)
+);
+
+CREATE INDEX idx_history_tags_tag_id ON history_tags (tag_id);
+
+CREATE TABLE item_tags (
+    item_id VARCHAR(50) NOT NULL,
+    tag_id VARCHAR(50) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (item_id, tag_id)
+);
+
+CREATE INDEX idx_item_tags_tag_id ON item_tags (tag_id);
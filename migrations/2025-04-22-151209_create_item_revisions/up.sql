@@ -0,0 +1,13 @@
+CREATE TABLE item_revisions (
+    revision_id VARCHAR(50) PRIMARY KEY NOT NULL,
+    item_id VARCHAR(50) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    value TEXT,
+    description TEXT,
+    request_options TEXT,
+    form_template_options TEXT,
+    item_options TEXT,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_item_revisions_item_id ON item_revisions (item_id, created_at);
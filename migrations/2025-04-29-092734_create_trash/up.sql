@@ -0,0 +1,10 @@
+CREATE TABLE trash (
+    trash_id VARCHAR(50) PRIMARY KEY NOT NULL,
+    record_type VARCHAR(20) NOT NULL,
+    record_id VARCHAR(50) NOT NULL,
+    title TEXT,
+    data TEXT NOT NULL,
+    deleted_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_trash_deleted_at ON trash (deleted_at);
@@ -0,0 +1,2 @@
+-- Size in bytes of the image file, set when the image is saved so quotas don't read the disk
+ALTER TABLE clipboard_history ADD COLUMN image_size BIGINT;
@@ -1,6 +1,7 @@
 use arboard::{Clipboard, ImageData};
 use base64::{engine::general_purpose, Engine as _};
 use clipboard_master::{CallbackResult, ClipboardHandler, Master};
+use clipboard_rs::{Clipboard as _, ClipboardContent, ClipboardContext, ContentFormat};
 use image::GenericImageView;
 use image::{ImageBuffer, RgbaImage};
 use std::borrow::Cow;
@@ -11,6 +12,7 @@ use std::{
   sync::{Arc, Mutex},
 };
 use tauri::{self};
+use url::Url;
 use tauri::{
   plugin::{Builder, TauriPlugin},
   Manager, Runtime,
@@ -22,11 +24,18 @@ use clipboard_win::{formats, get_clipboard};
 
 use active_win_pos_rs::get_active_window;
 
-use crate::cron_jobs;
 use crate::models::Setting;
-use crate::services::history_service;
+use crate::services::history_dedupe::DedupeOptions;
+use crate::services::history_quota_service;
+use crate::services::image_storage::ImageStorageOptions;
+use crate::services::history_service::{self, HistoryCaptureOutcome};
+use crate::services::secret_detectors::{get_enabled_secret_detectors, SecretDetector};
+use crate::services::url_cleaner::UrlCleanerOptions;
 use crate::services::utils::debug_output;
 
+#[cfg(target_os = "linux")]
+mod primary_selection;
+
 #[derive(Debug)]
 pub struct LanguageDetectOptions {
   pub should_detect_language: bool,
@@ -34,6 +43,74 @@ pub struct LanguageDetectOptions {
   pub enabled_languages: Vec<String>,
   pub prioritized_languages: Vec<String>,
   pub auto_mask_words_list: Vec<String>,
+  pub secret_detectors: Vec<SecretDetector>,
+  pub secrets_expire_after_minutes: Option<i64>,
+  pub dedupe_options: DedupeOptions,
+  pub url_cleaner_options: UrlCleanerOptions,
+}
+
+/// Bits a 64 bit perceptual hash may differ by for two images to count as the same,
+/// 0 only merges identical hashes
+const DEFAULT_IMAGE_NEAR_DUPLICATE_MAX_DISTANCE: i32 = 3;
+
+pub const SOURCE_SELECTION_CLIPBOARD: &str = "clipboard";
+pub const SOURCE_SELECTION_PRIMARY: &str = "primary";
+
+/// Where a clipboard change came from, used for rule matching and stored with the history entry
+#[derive(Debug, Default, Clone)]
+pub struct CaptureSource {
+  pub app_name: Option<String>,
+  pub window_title: Option<String>,
+  pub process_path: Option<String>,
+  /// `clipboard` or `primary` (Linux selection) for text captures
+  pub selection: Option<String>,
+  /// Whether the window title and process path are saved with the entry. They are always
+  /// available to capture rules.
+  pub is_window_context_saved: bool,
+}
+
+impl CaptureSource {
+  /// Reads the focused window, falls back to a source without app details when it is unavailable
+  pub fn from_active_window(selection: &str, settings_map: &HashMap<String, Setting>) -> Self {
+    let is_window_context_saved = settings_map
+      .get("isHistorySourceWindowCaptureEnabled")
+      .and_then(|s| s.value_bool)
+      .unwrap_or(true);
+
+    match get_active_window() {
+      Ok(active_window) => CaptureSource {
+        app_name: Some(active_window.app_name),
+        window_title: Some(active_window.title).filter(|title| !title.trim().is_empty()),
+        process_path: active_window
+          .process_path
+          .to_str()
+          .map(String::from)
+          .filter(|path| !path.is_empty()),
+        selection: Some(selection.to_string()),
+        is_window_context_saved,
+      },
+      Err(()) => CaptureSource {
+        selection: Some(selection.to_string()),
+        is_window_context_saved,
+        ..CaptureSource::default()
+      },
+    }
+  }
+
+  /// Window title and process path to store with a new history entry
+  pub fn stored_window_context(&self) -> (Option<String>, Option<String>) {
+    if !self.is_window_context_saved {
+      return (None, None);
+    }
+
+    (self.window_title.clone(), self.process_path.clone())
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct RichTextFormats {
+  pub html: Option<String>,
+  pub rtf: Option<String>,
 }
 
 struct ClipboardMonitor<R>
@@ -83,157 +160,54 @@ where
 
     let clipboard_text = clipboard_manager.read_text();
 
-    history_service::increment_history_insert_count();
-
-    let current_count = *history_service::HISTORY_INSERT_COUNT.lock().unwrap();
-
-    if current_count >= 200 {
-      history_service::reset_history_insert_count();
-      cron_jobs::run_pending_jobs();
-    }
-
-    let mut do_refresh_clipboard: Option<String> = None;
+    let mut capture_outcome: Option<HistoryCaptureOutcome> = None;
 
     let should_auto_star_on_double_copy = settings_map
       .get("isAutoFavoriteOnDoubleCopyEnabled")
       .and_then(|s| s.value_bool)
       .unwrap_or(true);
 
-    let copied_from_app = match get_active_window() {
-      Ok(active_window) => Some(active_window.app_name),
-      Err(()) => None,
-    };
+    let capture_source =
+      CaptureSource::from_active_window(SOURCE_SELECTION_CLIPBOARD, &settings_map);
+
+    let clipboard_files = clipboard_manager.read_files().unwrap_or_default();
 
-    if let Ok(mut text) = clipboard_text {
-      let trim_text_history = settings_map
-        .get("isHistoryAutoTrimOnCaputureEnabled")
+    if !clipboard_files.is_empty() {
+      let is_file_capture_disabled = settings_map
+        .get("isFileCaptureDisabled")
         .and_then(|s| s.value_bool)
-        .unwrap_or(true);
+        .unwrap_or(false);
 
-      if trim_text_history {
-        text = text.trim().to_string();
+      if is_file_capture_disabled {
+        debug_output(|| {
+          println!("File capturing is disabled, skipping file list capture!");
+        });
+        return CallbackResult::Next;
       }
 
-      if !text.is_empty() {
-        let mut is_excluded = false;
-
-        let text_min_length = settings_map
-          .get("clipTextMinLength")
-          .and_then(|s| s.value_int)
-          .unwrap_or(0) as usize;
-
-        let text_max_length = settings_map
-          .get("clipTextMaxLength")
-          .and_then(|s| s.value_int)
-          .unwrap_or(5000) as usize;
-
-        if text.len() < text_min_length || (text.len() > text_max_length && text_max_length > 0) {
-          is_excluded = true;
-        }
-
-        if !is_excluded {
-          if let Some(setting) = settings_map.get("isExclusionListEnabled") {
-            if let Some(value_bool) = setting.value_bool {
-              if value_bool {
-                let exclusion_list: Vec<String> = settings_map
-                  .get("historyExclusionList")
-                  .and_then(|s| s.value_text.as_ref())
-                  .map_or(Vec::new(), |exclusion_list_text| {
-                    exclusion_list_text.lines().map(String::from).collect()
-                  });
-
-                is_excluded = text.lines().any(|line| {
-                  exclusion_list
-                    .iter()
-                    .any(|item| line.to_lowercase().contains(&item.to_lowercase()))
-                });
-              }
-            }
-          }
-        }
-
-        if !is_excluded {
-          if let Some(setting) = settings_map.get("isExclusionAppListEnabled") {
-            if let Some(value_bool) = setting.value_bool {
-              if value_bool {
-                if let Some(app_name) = &copied_from_app {
-                  let exclusion_app_list: Vec<String> = settings_map
-                    .get("historyExclusionAppList")
-                    .and_then(|s| s.value_text.as_ref())
-                    .map_or(Vec::new(), |exclusion_list_text| {
-                      exclusion_list_text.lines().map(String::from).collect()
-                    });
-
-                  is_excluded |= exclusion_app_list
-                    .iter()
-                    .any(|item| item.to_lowercase() == app_name.to_lowercase());
-                }
-              }
-            }
-          }
-        }
-
-        if !is_excluded {
-          let should_detect_language = settings_map
-            .get("isHistoryDetectLanguageEnabled")
-            .and_then(|s| s.value_bool)
-            .unwrap_or(true);
-
-          let min_lines_required = settings_map
-            .get("historyDetectLanguageMinLines")
-            .and_then(|s| s.value_int)
-            .unwrap_or(3) as usize;
-
-          let enabled_languages: Vec<String> = settings_map
-            .get("historyDetectLanguagesEnabledList")
-            .and_then(|s| s.value_text.as_ref())
-            .map_or(Vec::new(), |langs| {
-              langs.split(',').map(String::from).collect()
-            });
-
-          let prioritized_languages: Vec<String> = settings_map
-            .get("historyDetectLanguagesPrioritizedList")
-            .and_then(|s| s.value_text.as_ref())
-            .map_or(Vec::new(), |langs| {
-              langs.split(',').map(String::from).collect()
-            });
-
-          let auto_mask_words_list = {
-            if let Some(is_enabled) = settings_map
-              .get("isAutoMaskWordsListEnabled")
-              .and_then(|setting| setting.value_bool)
-            {
-              if is_enabled {
-                settings_map
-                  .get("autoMaskWordsList")
-                  .and_then(|setting| setting.value_text.as_ref())
-                  .map_or(Vec::new(), |exclusion_list_text| {
-                    exclusion_list_text.lines().map(String::from).collect()
-                  })
-              } else {
-                Vec::new()
-              }
-            } else {
-              Vec::new()
-            }
-          };
-
-          let detect_options = LanguageDetectOptions {
-            should_detect_language,
-            min_lines_required,
-            enabled_languages,
-            prioritized_languages,
-            auto_mask_words_list,
-          };
-
-          do_refresh_clipboard = Some(history_service::add_clipboard_history_from_text(
-            text,
-            detect_options,
-            should_auto_star_on_double_copy,
-            copied_from_app,
-          ));
-        }
+      if !is_app_excluded(&settings_map, &capture_source.app_name) {
+        capture_outcome = Some(history_service::add_clipboard_history_from_files(
+          clipboard_files,
+          should_auto_star_on_double_copy,
+          DedupeOptions::from_settings(&settings_map).policy,
+          capture_source,
+        ));
+      } else {
+        capture_outcome = Some(HistoryCaptureOutcome::excluded(
+          history_service::CAPTURE_REASON_EXCLUDED_APP,
+        ));
       }
+    } else if let Ok(text) = clipboard_text {
+      capture_outcome = Some(add_text_to_history(
+        &settings_map,
+        text,
+        || RichTextFormats {
+          html: clipboard_manager.read_html().ok(),
+          rtf: clipboard_manager.read_rtf().ok(),
+        },
+        should_auto_star_on_double_copy,
+        capture_source,
+      ));
     } else {
       // Check if image capturing is disabled first (before accessing clipboard)
       let is_image_capture_disabled = settings_map
@@ -250,44 +224,34 @@ where
 
       // Only try to get image from clipboard if capture is enabled
       if let Ok(image_binary) = clipboard_manager.get_image_binary() {
-        let mut is_app_excluded = false;
-
-        if let Some(setting) = settings_map.get("isExclusionAppListEnabled") {
-          if let Some(value_bool) = setting.value_bool {
-            if value_bool {
-              if let Some(app_name) = &copied_from_app {
-                let exclusion_app_list: Vec<String> = settings_map
-                  .get("historyExclusionAppList")
-                  .and_then(|s| s.value_text.as_ref())
-                  .map_or(Vec::new(), |exclusion_list_text| {
-                    exclusion_list_text.lines().map(String::from).collect()
-                  });
-
-                is_app_excluded |= exclusion_app_list
-                  .iter()
-                  .any(|item| item.to_lowercase() == app_name.to_lowercase());
-              }
-            }
-          }
-        }
+        if !is_app_excluded(&settings_map, &capture_source.app_name) {
+          let near_duplicate_max_distance = settings_map
+            .get("imageNearDuplicateMaxDistance")
+            .and_then(|s| s.value_int)
+            .unwrap_or(DEFAULT_IMAGE_NEAR_DUPLICATE_MAX_DISTANCE)
+            .clamp(0, 64) as u32;
 
-        if !is_app_excluded {
-          do_refresh_clipboard = Some(history_service::add_clipboard_history_from_image(
+          capture_outcome = Some(history_service::add_clipboard_history_from_image(
             image_binary,
             should_auto_star_on_double_copy,
-            copied_from_app,
+            near_duplicate_max_distance,
+            ImageStorageOptions::from_settings(&settings_map),
+            capture_source,
+          ));
+        } else {
+          capture_outcome = Some(HistoryCaptureOutcome::excluded(
+            history_service::CAPTURE_REASON_EXCLUDED_APP,
           ));
         }
       }
     }
 
-    if let Some(refresh_value) = &do_refresh_clipboard {
-      if refresh_value == "ok" {
-        let _ = self.app_handle.emit_all(
-          "clipboard://clipboard-monitor/update",
-          format!("clipboard update"),
-        );
+    if let Some(outcome) = &capture_outcome {
+      if outcome.is_history_changed() {
+        history_quota_service::enforce_history_quotas_and_notify(&self.app_handle, &settings_map);
       }
+
+      emit_capture_outcome(&self.app_handle, outcome);
     }
 
     CallbackResult::Next
@@ -303,6 +267,182 @@ where
   }
 }
 
+/// Emits the outcome of a capture as `clipboard://clipboard-monitor/captured`, and the
+/// `clipboard update` event for listeners which refetch history when it changed
+pub(crate) fn emit_capture_outcome<R: Runtime>(
+  app_handle: &tauri::AppHandle<R>,
+  outcome: &HistoryCaptureOutcome,
+) {
+  let _ = app_handle.emit_all("clipboard://clipboard-monitor/captured", outcome);
+
+  if outcome.is_history_changed() {
+    let _ = app_handle.emit_all(
+      "clipboard://clipboard-monitor/update",
+      format!("clipboard update"),
+    );
+  }
+}
+
+/// Applies the text capture settings (trim, length limits, exclusion lists) and stores the text
+/// in history. Shared by the clipboard monitor and the Linux primary selection monitor.
+pub(crate) fn add_text_to_history(
+  settings_map: &HashMap<String, Setting>,
+  mut text: String,
+  read_rich_text_formats: impl FnOnce() -> RichTextFormats,
+  should_auto_star_on_double_copy: bool,
+  capture_source: CaptureSource,
+) -> HistoryCaptureOutcome {
+  let trim_text_history = settings_map
+    .get("isHistoryAutoTrimOnCaputureEnabled")
+    .and_then(|s| s.value_bool)
+    .unwrap_or(true);
+
+  if trim_text_history {
+    text = text.trim().to_string();
+  }
+
+  let mut excluded_reason = history_service::CAPTURE_REASON_EMPTY;
+
+  if !text.is_empty() {
+    let mut is_excluded = false;
+
+    let text_min_length = settings_map
+      .get("clipTextMinLength")
+      .and_then(|s| s.value_int)
+      .unwrap_or(0) as usize;
+
+    let text_max_length = settings_map
+      .get("clipTextMaxLength")
+      .and_then(|s| s.value_int)
+      .unwrap_or(5000) as usize;
+
+    if text.len() < text_min_length || (text.len() > text_max_length && text_max_length > 0) {
+      is_excluded = true;
+      excluded_reason = history_service::CAPTURE_REASON_TEXT_LENGTH;
+    }
+
+    if !is_excluded {
+      if let Some(setting) = settings_map.get("isExclusionListEnabled") {
+        if let Some(value_bool) = setting.value_bool {
+          if value_bool {
+            let exclusion_list: Vec<String> = settings_map
+              .get("historyExclusionList")
+              .and_then(|s| s.value_text.as_ref())
+              .map_or(Vec::new(), |exclusion_list_text| {
+                exclusion_list_text.lines().map(String::from).collect()
+              });
+
+            is_excluded = text.lines().any(|line| {
+              exclusion_list
+                .iter()
+                .any(|item| line.to_lowercase().contains(&item.to_lowercase()))
+            });
+            if is_excluded {
+              excluded_reason = history_service::CAPTURE_REASON_EXCLUSION_LIST;
+            }
+          }
+        }
+      }
+    }
+
+    if !is_excluded && is_app_excluded(settings_map, &capture_source.app_name) {
+      is_excluded = true;
+      excluded_reason = history_service::CAPTURE_REASON_EXCLUDED_APP;
+    }
+
+    if !is_excluded {
+      let should_detect_language = settings_map
+        .get("isHistoryDetectLanguageEnabled")
+        .and_then(|s| s.value_bool)
+        .unwrap_or(true);
+
+      let min_lines_required = settings_map
+        .get("historyDetectLanguageMinLines")
+        .and_then(|s| s.value_int)
+        .unwrap_or(3) as usize;
+
+      let enabled_languages: Vec<String> = settings_map
+        .get("historyDetectLanguagesEnabledList")
+        .and_then(|s| s.value_text.as_ref())
+        .map_or(Vec::new(), |langs| {
+          langs.split(',').map(String::from).collect()
+        });
+
+      let prioritized_languages: Vec<String> = settings_map
+        .get("historyDetectLanguagesPrioritizedList")
+        .and_then(|s| s.value_text.as_ref())
+        .map_or(Vec::new(), |langs| {
+          langs.split(',').map(String::from).collect()
+        });
+
+      let auto_mask_words_list = {
+        if let Some(is_enabled) = settings_map
+          .get("isAutoMaskWordsListEnabled")
+          .and_then(|setting| setting.value_bool)
+        {
+          if is_enabled {
+            settings_map
+              .get("autoMaskWordsList")
+              .and_then(|setting| setting.value_text.as_ref())
+              .map_or(Vec::new(), |exclusion_list_text| {
+                exclusion_list_text.lines().map(String::from).collect()
+              })
+          } else {
+            Vec::new()
+          }
+        } else {
+          Vec::new()
+        }
+      };
+
+      let detect_options = LanguageDetectOptions {
+        should_detect_language,
+        min_lines_required,
+        enabled_languages,
+        prioritized_languages,
+        auto_mask_words_list,
+        secret_detectors: get_enabled_secret_detectors(settings_map),
+        secrets_expire_after_minutes: settings_map
+          .get("secretsExpireAfterMinutes")
+          .and_then(|s| s.value_int)
+          .filter(|minutes| *minutes > 0)
+          .map(|minutes| minutes as i64),
+        dedupe_options: DedupeOptions::from_settings(settings_map),
+        url_cleaner_options: UrlCleanerOptions::from_settings(settings_map),
+      };
+
+      return history_service::add_clipboard_history_from_text(
+        text,
+        read_rich_text_formats(),
+        detect_options,
+        should_auto_star_on_double_copy,
+        capture_source,
+      );
+    }
+  }
+
+  HistoryCaptureOutcome::excluded(excluded_reason)
+}
+
+fn is_app_excluded(settings_map: &HashMap<String, Setting>, app_name: &Option<String>) -> bool {
+  let is_enabled = settings_map
+    .get("isExclusionAppListEnabled")
+    .and_then(|s| s.value_bool)
+    .unwrap_or(false);
+
+  match app_name {
+    Some(app_name) if is_enabled => settings_map
+      .get("historyExclusionAppList")
+      .and_then(|s| s.value_text.as_ref())
+      .map_or(false, |exclusion_list_text| {
+        exclusion_list_text
+          .lines()
+          .any(|item| item.to_lowercase() == app_name.to_lowercase())
+      }),
+    _ => false,
+  }
+}
+
 #[derive(Default)]
 pub struct ClipboardManager {
   terminate_flag: Arc<Mutex<bool>>,
@@ -320,6 +460,71 @@ impl ClipboardManager {
     clipboard.set_text(text).map_err(|err| err.to_string())
   }
 
+  /// Returns local paths of files copied in a file manager (`text/uri-list` / CF_HDROP)
+  pub fn read_files(&self) -> Result<Vec<String>, String> {
+    let context = ClipboardContext::new().map_err(|err| err.to_string())?;
+    if !context.has(ContentFormat::Files) {
+      return Ok(Vec::new());
+    }
+    let files = context.get_files().map_err(|err| err.to_string())?;
+
+    Ok(
+      files
+        .into_iter()
+        .filter_map(|file| {
+          if file.starts_with("file://") {
+            Url::parse(&file)
+              .ok()
+              .and_then(|url| url.to_file_path().ok())
+              .and_then(|path| path.to_str().map(String::from))
+          } else if file.trim().is_empty() {
+            None
+          } else {
+            Some(file)
+          }
+        })
+        .collect(),
+    )
+  }
+
+  pub fn write_files(&self, files: Vec<String>) -> Result<(), String> {
+    // X11 and Wayland expect a uri-list, other platforms take plain paths
+    #[cfg(target_os = "linux")]
+    let files = files
+      .into_iter()
+      .map(|file| {
+        Url::from_file_path(&file)
+          .map(|url| url.to_string())
+          .unwrap_or(file)
+      })
+      .collect::<Vec<String>>();
+
+    let context = ClipboardContext::new().map_err(|err| err.to_string())?;
+    context.set_files(files).map_err(|err| err.to_string())
+  }
+
+  pub fn read_html(&self) -> Result<String, String> {
+    let context = ClipboardContext::new().map_err(|err| err.to_string())?;
+    if !context.has(ContentFormat::Html) {
+      return Err("No html content in clipboard".to_string());
+    }
+    context
+      .get_html()
+      .map_err(|err| err.to_string())
+      .and_then(|html| non_empty_format(html, "html"))
+  }
+
+  pub fn read_rtf(&self) -> Result<String, String> {
+    let context = ClipboardContext::new().map_err(|err| err.to_string())?;
+    if !context.has(ContentFormat::Rtf) {
+      return Err("No rtf content in clipboard".to_string());
+    }
+    context
+      .get_rich_text()
+      .map_err(|err| err.to_string())
+      .and_then(|rtf| non_empty_format(rtf, "rtf"))
+  }
+
   // write_image function remains unchanged as it's writing, not reading
   pub fn write_image(&self, base64_image: String) -> Result<(), String> {
     let mut clipboard = Clipboard::new().unwrap();
@@ -450,6 +655,31 @@ impl ClipboardManager {
   }
 }
 
+fn non_empty_format(value: String, format_name: &str) -> Result<String, String> {
+  if value.trim().is_empty() {
+    Err(format!("Empty {} content in clipboard", format_name))
+  } else {
+    Ok(value)
+  }
+}
+
+/// Writes plain text together with any stored html and rtf flavors in a single
+/// clipboard update, so the receiving app can pick the richest format it supports.
+pub fn write_text_with_formats(text: String, formats: RichTextFormats) -> Result<(), String> {
+  let mut contents = vec![ClipboardContent::Text(text)];
+
+  if let Some(html) = formats.html {
+    contents.push(ClipboardContent::Html(html));
+  }
+
+  if let Some(rtf) = formats.rtf {
+    contents.push(ClipboardContent::Rtf(rtf));
+  }
+
+  let context = ClipboardContext::new().map_err(|err| err.to_string())?;
+  context.set(contents).map_err(|err| err.to_string())
+}
+
 // Safe image retrieval function to avoid clipboard corruption
 #[cfg(target_os = "windows")]
 fn get_image_safe() -> Result<ImageData<'static>, String> {
@@ -525,6 +755,10 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
         ))
         .run();
       });
+
+      #[cfg(target_os = "linux")]
+      primary_selection::start_primary_selection_monitor(app.app_handle());
+
       Ok(())
     })
     .build()
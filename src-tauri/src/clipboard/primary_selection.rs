@@ -0,0 +1,157 @@
+use arboard::{Clipboard, GetExtLinux, LinuxClipboardKind};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Manager, Runtime};
+
+use super::{
+  add_text_to_history, emit_capture_outcome, CaptureSource, RichTextFormats,
+  SOURCE_SELECTION_PRIMARY,
+};
+use crate::models::Setting;
+use crate::services::history_quota_service;
+use crate::services::utils::debug_output;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const DEFAULT_DEBOUNCE_MS: i32 = 800;
+const MIN_DEBOUNCE_MS: i32 = 250;
+
+/// Polls the X11/Wayland PRIMARY selection and records a selection once it stopped
+/// changing for the debounce period, so dragging over text only saves the final selection.
+pub fn start_primary_selection_monitor<R: Runtime>(app_handle: tauri::AppHandle<R>) {
+  thread::spawn(move || {
+    let mut clipboard = match Clipboard::new() {
+      Ok(clipboard) => clipboard,
+      Err(e) => {
+        eprintln!("Primary selection monitor could not access the clipboard: {}", e);
+        return;
+      }
+    };
+
+    let mut last_captured: Option<String> = None;
+    let mut pending_selection: Option<(String, Instant)> = None;
+    let mut needs_resync = true;
+
+    loop {
+      thread::sleep(POLL_INTERVAL);
+
+      let (is_capture_enabled, debounce) = {
+        let app_settings = app_handle.state::<Mutex<HashMap<String, Setting>>>();
+        let settings_map = app_settings.lock().unwrap();
+        get_primary_selection_settings(&settings_map)
+      };
+
+      if !is_capture_enabled {
+        pending_selection = None;
+        needs_resync = true;
+        continue;
+      }
+
+      let selection = read_primary_selection(&mut clipboard);
+
+      // Text that was already selected when capturing got enabled is not a new selection
+      if needs_resync {
+        last_captured = selection;
+        needs_resync = false;
+        continue;
+      }
+
+      let selection = match selection {
+        Some(selection) => selection,
+        None => {
+          pending_selection = None;
+          continue;
+        }
+      };
+
+      if last_captured.as_ref() == Some(&selection) {
+        pending_selection = None;
+        continue;
+      }
+
+      match &pending_selection {
+        Some((pending_text, changed_at)) if *pending_text == selection => {
+          if changed_at.elapsed() < debounce {
+            continue;
+          }
+        }
+        _ => {
+          pending_selection = Some((selection, Instant::now()));
+          continue;
+        }
+      }
+
+      pending_selection = None;
+      last_captured = Some(selection.clone());
+
+      // A selection that was also copied is recorded by the clipboard monitor
+      if clipboard.get_text().ok().as_ref() == Some(&selection) {
+        continue;
+      }
+
+      capture_primary_selection(&app_handle, selection);
+    }
+  });
+}
+
+fn get_primary_selection_settings(settings_map: &HashMap<String, Setting>) -> (bool, Duration) {
+  let is_history_enabled = settings_map
+    .get("isHistoryEnabled")
+    .and_then(|s| s.value_bool)
+    .unwrap_or(true);
+
+  let is_primary_selection_enabled = settings_map
+    .get("isPrimarySelectionCaptureEnabled")
+    .and_then(|s| s.value_bool)
+    .unwrap_or(false);
+
+  let debounce_ms = settings_map
+    .get("primarySelectionDebounceMs")
+    .and_then(|s| s.value_int)
+    .unwrap_or(DEFAULT_DEBOUNCE_MS)
+    .max(MIN_DEBOUNCE_MS);
+
+  (
+    is_history_enabled && is_primary_selection_enabled,
+    Duration::from_millis(debounce_ms as u64),
+  )
+}
+
+fn read_primary_selection(clipboard: &mut Clipboard) -> Option<String> {
+  clipboard
+    .get()
+    .clipboard(LinuxClipboardKind::Primary)
+    .text()
+    .ok()
+    .filter(|text| !text.trim().is_empty())
+}
+
+fn capture_primary_selection<R: Runtime>(app_handle: &tauri::AppHandle<R>, selection: String) {
+  let result = {
+    let app_settings = app_handle.state::<Mutex<HashMap<String, Setting>>>();
+    let settings_map = app_settings.lock().unwrap();
+    let capture_source = CaptureSource::from_active_window(SOURCE_SELECTION_PRIMARY, &settings_map);
+
+    // Selecting the same text twice is not a deliberate double copy, never auto-star it
+    let result = add_text_to_history(
+      &settings_map,
+      selection,
+      RichTextFormats::default,
+      false,
+      capture_source,
+    );
+
+    if result.is_history_changed() {
+      history_quota_service::enforce_history_quotas_and_notify(app_handle, &settings_map);
+    }
+
+    result
+  };
+
+  debug_output(|| {
+    println!("Primary selection captured: {:?}", result);
+  });
+
+  emit_capture_outcome(app_handle, &result);
+}
@@ -0,0 +1,54 @@
+use crate::models::models::CaptureRule;
+use crate::services::capture_rules_service::{self, CreateCaptureRule};
+use chrono::Utc;
+use nanoid::nanoid;
+
+#[tauri::command]
+pub fn get_capture_rules() -> Result<Vec<CaptureRule>, String> {
+  capture_rules_service::get_capture_rules()
+    .map_err(|e| format!("Failed to load capture rules: {}", e))
+}
+
+#[tauri::command]
+pub fn create_capture_rule(rule: CreateCaptureRule) -> Result<String, String> {
+  let new_rule = CaptureRule {
+    rule_id: nanoid!().to_string(),
+    name: rule.name,
+    is_enabled: rule.is_enabled.unwrap_or(true),
+    order_number: rule.order_number.unwrap_or(0),
+    match_content_regex: rule.match_content_regex,
+    match_app: rule.match_app,
+    match_window_title: rule.match_window_title,
+    match_language: rule.match_language,
+    match_content_type: rule.match_content_type,
+    action: rule.action,
+    action_value: rule.action_value,
+    created_at: Utc::now().timestamp_millis(),
+    updated_at: Utc::now().timestamp_millis(),
+  };
+
+  capture_rules_service::validate_capture_rule(&new_rule)?;
+
+  capture_rules_service::create_capture_rule(&new_rule)
+    .map_err(|e| format!("Failed to create capture rule: {}", e))
+}
+
+#[tauri::command]
+pub fn update_capture_rule(rule: CaptureRule) -> Result<String, String> {
+  capture_rules_service::validate_capture_rule(&rule)?;
+
+  capture_rules_service::update_capture_rule(&rule)
+    .map_err(|e| format!("Failed to update capture rule: {}", e))
+}
+
+#[tauri::command]
+pub fn update_capture_rules_order(rule_ids: Vec<String>) -> Result<String, String> {
+  capture_rules_service::update_capture_rules_order(&rule_ids)
+    .map_err(|e| format!("Failed to reorder capture rules: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_capture_rule_by_id(rule_id: String) -> Result<String, String> {
+  capture_rules_service::delete_capture_rule_by_id(&rule_id)
+    .map_err(|e| format!("Failed to delete capture rule: {}", e))
+}
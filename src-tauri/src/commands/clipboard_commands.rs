@@ -1,5 +1,7 @@
+use crate::clipboard::{write_text_with_formats, RichTextFormats};
 use crate::models::models::UpdatedItemData;
 use crate::services::history_service;
+use crate::services::image_storage;
 
 use crate::models::Setting;
 use crate::services::items_service::update_item_by_id;
@@ -9,6 +11,7 @@ use crate::services::request_service::{
 use crate::services::shell_service::{
   run_shell_command, ExecHomeDir, OutputRegexFilter, OutputTemplate,
 };
+use crate::services::usage_service;
 use crate::services::utils::{
   apply_global_templates, ensure_url_or_email_prefix, ensure_url_prefix, mask_value,
   remove_special_bbcode_tags,
@@ -16,7 +19,7 @@ use crate::services::utils::{
 use crate::{constants, services::items_service::get_item_by_id};
 use arboard::{Clipboard, ImageData};
 use constants::IMAGE_NOT_FOUND_BASE64;
-use image::GenericImageView;
+use image::{DynamicImage, GenericImageView};
 use inputbot::KeybdKey::*;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -125,7 +128,19 @@ pub fn copy_paste(app_handle: AppHandle, text: String, delay: i32) -> String {
 
 #[tauri::command]
 pub fn copy_history_item(app_handle: AppHandle, history_id: String) -> String {
-  let history_item = match history_service::get_clipboard_history_by_id(&history_id) {
+  let result = write_history_item_to_clipboard(app_handle, &history_id);
+
+  if result == "ok" {
+    if let Err(e) = usage_service::record_history_usage(&history_id) {
+      eprintln!("Failed to record history item usage: {}", e);
+    }
+  }
+
+  result
+}
+
+fn write_history_item_to_clipboard(app_handle: AppHandle, history_id: &str) -> String {
+  let history_item = match history_service::get_clipboard_history_by_id(history_id) {
     Some(item) => item,
     None => return "History item not found".to_string(),
   };
@@ -133,34 +148,58 @@ pub fn copy_history_item(app_handle: AppHandle, history_id: String) -> String {
   let mut manager = app_handle.clipboard_manager();
 
   if let (Some(true), Some(false)) = (history_item.is_image, history_item.is_link) {
-    let base64_image = match history_item.image_path_full_res {
-      Some(path) => {
-        // Convert relative path to absolute path
-        let absolute_path = crate::db::to_absolute_image_path(&path);
-        match std::fs::read(&absolute_path) {
-          Ok(img_data) => base64::encode(&img_data),
-          Err(e) => {
-            eprintln!("Failed to read image from path: {}", e);
-            IMAGE_NOT_FOUND_BASE64.to_string()
-          }
-        }
+    let write_result = match image_storage::read_history_image(&history_item) {
+      Some((image_bytes, image_format)) => image_storage::decode_image(&image_bytes, image_format)
+        .and_then(write_dynamic_image_to_clipboard),
+      None => {
+        eprintln!("Failed to read image for history item {}", history_id);
+        write_image_to_clipboard(IMAGE_NOT_FOUND_BASE64.to_string())
       }
-      None => IMAGE_NOT_FOUND_BASE64.to_string(),
     };
 
-    match write_image_to_clipboard(base64_image) {
+    match write_result {
       Ok(_) => "ok".to_string(),
       Err(e) => {
         eprintln!("Failed to write image to clipboard: {}", e);
         "Failed to write image to clipboard".to_string()
       }
     }
+  } else if let Some(true) = history_item.is_file {
+    let files: Vec<String> = match history_service::get_clipboard_history_files(history_id) {
+      Ok(files) => files.into_iter().map(|file| file.file_path).collect(),
+      Err(e) => {
+        eprintln!("Failed to load history files: {}", e);
+        return "History item files are missing".to_string();
+      }
+    };
+
+    match crate::clipboard::ClipboardManager::default().write_files(files) {
+      Ok(_) => "ok".to_string(),
+      Err(e) => {
+        eprintln!("Failed to write files to clipboard: {}", e);
+        "Failed to write files to clipboard".to_string()
+      }
+    }
   } else {
     let value = match history_item.value {
       Some(val) => val,
       None => return "History item value is missing".to_string(),
     };
 
+    if history_item.value_html.is_some() || history_item.value_rtf.is_some() {
+      let formats = RichTextFormats {
+        html: history_item.value_html,
+        rtf: history_item.value_rtf,
+      };
+
+      match write_text_with_formats(value.clone(), formats) {
+        Ok(_) => return "ok".to_string(),
+        Err(e) => {
+          eprintln!("Failed to write rich text to clipboard: {}", e);
+        }
+      }
+    }
+
     match manager.write_text(value) {
       Ok(_) => "ok".to_string(),
       Err(e) => {
@@ -178,9 +217,13 @@ pub fn copy_paste_history_item(app_handle: AppHandle, history_id: String, delay:
 }
 
 pub fn write_image_to_clipboard(base64_image: String) -> Result<(), String> {
-  let mut clipboard = Clipboard::new().unwrap();
   let decoded = base64::decode(&base64_image).map_err(|err| err.to_string())?;
   let img = image::load_from_memory(&decoded).map_err(|err| err.to_string())?;
+  write_dynamic_image_to_clipboard(img)
+}
+
+pub fn write_dynamic_image_to_clipboard(img: DynamicImage) -> Result<(), String> {
+  let mut clipboard = Clipboard::new().map_err(|err| err.to_string())?;
   let pixels = img
     .pixels()
     .into_iter()
@@ -203,6 +246,22 @@ pub async fn copy_clip_item(
   app_handle: AppHandle,
   item_id: String,
   copy_from_menu: bool,
+) -> String {
+  let result = write_clip_item_to_clipboard(app_handle, item_id.clone(), copy_from_menu).await;
+
+  if result == "ok" || result == "link_or_app" {
+    if let Err(e) = usage_service::record_item_usage(&item_id) {
+      eprintln!("Failed to record clip usage: {}", e);
+    }
+  }
+
+  result
+}
+
+async fn write_clip_item_to_clipboard(
+  app_handle: AppHandle,
+  item_id: String,
+  copy_from_menu: bool,
 ) -> String {
   // Fetch the item from the database
   let item = match get_item_by_id(item_id.clone()) {
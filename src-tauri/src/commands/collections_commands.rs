@@ -11,6 +11,7 @@ use crate::services::collections_service::{
   UpdatedOnMoveClipData, UpdatedOnMoveMenuData,
 };
 use crate::services::tabs_service;
+use crate::services::trash_service;
 use crate::services::utils::{debug_output, pretty_print_struct};
 use nanoid::nanoid;
 
@@ -112,7 +113,10 @@ pub fn delete_collection_by_id(delete_collection: DeleteByCollectionId) -> Strin
 
   let delete_all_items: bool = delete_collection.delete_all_items_in_collection;
 
-  collections_service::delete_collection_by_id(&collection_id_value, delete_all_items)
+  match trash_service::trash_collection_by_id(&collection_id_value, delete_all_items) {
+    Ok(_) => "ok".to_string(),
+    Err(e) => format!("Error moving collection to trash: {}", e),
+  }
 }
 
 #[tauri::command]
@@ -1,6 +1,15 @@
-use crate::models::models::UpdatedHistoryData;
+use crate::models::models::{ClipboardHistoryFile, UpdatedHistoryData, UsageStats};
 use crate::models::{ClipboardHistory, Setting};
-use crate::services::history_service::{self, ClipboardHistoryWithMetaData};
+use crate::services::history_pause_service::{self, HistoryPauseState};
+use crate::services::history_query_service::{self, HistoryQueryError};
+use crate::services::history_quota_service::{self, HistoryEvictionReport, HistoryQuotaOptions};
+use crate::services::history_search_service::HistorySearchMode;
+use crate::services::history_service::{self, ClipboardHistoryWithMetaData, HistoryCursor};
+use crate::services::history_timeline_service::{self, HistoryTimelineBucket, TimelineGranularity};
+use crate::services::image_storage;
+use crate::services::regex_search_service::{self, RegexSearchResult};
+use crate::services::trash_service;
+use crate::services::usage_service::{self, HistorySortOrder, SORT_BY_RECENT, SORT_BY_RELEVANCE};
 use crate::services::utils::{ensure_url_prefix, is_base64_image, pretty_print_struct};
 use chrono::{Duration, Local};
 use url::Url;
@@ -16,7 +25,9 @@ pub fn get_clipboard_history(
   app_settings: tauri::State<Mutex<HashMap<String, Setting>>>,
   limit: Option<i64>,
   offset: Option<i64>,
-) -> Vec<ClipboardHistoryWithMetaData> {
+  cursor: Option<HistoryCursor>,
+  sort_by: Option<String>,
+) -> Result<Vec<ClipboardHistoryWithMetaData>, String> {
   let mut auto_mask_words_list = Vec::new();
 
   {
@@ -36,8 +47,14 @@ pub fn get_clipboard_history(
     }
   }
 
-  history_service::get_clipboard_histories(limit, offset, auto_mask_words_list)
-    .unwrap_or_else(|_| Vec::new())
+  history_service::get_clipboard_histories(
+    limit,
+    offset,
+    cursor.as_ref(),
+    auto_mask_words_list,
+    HistorySortOrder::from_name(sort_by.as_deref()),
+  )
+  .map_err(|e| format!("Error fetching clipboard history: {}", e))
 }
 
 #[tauri::command]
@@ -47,7 +64,21 @@ pub fn get_clipboard_history_by_id(history_id: String) -> Option<ClipboardHistor
 
 #[tauri::command]
 pub fn delete_clipboard_history_by_ids(history_ids: Vec<String>) -> String {
-  history_service::delete_clipboard_history_by_ids(&history_ids)
+  match trash_service::trash_clipboard_history_by_ids(&history_ids) {
+    Ok(_) => "ok".to_string(),
+    Err(e) => format!("Error moving history items to trash: {}", e),
+  }
+}
+
+/// Searches are sorted by relevance, except pages after a cursor which continue in recency order
+fn search_sort_order(sort_by: Option<&str>, cursor: Option<&HistoryCursor>) -> HistorySortOrder {
+  let default_sort_by = if cursor.is_some() {
+    SORT_BY_RECENT
+  } else {
+    SORT_BY_RELEVANCE
+  };
+
+  HistorySortOrder::from_name(sort_by.or(Some(default_sort_by)))
 }
 
 #[tauri::command]
@@ -56,17 +87,27 @@ pub fn find_clipboard_histories_by_value_or_filters(
   filters: Vec<String>,
   code_filters: Vec<String>,
   app_filters: Vec<String>,
+  window_filters: Option<Vec<String>>,
+  tag_filters: Option<Vec<String>>,
+  cursor: Option<HistoryCursor>,
+  sort_by: Option<String>,
+  search_mode: Option<String>,
   app_settings: tauri::State<Mutex<HashMap<String, Setting>>>,
-) -> Vec<ClipboardHistoryWithMetaData> {
+) -> Result<Vec<ClipboardHistoryWithMetaData>, String> {
   history_service::find_clipboard_histories_by_value_or_filter(
     &query,
     &filters,
     &code_filters,
     &app_filters,
+    &window_filters.unwrap_or_default(),
+    &tag_filters.unwrap_or_default(),
     100,
+    cursor.as_ref(),
+    search_sort_order(sort_by.as_deref(), cursor.as_ref()),
+    HistorySearchMode::from_name(search_mode.as_deref()),
     app_settings,
   )
-  .unwrap_or_else(|_| Vec::new())
+  .map_err(|e| format!("Error searching clipboard history: {}", e))
 }
 
 #[tauri::command]
@@ -77,24 +118,141 @@ pub async fn get_history_items_source_apps() -> Result<Vec<Option<String>>, Stri
   }
 }
 
+#[tauri::command]
+pub async fn get_history_items_source_windows(
+  app_filters: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+  match history_service::get_source_window_titles_list(&app_filters.unwrap_or_default()) {
+    Ok(window_titles) => Ok(window_titles),
+    Err(e) => Err(format!("Error fetching source windows: {}", e)),
+  }
+}
+
+#[tauri::command]
+pub fn clear_history_source_windows() -> Result<usize, String> {
+  history_service::clear_source_window_context().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_history_item_files(history_id: String) -> Result<Vec<ClipboardHistoryFile>, String> {
+  match history_service::get_clipboard_history_files(&history_id) {
+    Ok(files) => Ok(files),
+    Err(e) => Err(format!("Error fetching history files: {}", e)),
+  }
+}
+
 #[tauri::command]
 pub fn search_clipboard_histories_by_value_or_filters(
   query: String,
   filters: Vec<String>,
+  cursor: Option<HistoryCursor>,
+  sort_by: Option<String>,
+  search_mode: Option<String>,
   app_settings: tauri::State<Mutex<HashMap<String, Setting>>>,
-) -> Vec<ClipboardHistoryWithMetaData> {
+) -> Result<Vec<ClipboardHistoryWithMetaData>, String> {
   let code_filters = Vec::new();
   let app_filters = Vec::new();
+  let window_filters = Vec::new();
+  let tag_filters = Vec::new();
 
   history_service::find_clipboard_histories_by_value_or_filter(
     &query,
     &filters,
     &code_filters,
     &app_filters,
+    &window_filters,
+    &tag_filters,
     300,
+    cursor.as_ref(),
+    search_sort_order(sort_by.as_deref(), cursor.as_ref()),
+    HistorySearchMode::from_name(search_mode.as_deref()),
     app_settings,
   )
-  .unwrap_or_else(|_| Vec::new())
+  .map_err(|e| format!("Error searching clipboard history: {}", e))
+}
+
+/// Searches with the query syntax of `history_query_service`, such as
+/// `app:Slack lang:rust is:pinned after:7d "exact phrase" -word`
+#[tauri::command]
+pub fn find_clipboard_histories_by_query(
+  query: String,
+  limit: Option<i64>,
+  cursor: Option<HistoryCursor>,
+  sort_by: Option<String>,
+  app_settings: tauri::State<Mutex<HashMap<String, Setting>>>,
+) -> Result<Vec<ClipboardHistoryWithMetaData>, HistoryQueryError> {
+  let clauses = history_query_service::parse_history_query(&query)?;
+  let mut auto_mask_words_list = Vec::new();
+
+  {
+    let settings_map = app_settings.lock().unwrap();
+
+    if let Some(setting) = settings_map.get("isAutoMaskWordsListEnabled") {
+      if let Some(value_bool) = setting.value_bool {
+        if value_bool {
+          auto_mask_words_list = settings_map
+            .get("autoMaskWordsList")
+            .and_then(|s| s.value_text.as_ref())
+            .map_or(Vec::new(), |exclusion_list_text| {
+              exclusion_list_text.lines().map(String::from).collect()
+            });
+        }
+      }
+    }
+  }
+
+  history_service::find_clipboard_histories_by_query(
+    &clauses,
+    limit.unwrap_or(300),
+    cursor.as_ref(),
+    HistorySortOrder::from_name(sort_by.as_deref()),
+    &auto_mask_words_list,
+  )
+  .map_err(|e| HistoryQueryError::new(format!("Error searching clipboard history: {}", e), None))
+}
+
+/// Searches entry values with a regular expression, returning the matched ranges of each entry
+#[tauri::command]
+pub fn search_clipboard_histories_by_regex(
+  pattern: String,
+  is_case_insensitive: Option<bool>,
+  limit: Option<i64>,
+  cursor: Option<HistoryCursor>,
+  app_settings: tauri::State<Mutex<HashMap<String, Setting>>>,
+) -> Result<RegexSearchResult<ClipboardHistoryWithMetaData>, String> {
+  let regex =
+    regex_search_service::compile_search_regex(&pattern, is_case_insensitive.unwrap_or(false))?;
+  let mut auto_mask_words_list = Vec::new();
+
+  {
+    let settings_map = app_settings.lock().unwrap();
+
+    if let Some(setting) = settings_map.get("isAutoMaskWordsListEnabled") {
+      if let Some(value_bool) = setting.value_bool {
+        if value_bool {
+          auto_mask_words_list = settings_map
+            .get("autoMaskWordsList")
+            .and_then(|s| s.value_text.as_ref())
+            .map_or(Vec::new(), |exclusion_list_text| {
+              exclusion_list_text.lines().map(String::from).collect()
+            });
+        }
+      }
+    }
+  }
+
+  history_service::find_clipboard_histories_by_regex(
+    &regex,
+    limit.unwrap_or(100),
+    cursor.as_ref(),
+    &auto_mask_words_list,
+  )
+  .map_err(|e| format!("Error searching clipboard history: {}", e))
+}
+
+#[tauri::command]
+pub fn get_history_usage_stats(history_ids: Vec<String>) -> Result<Vec<UsageStats>, String> {
+  usage_service::get_usage_stats_by_history_ids(&history_ids).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -138,6 +296,21 @@ pub fn get_clipboard_histories_within_date_range(
     .unwrap_or_else(|_| Vec::new())
 }
 
+/// Entry counts per local day or hour for jumping through the history by date
+#[tauri::command]
+pub fn get_clipboard_history_timeline(
+  granularity: Option<String>,
+  from: Option<i64>,
+  to: Option<i64>,
+) -> Result<Vec<HistoryTimelineBucket>, String> {
+  history_timeline_service::get_history_timeline(
+    TimelineGranularity::from_name(granularity.as_deref()),
+    from,
+    to,
+  )
+  .map_err(|e| format!("Error fetching clipboard history timeline: {}", e))
+}
+
 #[tauri::command]
 pub fn clear_clipboard_history_older_than(
   duration_type: String,
@@ -320,12 +493,19 @@ pub async fn save_to_file_history_item(
           return Err("Provided string is not a valid base64 image data".to_string());
         }
       }
-    } else if let (Some(image_path), Some(true)) =
-      (history_item.image_path_full_res, history_item.is_image)
-    {
-      // Convert relative path to absolute path
-      let absolute_path = crate::db::to_absolute_image_path(&image_path);
-      img_data = Some(std::fs::read(&absolute_path).map_err(|e| e.to_string())?);
+    } else if let (Some(true), false) = (
+      history_item.is_image,
+      history_item.is_link.unwrap_or(false),
+    ) {
+      // Stored images are saved as they are, only the extension follows the stored format
+      let (image_bytes, image_format) = image_storage::read_history_image(&history_item)
+        .ok_or_else(|| "Image file for history item is missing".to_string())?;
+      file_name = format!(
+        "saved_clipboard_image_{}.{}",
+        current_datetime,
+        image_format.extension()
+      );
+      img_data = Some(image_bytes);
     } else if let Some(true) = history_item.is_link {
       if let Some(image_url) = &history_item.value {
         let parsed_url = Url::parse(&ensure_url_prefix(image_url)).map_err(|e| e.to_string())?;
@@ -384,7 +564,10 @@ pub fn count_clipboard_histories() -> i64 {
 
 #[tauri::command]
 pub fn insert_clipboard_history(new_history: ClipboardHistory) -> String {
-  history_service::insert_clipboard_history(&new_history)
+  match history_service::insert_clipboard_history(&new_history) {
+    Ok(_) => "ok".to_string(),
+    Err(e) => format!("Error inserting history: {}", e),
+  }
 }
 
 #[tauri::command]
@@ -408,6 +591,58 @@ pub fn update_pinned_clipboard_history_by_ids(history_ids: Vec<String>, is_pinne
   history_service::update_pinned_clipboard_history_by_ids(&history_ids, is_pinned)
 }
 
+/// Sets entries to expire after the given number of minutes, or clears the expiry when `None`
+#[tauri::command]
+pub fn update_clipboard_history_expiry_by_ids(
+  history_ids: Vec<String>,
+  expire_after_minutes: Option<i64>,
+) -> String {
+  let expires_at = expire_after_minutes
+    .filter(|minutes| *minutes > 0)
+    .map(|minutes| chrono::Utc::now().timestamp_millis() + minutes * 60 * 1000);
+
+  history_service::update_clipboard_history_expiry_by_ids(&history_ids, expires_at)
+}
+
+/// Pauses history capture for `pause_minutes`, or until `until_app_blur` loses focus
+#[tauri::command]
+pub fn pause_history_capture(
+  app_handle: tauri::AppHandle,
+  pause_minutes: Option<i64>,
+  until_app_blur: Option<String>,
+) -> Result<HistoryPauseState, String> {
+  history_pause_service::pause_history_capture(&app_handle, pause_minutes, until_app_blur)
+}
+
+#[tauri::command]
+pub fn resume_history_capture(app_handle: tauri::AppHandle) -> Result<(), String> {
+  history_pause_service::resume_history_capture(
+    &app_handle,
+    history_pause_service::RESUME_REASON_MANUAL,
+  )
+}
+
+#[tauri::command]
+pub fn get_history_pause_state(
+  app_settings: tauri::State<Mutex<HashMap<String, Setting>>>,
+) -> Option<HistoryPauseState> {
+  let settings_map = app_settings.lock().unwrap();
+  history_pause_service::get_history_pause_state(&settings_map)
+}
+
+/// Evicts least recently used entries until the configured storage quotas are met
+#[tauri::command]
+pub fn enforce_history_quotas(
+  app_settings: tauri::State<Mutex<HashMap<String, Setting>>>,
+) -> Result<HistoryEvictionReport, String> {
+  let options = {
+    let settings_map = app_settings.lock().unwrap();
+    HistoryQuotaOptions::from_settings(&settings_map)
+  };
+
+  history_quota_service::enforce_history_quotas(&options).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn unpin_all_clipboard_history_items() -> String {
   history_service::unpin_all_clipboard_history_items()
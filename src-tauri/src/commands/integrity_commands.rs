@@ -0,0 +1,17 @@
+use crate::services::image_gc_service::{self, ImageGcReport, ORPHAN_IMAGE_GRACE_PERIOD};
+use crate::services::integrity_service::{self, IntegrityReport};
+
+/// Reports database corruption, orphaned rows and missing or unused image files. Pass `repair` to
+/// fix them.
+#[tauri::command]
+pub async fn check_database_integrity(repair: Option<bool>) -> Result<IntegrityReport, String> {
+  integrity_service::check_database_integrity(repair.unwrap_or(false))
+    .map_err(|e| format!("Failed to check database integrity: {}", e))
+}
+
+/// Deletes image files nothing uses anymore, files newer than the grace period are kept
+#[tauri::command]
+pub async fn collect_orphan_image_files() -> Result<ImageGcReport, String> {
+  image_gc_service::collect_orphan_image_files(ORPHAN_IMAGE_GRACE_PERIOD)
+    .map_err(|e| format!("Failed to delete orphaned image files: {}", e))
+}
@@ -3,12 +3,16 @@ use std::path::Path;
 use std::sync::Mutex;
 
 use crate::menu::{self, update_system_menu};
-use crate::models::models::UpdatedItemData;
+use crate::models::models::{ItemRevision, UpdatedItemData, UsageStats};
 use crate::models::{Item, Setting};
 
 use crate::services::collections_service::{add_item_to_collection, add_menu_to_collection};
 use crate::services::history_service;
-use crate::services::items_service::{self, CreateItem};
+use crate::services::item_revisions_service::{self, DiffLine};
+use crate::services::items_service::{self, CreateItem, ItemRegexMatch};
+use crate::services::regex_search_service::{self, RegexSearchResult};
+use crate::services::trash_service;
+use crate::services::usage_service;
 use crate::services::utils::{
   ensure_url_prefix, is_base64_image, pretty_print_json, pretty_print_struct,
 };
@@ -32,6 +36,48 @@ pub fn update_item_by_id(updated_item: UpdatedItemData) -> String {
   items_service::update_item_by_id(_item_id_value, updated_item)
 }
 
+#[tauri::command]
+pub fn get_item_usage_stats(item_ids: Vec<String>) -> Result<Vec<UsageStats>, String> {
+  usage_service::get_usage_stats_by_item_ids(&item_ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_item_revisions(item_id: String) -> Result<Vec<ItemRevision>, String> {
+  item_revisions_service::get_item_revisions(&item_id)
+    .map_err(|e| format!("Failed to load item revisions: {}", e))
+}
+
+/// Line diff between two revisions, or between a revision and the current value of the item
+/// when `to_revision_id` is not given
+#[tauri::command]
+pub fn diff_item_revisions(
+  from_revision_id: String,
+  to_revision_id: Option<String>,
+) -> Result<Vec<DiffLine>, String> {
+  item_revisions_service::diff_item_revisions(&from_revision_id, to_revision_id.as_deref())
+    .map_err(|e| format!("Failed to compare item revisions: {}", e))
+}
+
+#[tauri::command]
+pub fn restore_item_revision(revision_id: String) -> Result<String, String> {
+  item_revisions_service::restore_item_revision(&revision_id)
+    .map_err(|e| format!("Failed to restore item revision: {}", e))
+}
+
+/// Searches clip and menu values with a regular expression, returning the matched ranges
+#[tauri::command]
+pub fn search_items_by_regex(
+  pattern: String,
+  is_case_insensitive: Option<bool>,
+  limit: Option<i64>,
+) -> Result<RegexSearchResult<ItemRegexMatch>, String> {
+  let regex =
+    regex_search_service::compile_search_regex(&pattern, is_case_insensitive.unwrap_or(false))?;
+
+  items_service::find_items_by_regex(&regex, limit.unwrap_or(100))
+    .map_err(|e| format!("Error searching items: {}", e))
+}
+
 #[tauri::command]
 pub fn update_items_by_ids(
   app_handle: tauri::AppHandle,
@@ -107,8 +153,11 @@ pub fn update_menu_items_by_ids(
 }
 
 #[tauri::command]
-pub fn delete_items_by_ids(item_ids: Vec<String>, collection_id: String) -> String {
-  items_service::delete_items_by_ids(&item_ids, collection_id)
+pub fn delete_items_by_ids(item_ids: Vec<String>) -> String {
+  match trash_service::trash_items_by_ids(&item_ids) {
+    Ok(_) => "ok".to_string(),
+    Err(e) => format!("Error moving items to trash: {}", e),
+  }
 }
 
 #[tauri::command]
@@ -125,28 +174,18 @@ pub fn update_item_value_by_history_id(history_id: String, item_id: String) -> S
   let history_item = history_service::get_clipboard_history_by_id(&history_id);
 
   if let Some(history_item) = history_item {
-    if history_item.is_image.unwrap_or(false) && history_item.image_path_full_res.is_some() {
-      if let Some(history_image_path) = history_item.image_path_full_res {
-        let mut image_data_url = None;
-
-        if let Some(_image_data_low_res) = &history_item.image_data_low_res {
-          let base64_encoded: String = general_purpose::STANDARD_NO_PAD.encode(_image_data_low_res);
-          image_data_url = Some(format!("data:image/png;base64,{}", base64_encoded));
-        }
+    if history_item.is_image.unwrap_or(false) {
+      let mut image_data_url = None;
 
-        return match items_service::update_item_image_by_id(
-          &item_id,
-          image_data_url,
-          history_item.image_height,
-          history_item.image_width,
-          history_item.image_preview_height,
-          history_item.image_hash,
-          &history_image_path,
-        ) {
-          Ok(_) => "ok".to_string(),
-          Err(e) => e,
-        };
+      if let Some(_image_data_low_res) = &history_item.image_data_low_res {
+        let base64_encoded: String = general_purpose::STANDARD_NO_PAD.encode(_image_data_low_res);
+        image_data_url = Some(format!("data:image/png;base64,{}", base64_encoded));
       }
+
+      return match items_service::update_item_image_by_id(&item_id, &history_item, image_data_url) {
+        Ok(_) => "ok".to_string(),
+        Err(e) => e,
+      };
     }
 
     let updated_value = history_item.value;
@@ -162,8 +201,11 @@ pub fn add_image_to_item_id(item_id: String, image_path: String) -> Result<Strin
 }
 
 #[tauri::command]
-pub fn delete_item_by_id(item_id: String, collection_id: String) -> String {
-  items_service::delete_item_by_id(item_id, collection_id)
+pub fn delete_item_by_id(item_id: String) -> String {
+  match trash_service::trash_items_by_ids(&[item_id]) {
+    Ok(_) => "ok".to_string(),
+    Err(e) => format!("Error moving item to trash: {}", e),
+  }
 }
 
 #[tauri::command]
@@ -239,7 +281,10 @@ pub fn duplicate_item(
       new_item.created_date = chrono::Utc::now().naive_utc();
       new_item.updated_date = chrono::Utc::now().naive_utc();
 
-      let new_item_id = items_service::create_item(&new_item);
+      let new_item_id = match items_service::create_item(&new_item) {
+        Ok(new_item_id) => new_item_id,
+        Err(e) => return format!("Error creating item: {}", e),
+      };
 
       let _ = add_item_to_collection(
         collection_id,
@@ -277,7 +322,10 @@ pub fn duplicate_menu_item(
       new_item.created_date = chrono::Utc::now().naive_utc();
       new_item.updated_date = chrono::Utc::now().naive_utc();
 
-      let new_item_id = items_service::create_item(&new_item);
+      let new_item_id = match items_service::create_item(&new_item) {
+        Ok(new_item_id) => new_item_id,
+        Err(e) => return format!("Error creating item: {}", e),
+      };
 
       let _ = add_menu_to_collection(collection_id, new_item_id.clone(), parent_id, order_number);
 
@@ -324,6 +372,17 @@ pub fn link_clip_to_menu_item(
 
 #[tauri::command]
 pub fn create_item(item: CreateItem) -> String {
+  match create_item_on_board(item) {
+    Ok(new_item_id) => new_item_id,
+    Err(e) => {
+      eprintln!("{}", e);
+      e
+    }
+  }
+}
+
+/// Creates an item with its image and collection placement, failing when any of them is not saved
+pub fn create_item_on_board(item: CreateItem) -> Result<String, String> {
   let value = match &item.history_id {
     Some(history_id) => {
       if let Some(h_item) = history_service::get_clipboard_history_by_id(history_id) {
@@ -346,14 +405,18 @@ pub fn create_item(item: CreateItem) -> String {
   let mut new_image_hash = None;
   let mut new_image_type = None;
 
-  if item.is_image.unwrap_or(false) && item.image_path_full_res.is_some() {
-    if let Some(history_image_path) = &item.image_path_full_res {
+  if item.is_image.unwrap_or(false) {
+    if let Some(history_item) = item
+      .history_id
+      .as_ref()
+      .and_then(history_service::get_clipboard_history_by_id)
+    {
       new_image_hash = Some(chrono::Utc::now().timestamp_millis().to_string());
-      new_image_type = Some("png".to_string());
-      match items_service::save_item_image_from_history_item(&new_item_id, history_image_path) {
-        Ok(path) => new_item_image_path_full_res = Some(path),
-        Err(e) => println!("Error saving image: {}", e),
-      };
+      let (path, saved_image_type) =
+        items_service::save_item_image_from_history_item(&new_item_id, &history_item)
+          .map_err(|e| format!("Error saving image: {}", e))?;
+      new_item_image_path_full_res = Some(path);
+      new_image_type = Some(saved_image_type);
     }
   }
 
@@ -421,26 +484,30 @@ pub fn create_item(item: CreateItem) -> String {
     item_options: None,
   };
 
-  let new_item_id = items_service::create_item(&new_item);
+  let new_item_id =
+    items_service::create_item(&new_item).map_err(|e| format!("Error creating item: {}", e))?;
 
-  if item.is_menu.unwrap_or(false) {
-    let _ = add_menu_to_collection(
+  let placement = if item.is_menu.unwrap_or(false) {
+    add_menu_to_collection(
       item.collection_id,
       new_item_id.clone(),
       item.parent_id,
       item.order_number,
-    );
-  } else if item.tab_id.is_some() {
-    let _ = add_item_to_collection(
+    )
+  } else if let Some(tab_id) = item.tab_id {
+    add_item_to_collection(
       item.collection_id,
       new_item_id.clone(),
-      item.tab_id.unwrap(),
+      tab_id,
       item.parent_id,
       item.order_number,
-    );
-  }
+    )
+  } else {
+    Ok("ok".to_string())
+  };
+  placement.map_err(|e| format!("Error adding item to collection: {}", e))?;
 
-  new_item_id
+  Ok(new_item_id)
 }
 
 #[tauri::command]
@@ -1,7 +1,10 @@
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Mutex;
 use std::time::Duration;
 
 use crate::models::models::LinkMetadata;
+use crate::models::Setting;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 
@@ -10,6 +13,7 @@ use crate::services::link_metadata_service::{
   delete_link_metadata_by_item_id, insert_or_update_link_metadata, AudioInfo,
 };
 
+use crate::services::url_cleaner::UrlCleanerOptions;
 use crate::services::utils::{debug_output, decode_html_entities, ensure_url_prefix};
 use linkify::{LinkFinder, LinkKind};
 
@@ -247,7 +251,16 @@ pub async fn fetch_link_metadata(
   history_id: Option<String>,
   item_id: Option<String>,
   is_preview_only: Option<bool>,
+  app_settings: tauri::State<'_, Mutex<HashMap<String, Setting>>>,
 ) -> Result<LinkMetadata, String> {
+  // Metadata is fetched and stored for the link without tracking parameters
+  let url = {
+    let settings_map = app_settings.lock().unwrap();
+    UrlCleanerOptions::from_settings(&settings_map)
+      .clean_url(&url)
+      .unwrap_or(url)
+  };
+
   debug_output(|| {
     println!("Fetching metadata for URL: {}", url);
   });
@@ -0,0 +1,78 @@
+use crate::models::models::{HistoryTag, ItemTag, Tag};
+use crate::services::tags_service::{self, CreateTag};
+use chrono::Utc;
+use nanoid::nanoid;
+
+#[tauri::command]
+pub fn get_tags() -> Result<Vec<Tag>, String> {
+  tags_service::get_tags().map_err(|e| format!("Failed to load tags: {}", e))
+}
+
+#[tauri::command]
+pub fn create_tag(tag: CreateTag) -> Result<String, String> {
+  let new_tag = Tag {
+    tag_id: nanoid!().to_string(),
+    name: tag.name.trim().to_string(),
+    color: tag.color,
+    created_at: Utc::now().timestamp_millis(),
+    updated_at: Utc::now().timestamp_millis(),
+  };
+
+  tags_service::validate_tag(&new_tag)?;
+
+  tags_service::create_tag(&new_tag).map_err(|e| format!("Failed to create tag: {}", e))
+}
+
+#[tauri::command]
+pub fn update_tag(tag: Tag) -> Result<String, String> {
+  tags_service::validate_tag(&tag)?;
+
+  tags_service::update_tag(&tag).map_err(|e| format!("Failed to update tag: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_tag_by_id(tag_id: String) -> Result<String, String> {
+  tags_service::delete_tag_by_id(&tag_id).map_err(|e| format!("Failed to delete tag: {}", e))
+}
+
+#[tauri::command]
+pub fn tag_clipboard_history_by_ids(
+  history_ids: Vec<String>,
+  tag_ids: Vec<String>,
+) -> Result<usize, String> {
+  tags_service::tag_history_by_ids(&history_ids, &tag_ids)
+    .map_err(|e| format!("Failed to tag history items: {}", e))
+}
+
+#[tauri::command]
+pub fn untag_clipboard_history_by_ids(
+  history_ids: Vec<String>,
+  tag_ids: Vec<String>,
+) -> Result<usize, String> {
+  tags_service::untag_history_by_ids(&history_ids, &tag_ids)
+    .map_err(|e| format!("Failed to untag history items: {}", e))
+}
+
+#[tauri::command]
+pub fn tag_items_by_ids(item_ids: Vec<String>, tag_ids: Vec<String>) -> Result<usize, String> {
+  tags_service::tag_items_by_ids(&item_ids, &tag_ids)
+    .map_err(|e| format!("Failed to tag items: {}", e))
+}
+
+#[tauri::command]
+pub fn untag_items_by_ids(item_ids: Vec<String>, tag_ids: Vec<String>) -> Result<usize, String> {
+  tags_service::untag_items_by_ids(&item_ids, &tag_ids)
+    .map_err(|e| format!("Failed to untag items: {}", e))
+}
+
+#[tauri::command]
+pub fn get_clipboard_history_tags(history_ids: Vec<String>) -> Result<Vec<HistoryTag>, String> {
+  tags_service::get_tags_by_history_ids(&history_ids)
+    .map_err(|e| format!("Failed to load history tags: {}", e))
+}
+
+#[tauri::command]
+pub fn get_item_tags(item_ids: Vec<String>) -> Result<Vec<ItemTag>, String> {
+  tags_service::get_tags_by_item_ids(&item_ids)
+    .map_err(|e| format!("Failed to load item tags: {}", e))
+}
@@ -0,0 +1,24 @@
+use crate::services::trash_service::{self, TrashListEntry, TrashRestoreReport};
+
+#[tauri::command]
+pub fn get_trash_entries(record_type: Option<String>) -> Result<Vec<TrashListEntry>, String> {
+  trash_service::get_trash_entries(record_type.as_deref())
+    .map_err(|e| format!("Failed to load trash: {}", e))
+}
+
+#[tauri::command]
+pub fn restore_trash_entries(trash_ids: Vec<String>) -> Result<TrashRestoreReport, String> {
+  trash_service::restore_trash_entries_by_ids(&trash_ids)
+    .map_err(|e| format!("Failed to restore from trash: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_trash_entries(trash_ids: Vec<String>) -> Result<usize, String> {
+  trash_service::delete_trash_entries_by_ids(&trash_ids)
+    .map_err(|e| format!("Failed to delete from trash: {}", e))
+}
+
+#[tauri::command]
+pub fn empty_trash() -> Result<usize, String> {
+  trash_service::empty_trash().map_err(|e| format!("Failed to empty trash: {}", e))
+}
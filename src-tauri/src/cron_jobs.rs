@@ -1,10 +1,34 @@
 use crate::commands::history_commands;
+use crate::services::history_pause_service;
+use crate::services::history_quota_service::{self, HistoryQuotaOptions};
+use crate::services::history_service;
+use crate::services::image_gc_service::{self, ORPHAN_IMAGE_GRACE_PERIOD};
+use crate::services::item_revisions_service;
 use crate::services::settings_service::get_all_settings;
+use crate::services::tags_service;
+use crate::services::trash_service::{self, DEFAULT_TRASH_RETENTION_DAYS};
+use crate::services::usage_service;
 use crate::services::utils::debug_output;
+use chrono::Utc;
 use clokwerk::Scheduler;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
 pub static SCHEDULER: once_cell::sync::Lazy<std::sync::Mutex<Scheduler>> =
   once_cell::sync::Lazy::new(|| std::sync::Mutex::new(Scheduler::new()));
 
+// Wakes the expiry scheduler early when a new expiry is set
+static EXPIRY_WAKEUP: once_cell::sync::Lazy<(Mutex<bool>, Condvar)> =
+  once_cell::sync::Lazy::new(|| (Mutex::new(false), Condvar::new()));
+
+const EXPIRY_MAX_WAIT_MS: i64 = 60 * 1000;
+const EXPIRY_MIN_WAIT_MS: i64 = 500;
+
+const HISTORY_PAUSE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+const SCHEDULED_JOBS_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
 pub fn setup_cron_jobs() {
   let mut scheduler = SCHEDULER.lock().unwrap();
 
@@ -13,9 +37,43 @@ pub fn setup_cron_jobs() {
   scheduler
     .every(clokwerk::Interval::Hours(1))
     .run(run_history_cleanup_job);
+
+  scheduler
+    .every(clokwerk::Interval::Hours(6))
+    .run(run_image_gc_job);
+
+  // Scheduled jobs run on time even when nothing is copied, the image GC also runs once at
+  // startup in the background as it walks the image folders
+  std::thread::spawn(|| {
+    run_image_gc_job();
+
+    loop {
+      std::thread::sleep(SCHEDULED_JOBS_CHECK_INTERVAL);
+      run_pending_jobs();
+    }
+  });
+}
+
+fn run_image_gc_job() {
+  match image_gc_service::collect_orphan_image_files(ORPHAN_IMAGE_GRACE_PERIOD) {
+    Ok(report) => debug_output(|| {
+      println!(
+        "Deleted {} orphaned image files, reclaimed {} bytes",
+        report.deleted_count, report.reclaimed_bytes
+      );
+    }),
+    Err(e) => eprintln!("Error deleting orphaned image files: {}", e),
+  }
 }
 
 fn run_history_cleanup_job() {
+  match history_service::delete_expired_clipboard_histories() {
+    Ok(expired_count) => debug_output(|| {
+      println!("Deleted {} expired items from clipboard history", expired_count);
+    }),
+    Err(e) => eprintln!("Error deleting expired clipboard history: {}", e),
+  }
+
   let app_settings = get_all_settings(None).unwrap_or_default(); // Fetch latest settings
   let locked_settings = app_settings.lock().unwrap();
 
@@ -55,9 +113,120 @@ fn run_history_cleanup_job() {
       println!("Deleted {} items from clipboard history", deleted_count);
     });
   }
+
+  let quota_options = HistoryQuotaOptions::from_settings(&locked_settings);
+
+  if quota_options.is_enabled() {
+    match history_quota_service::enforce_history_quotas(&quota_options) {
+      Ok(report) => debug_output(|| {
+        println!(
+          "History quota evicted {} items, freed {} bytes, {} items using {} bytes remain",
+          report.evicted_count, report.freed_bytes, report.remaining_count, report.remaining_bytes
+        );
+      }),
+      Err(e) => eprintln!("Error enforcing history quotas: {}", e),
+    }
+  }
+
+  let trash_retention_days = locked_settings
+    .get("trashRetentionDays")
+    .and_then(|s| s.value_int)
+    .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+
+  match trash_service::delete_expired_trash(trash_retention_days) {
+    Ok(deleted_count) => debug_output(|| {
+      println!("Deleted {} expired entries from trash", deleted_count);
+    }),
+    Err(e) => eprintln!("Error deleting expired trash: {}", e),
+  }
+
+  // Deleted clips and board items are not tracked one by one, their usage is removed here
+  match usage_service::delete_orphan_usage_stats() {
+    Ok(deleted_count) => debug_output(|| {
+      println!("Deleted {} orphaned usage stats", deleted_count);
+    }),
+    Err(e) => eprintln!("Error deleting orphaned usage stats: {}", e),
+  }
+
+  match tags_service::delete_orphan_tag_links() {
+    Ok(deleted_count) => debug_output(|| {
+      println!("Deleted {} orphaned tag links", deleted_count);
+    }),
+    Err(e) => eprintln!("Error deleting orphaned tag links: {}", e),
+  }
+
+  match item_revisions_service::delete_orphan_item_revisions() {
+    Ok(deleted_count) => debug_output(|| {
+      println!("Deleted {} orphaned item revisions", deleted_count);
+    }),
+    Err(e) => eprintln!("Error deleting orphaned item revisions: {}", e),
+  }
 }
 
-pub fn run_pending_jobs() {
+fn run_pending_jobs() {
   let mut scheduler = SCHEDULER.lock().unwrap();
   scheduler.run_pending();
 }
+
+/// Purges expired history entries as soon as they expire instead of waiting for the hourly
+/// cleanup. Sleeps until the nearest `expires_at` and is woken early when a new expiry is set.
+pub fn start_history_expiry_scheduler<R: Runtime>(app_handle: AppHandle<R>) {
+  std::thread::spawn(move || loop {
+    match history_service::delete_expired_clipboard_histories() {
+      Ok(expired_count) if expired_count > 0 => {
+        debug_output(|| {
+          println!("Expired {} items from clipboard history", expired_count);
+        });
+        let _ = app_handle.emit_all(
+          "clipboard://clipboard-monitor/update",
+          format!("clipboard update"),
+        );
+      }
+      Ok(_) => {}
+      Err(e) => eprintln!("Error deleting expired clipboard history: {}", e),
+    }
+
+    let wait_ms = match history_service::get_next_clipboard_history_expiry() {
+      Ok(Some(next_expiry)) => (next_expiry - Utc::now().timestamp_millis())
+        .clamp(EXPIRY_MIN_WAIT_MS, EXPIRY_MAX_WAIT_MS),
+      _ => EXPIRY_MAX_WAIT_MS,
+    };
+
+    let (lock, condvar) = &*EXPIRY_WAKEUP;
+    let mut is_woken = lock.lock().unwrap();
+    if !*is_woken {
+      is_woken = condvar
+        .wait_timeout(is_woken, Duration::from_millis(wait_ms as u64))
+        .unwrap()
+        .0;
+    }
+    *is_woken = false;
+  });
+}
+
+pub fn notify_history_expiry_changed() {
+  let (lock, condvar) = &*EXPIRY_WAKEUP;
+  *lock.lock().unwrap() = true;
+  condvar.notify_one();
+}
+
+/// Resumes paused history capture when the pause ends. Checks every second so an app losing
+/// focus is noticed quickly, and rebuilds the tray menu when the remaining minutes change.
+pub fn start_history_pause_watcher(app_handle: AppHandle) {
+  std::thread::spawn(move || {
+    let mut shown_remaining_minutes: Option<i64> = None;
+
+    loop {
+      let remaining_minutes = history_pause_service::check_history_pause(&app_handle)
+        .and_then(|pause_state| pause_state.remaining_ms)
+        .map(history_pause_service::get_remaining_minutes);
+
+      if remaining_minutes.is_some() && remaining_minutes != shown_remaining_minutes {
+        history_pause_service::refresh_tray_menu(&app_handle);
+      }
+      shown_remaining_minutes = remaining_minutes;
+
+      std::thread::sleep(HISTORY_PAUSE_CHECK_INTERVAL);
+    }
+  });
+}
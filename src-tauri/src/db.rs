@@ -210,6 +210,14 @@ fn run_migrations() {
   connection.run_pending_migrations(MIGRATIONS).unwrap();
 }
 
+/// In-memory database with every migration applied, for tests
+#[cfg(test)]
+pub fn establish_test_connection() -> SqliteConnection {
+  let mut connection = SqliteConnection::establish(":memory:").unwrap();
+  connection.run_pending_migrations(MIGRATIONS).unwrap();
+  connection
+}
+
 fn create_db_file() {
   let db_path = get_db_path();
   let db_dir = Path::new(&db_path).parent().unwrap();
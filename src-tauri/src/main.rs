@@ -12,7 +12,7 @@ use dotenv::dotenv;
 use menu::DbRecentHistoryItems;
 use opener;
 // use schema::clipboard_history::history_id;
-use services::settings_service::insert_or_update_setting_by_name;
+use services::settings_service::{insert_or_update_setting_by_name, SettingUpdatePayload};
 use services::utils;
 use services::utils::debug_output;
 use tokio::time::sleep;
@@ -44,27 +44,34 @@ mod services;
 mod simple_cache;
 
 use crate::commands::clipboard_commands::copy_paste_clip_item_from_menu;
-use crate::commands::clipboard_commands::write_image_to_clipboard;
+use crate::commands::clipboard_commands::{write_dynamic_image_to_clipboard, write_image_to_clipboard};
 use crate::menu::DbItems;
 use crate::models::Setting;
+use crate::services::history_pause_service;
 use crate::services::history_service;
+use crate::services::image_storage;
 use crate::services::settings_service::get_all_settings;
 use crate::services::translations::translations::Translations;
+use crate::services::usage_service;
 use crate::services::utils::remove_special_bbcode_tags;
 use crate::services::utils::{apply_global_templates, ensure_url_or_email_prefix};
 use commands::backup_restore_commands;
+use commands::capture_rules_commands;
 use commands::clipboard_commands;
 use commands::collections_commands;
 use commands::download_update;
 use commands::format_converter_commands;
 use commands::history_commands;
+use commands::integrity_commands;
 use commands::items_commands;
 use commands::link_metadata_commands;
 use commands::request_commands;
 use commands::security_commands;
 use commands::shell_commands;
 use commands::tabs_commands;
+use commands::tags_commands;
 use commands::translations_commands;
+use commands::trash_commands;
 use commands::user_settings_command;
 
 use db::AppConstants;
@@ -111,14 +118,6 @@ struct AppReadyResponse<'a> {
   settings: &'a Mutex<HashMap<String, Setting>>,
 }
 
-#[derive(Clone, serde::Serialize)]
-struct SettingUpdatePayload {
-  name: String,
-  value_bool: Option<bool>,
-  value_string: Option<String>,
-  value_number: Option<i32>,
-}
-
 #[tauri::command]
 async fn quickpaste_hide_paste_close(
   app_handle: tauri::AppHandle,
@@ -719,7 +718,31 @@ async fn main() {
           )
           .unwrap();
         }
+        "pause_history_capture_5m" | "pause_history_capture_1h" => {
+          let pause_minutes = if id == "pause_history_capture_5m" { 5 } else { 60 };
+          if let Err(e) =
+            history_pause_service::pause_history_capture(app, Some(pause_minutes), None)
+          {
+            eprintln!("Error pausing history capture: {}", e);
+          }
+        }
         "enable_history_capture" => {
+          let is_paused = {
+            let app_settings = app.state::<Mutex<HashMap<String, Setting>>>();
+            let settings_map = app_settings.lock().unwrap();
+            history_pause_service::get_history_pause_state(&settings_map).is_some()
+          };
+
+          if is_paused {
+            if let Err(e) = history_pause_service::resume_history_capture(
+              app,
+              history_pause_service::RESUME_REASON_MANUAL,
+            ) {
+              eprintln!("Error resuming history capture: {}", e);
+            }
+            return;
+          }
+
           let w = app.get_window("main").unwrap();
           w.emit(
             "setting:update",
@@ -915,18 +938,18 @@ async fn main() {
                 detailed_history_item.is_image,
                 detailed_history_item.is_link,
               ) {
-                let image_path = match detailed_history_item.image_path_full_res {
-                  Some(path) => path,
-                  None => return (),
-                };
-
-                // Convert relative path to absolute path
-                let absolute_path = db::to_absolute_image_path(&image_path);
-                let img_data =
-                  std::fs::read(&absolute_path).expect("Failed to read image from path");
-                let base64_image = base64::encode(&img_data);
-
-                write_image_to_clipboard(base64_image).expect("Failed to write image to clipboard");
+                let (image_bytes, image_format) =
+                  match image_storage::read_history_image(&detailed_history_item) {
+                    Some(stored_image) => stored_image,
+                    None => return (),
+                  };
+
+                if let Err(e) = image_storage::decode_image(&image_bytes, image_format)
+                  .and_then(write_dynamic_image_to_clipboard)
+                {
+                  eprintln!("Failed to write image to clipboard: {}", e);
+                  return ();
+                }
               } else {
                 let value = match detailed_history_item.value {
                   Some(val) => val,
@@ -939,6 +962,10 @@ async fn main() {
                   .expect("failed to write to clipboard");
               }
 
+              if let Err(e) = usage_service::record_history_usage(&history_item.history_id) {
+                eprintln!("Failed to record history item usage: {}", e);
+              }
+
               #[cfg(target_os = "windows")]
               {
                 thread::sleep(StdDuration::from_secs(3));
@@ -1061,6 +1088,7 @@ async fn main() {
       db::init(app);
       let app_settings = get_all_settings(None).unwrap_or_default();
       cron_jobs::setup_cron_jobs();
+      cron_jobs::start_history_expiry_scheduler(app.handle());
 
       #[cfg(target_os = "macos")]
       {
@@ -1234,6 +1262,9 @@ async fn main() {
         }
       }
 
+      // Started after the tray is built, a pause that ended while the app was closed resumes right away
+      cron_jobs::start_history_pause_watcher(app.handle());
+
       if cfg!(debug_assertions) {
         #[cfg(debug_assertions)]
         {
@@ -1299,6 +1330,11 @@ async fn main() {
       items_commands::duplicate_menu_item,
       items_commands::update_item_by_id,
       items_commands::update_items_by_ids,
+      items_commands::get_item_usage_stats,
+      items_commands::search_items_by_regex,
+      items_commands::get_item_revisions,
+      items_commands::diff_item_revisions,
+      items_commands::restore_item_revision,
       items_commands::update_menu_item_by_id,
       items_commands::update_menu_items_by_ids,
       items_commands::update_item_value_by_history_id,
@@ -1347,6 +1383,7 @@ async fn main() {
       history_commands::find_clipboard_histories_by_value_or_filters,
       history_commands::get_recent_clipboard_histories,
       history_commands::get_clipboard_histories_within_date_range,
+      history_commands::get_clipboard_history_timeline,
       history_commands::clear_clipboard_history_older_than,
       history_commands::clear_recent_clipboard_history,
       history_commands::count_clipboard_histories,
@@ -1355,11 +1392,43 @@ async fn main() {
       history_commands::update_clipboard_history_by_ids,
       history_commands::update_pinned_clipboard_history_by_ids,
       history_commands::unpin_all_clipboard_history_items,
+      history_commands::update_clipboard_history_expiry_by_ids,
+      history_commands::pause_history_capture,
+      history_commands::resume_history_capture,
+      history_commands::get_history_pause_state,
+      history_commands::enforce_history_quotas,
       history_commands::move_pinned_item_up_down,
       history_commands::find_clipboard_history_by_id,
       history_commands::search_clipboard_histories_by_value_or_filters,
       history_commands::save_to_file_history_item,
       history_commands::get_history_items_source_apps,
+      history_commands::get_history_items_source_windows,
+      history_commands::get_history_usage_stats,
+      history_commands::find_clipboard_histories_by_query,
+      history_commands::search_clipboard_histories_by_regex,
+      history_commands::clear_history_source_windows,
+      history_commands::get_history_item_files,
+      capture_rules_commands::get_capture_rules,
+      capture_rules_commands::create_capture_rule,
+      capture_rules_commands::update_capture_rule,
+      capture_rules_commands::update_capture_rules_order,
+      capture_rules_commands::delete_capture_rule_by_id,
+      tags_commands::get_tags,
+      tags_commands::create_tag,
+      tags_commands::update_tag,
+      tags_commands::delete_tag_by_id,
+      tags_commands::tag_clipboard_history_by_ids,
+      tags_commands::untag_clipboard_history_by_ids,
+      tags_commands::tag_items_by_ids,
+      tags_commands::untag_items_by_ids,
+      tags_commands::get_clipboard_history_tags,
+      tags_commands::get_item_tags,
+      trash_commands::get_trash_entries,
+      trash_commands::restore_trash_entries,
+      trash_commands::delete_trash_entries,
+      trash_commands::empty_trash,
+      integrity_commands::check_database_integrity,
+      integrity_commands::collect_orphan_image_files,
       menu::build_system_menu,
       get_device_id,
       shell_commands::check_path,
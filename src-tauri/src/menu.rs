@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use tauri::{CustomMenuItem, SystemTrayMenu, SystemTrayMenuItem, SystemTraySubmenu};
 
 use crate::models::{ClipboardHistory, Setting};
+use crate::services::history_pause_service::{self, HistoryPauseState};
 use crate::services::utils::{debug_output, mask_value};
 use crate::services::{collections_service, history_service};
 
@@ -153,6 +154,8 @@ pub fn build_tray_menu(
     }
   }
 
+  let history_pause_state = history_pause_service::get_history_pause_state(&settings_map);
+
   let mut auto_mask_words_list = Vec::new();
 
   if let Some(setting) = settings_map.get("isAutoMaskWordsListEnabled") {
@@ -175,6 +178,7 @@ pub fn build_tray_menu(
         &tree,
         &db_recent_history_items_result,
         is_history_enabled,
+        &history_pause_state,
         is_app_locked,
         auto_mask_words_list,
       );
@@ -196,6 +200,7 @@ fn build_system_tray_menu(
   tree: &[AssociatedItemTree],
   recent_history: &Vec<ClipboardHistory>,
   is_history_enabled: bool,
+  history_pause_state: &Option<HistoryPauseState>,
   is_app_locked: bool,
   auto_mask_words_list: Vec<String>,
 ) -> SystemTrayMenu {
@@ -219,9 +224,12 @@ fn build_system_tray_menu(
   {
     menu = menu.add_item(menuitem_show);
     if !recent_history.is_empty() {
-      if let Some(history_menu) =
-        add_recent_history_items_to_menu(recent_history, auto_mask_words_list, is_history_enabled)
-      {
+      if let Some(history_menu) = add_recent_history_items_to_menu(
+        recent_history,
+        auto_mask_words_list,
+        is_history_enabled,
+        history_pause_state,
+      ) {
         if is_app_locked {
           menu = menu.add_item(
             CustomMenuItem::new(
@@ -257,9 +265,12 @@ fn build_system_tray_menu(
   #[cfg(target_os = "windows")]
   {
     if !recent_history.is_empty() {
-      if let Some(history_menu) =
-        add_recent_history_items_to_menu(recent_history, auto_mask_words_list, is_history_enabled)
-      {
+      if let Some(history_menu) = add_recent_history_items_to_menu(
+        recent_history,
+        auto_mask_words_list,
+        is_history_enabled,
+        history_pause_state,
+      ) {
         if is_app_locked {
           menu = menu.add_item(
             CustomMenuItem::new(
@@ -288,6 +299,7 @@ fn add_recent_history_items_to_menu(
   recent_history: &[ClipboardHistory],
   auto_mask_words_list: Vec<String>,
   is_history_enabled: bool,
+  history_pause_state: &Option<HistoryPauseState>,
 ) -> Option<SystemTrayMenu> {
   let history_items = create_recent_history_items(recent_history, auto_mask_words_list);
   if !history_items.is_empty() {
@@ -296,7 +308,7 @@ fn add_recent_history_items_to_menu(
     if !is_history_enabled {
       let menu_item = CustomMenuItem::new(
         "history_is_disabled".to_string(),
-        Translations::get("history_capture_is_disabled"),
+        get_history_disabled_label(history_pause_state),
       )
       .disabled();
 
@@ -308,6 +320,18 @@ fn add_recent_history_items_to_menu(
     }
     history_submenu = history_submenu.add_native_item(SystemTrayMenuItem::Separator);
 
+    if is_history_enabled {
+      history_submenu = history_submenu
+        .add_item(CustomMenuItem::new(
+          "pause_history_capture_5m".to_string(),
+          Translations::get("pause_history_capture_for_5_minutes"),
+        ))
+        .add_item(CustomMenuItem::new(
+          "pause_history_capture_1h".to_string(),
+          Translations::get("pause_history_capture_for_1_hour"),
+        ));
+    }
+
     let (item_id, item_label) = if is_history_enabled {
       (
         "disable_history_capture",
@@ -334,6 +358,23 @@ fn add_recent_history_items_to_menu(
   }
 }
 
+fn get_history_disabled_label(history_pause_state: &Option<HistoryPauseState>) -> String {
+  match history_pause_state {
+    Some(HistoryPauseState {
+      remaining_ms: Some(remaining_ms),
+      ..
+    }) => Translations::get("history_capture_paused_minutes_left").replace(
+      "{minutes}",
+      &history_pause_service::get_remaining_minutes(*remaining_ms).to_string(),
+    ),
+    Some(HistoryPauseState {
+      until_app_blur: Some(app_name),
+      ..
+    }) => Translations::get("history_capture_paused_until_app_blur").replace("{app}", app_name),
+    _ => Translations::get("history_capture_is_disabled"),
+  }
+}
+
 fn create_recent_history_items(
   recent_history: &[ClipboardHistory],
   auto_mask_words_list: Vec<String>,
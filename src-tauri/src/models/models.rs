@@ -1,11 +1,19 @@
+use crate::schema::capture_rules;
 use crate::schema::clipboard_history;
+use crate::schema::clipboard_history_files;
 use crate::schema::collection_clips;
 use crate::schema::collection_menu;
 use crate::schema::collections;
+use crate::schema::history_tags;
+use crate::schema::item_revisions;
+use crate::schema::item_tags;
 use crate::schema::items;
 use crate::schema::link_metadata;
 use crate::schema::settings;
 use crate::schema::tabs;
+use crate::schema::tags;
+use crate::schema::trash;
+use crate::schema::usage_stats;
 use chrono::NaiveDateTime;
 
 use diesel::{AsChangeset, Associations, Identifiable, Insertable, Queryable, Selectable};
@@ -233,10 +241,10 @@ pub struct CollectionClips {
 }
 
 #[derive(
-  Queryable, Identifiable, Deserialize, Insertable, Selectable, Debug, PartialEq, Serialize,
+  Queryable, Identifiable, Deserialize, Insertable, Selectable, Debug, Default, PartialEq, Serialize,
 )]
 #[diesel(primary_key(history_id))]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 #[diesel(table_name = clipboard_history)]
 pub struct ClipboardHistory {
   pub history_id: String,
@@ -276,6 +284,35 @@ pub struct ClipboardHistory {
   pub updated_date: NaiveDateTime,
   pub history_options: Option<String>,
   pub copied_from_app: Option<String>,
+  pub value_html: Option<String>,
+  pub value_rtf: Option<String>,
+  pub is_file: Option<bool>,
+  pub expires_at: Option<i64>,
+  pub detected_secrets: Option<String>,
+  pub source_selection: Option<String>,
+  pub duplicate_count: i32,
+  pub image_format: Option<String>,
+  pub copied_from_window_title: Option<String>,
+  pub copied_from_process_path: Option<String>,
+  pub image_size: Option<i64>,
+}
+
+#[derive(
+  Queryable, Identifiable, Deserialize, Insertable, Selectable, Debug, PartialEq, Serialize, Clone,
+)]
+#[diesel(primary_key(file_id))]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = clipboard_history_files)]
+pub struct ClipboardHistoryFile {
+  pub file_id: String,
+  pub history_id: String,
+  pub file_path: String,
+  pub file_name: Option<String>,
+  pub file_size: Option<i64>,
+  pub mime_type: Option<String>,
+  pub is_directory: Option<bool>,
+  pub order_number: i32,
+  pub created_at: i64,
 }
 
 #[derive(
@@ -349,3 +386,126 @@ impl ClipboardHistory {
     }
   }
 }
+
+#[derive(
+  Queryable,
+  Identifiable,
+  AsChangeset,
+  Deserialize,
+  Insertable,
+  Selectable,
+  Debug,
+  PartialEq,
+  Serialize,
+  Clone,
+)]
+#[diesel(primary_key(rule_id))]
+#[diesel(treat_none_as_null = true)]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = capture_rules)]
+pub struct CaptureRule {
+  pub rule_id: String,
+  pub name: String,
+  pub is_enabled: bool,
+  pub order_number: i32,
+  pub match_content_regex: Option<String>,
+  pub match_app: Option<String>,
+  pub match_window_title: Option<String>,
+  pub match_language: Option<String>,
+  pub match_content_type: Option<String>,
+  pub action: String,
+  pub action_value: Option<String>,
+  pub created_at: i64,
+  pub updated_at: i64,
+}
+
+#[derive(
+  Queryable, Identifiable, Insertable, Selectable, Deserialize, Debug, PartialEq, Serialize, Clone,
+)]
+#[diesel(primary_key(usage_id))]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = usage_stats)]
+pub struct UsageStats {
+  pub usage_id: String,
+  pub history_id: Option<String>,
+  pub item_id: Option<String>,
+  pub use_count: i32,
+  pub last_used_at: i64,
+  pub frecency_rank: f64,
+  pub created_at: i64,
+}
+
+#[derive(
+  Queryable,
+  Identifiable,
+  AsChangeset,
+  Deserialize,
+  Insertable,
+  Selectable,
+  Debug,
+  PartialEq,
+  Serialize,
+  Clone,
+)]
+#[diesel(primary_key(tag_id))]
+#[diesel(treat_none_as_null = true)]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = tags)]
+pub struct Tag {
+  pub tag_id: String,
+  pub name: String,
+  pub color: Option<String>,
+  pub created_at: i64,
+  pub updated_at: i64,
+}
+
+#[derive(Queryable, Insertable, Selectable, Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = history_tags)]
+pub struct HistoryTag {
+  pub history_id: String,
+  pub tag_id: String,
+  pub created_at: i64,
+}
+
+#[derive(Queryable, Insertable, Selectable, Deserialize, Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = item_tags)]
+pub struct ItemTag {
+  pub item_id: String,
+  pub tag_id: String,
+  pub created_at: i64,
+}
+
+#[derive(
+  Queryable, Identifiable, Insertable, Selectable, Deserialize, Debug, PartialEq, Serialize, Clone,
+)]
+#[diesel(primary_key(revision_id))]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = item_revisions)]
+pub struct ItemRevision {
+  pub revision_id: String,
+  pub item_id: String,
+  pub name: String,
+  pub value: Option<String>,
+  pub description: Option<String>,
+  pub request_options: Option<String>,
+  pub form_template_options: Option<String>,
+  pub item_options: Option<String>,
+  pub created_at: i64,
+}
+
+#[derive(
+  Queryable, Identifiable, Insertable, Selectable, Deserialize, Debug, PartialEq, Serialize, Clone,
+)]
+#[diesel(primary_key(trash_id))]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = trash)]
+pub struct TrashEntry {
+  pub trash_id: String,
+  pub record_type: String,
+  pub record_id: String,
+  pub title: Option<String>,
+  pub data: String,
+  pub deleted_at: i64,
+}
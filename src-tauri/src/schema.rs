@@ -1,5 +1,23 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    capture_rules (rule_id) {
+        rule_id -> Text,
+        name -> Text,
+        is_enabled -> Bool,
+        order_number -> Integer,
+        match_content_regex -> Nullable<Text>,
+        match_app -> Nullable<Text>,
+        match_window_title -> Nullable<Text>,
+        match_language -> Nullable<Text>,
+        match_content_type -> Nullable<Text>,
+        action -> Text,
+        action_value -> Nullable<Text>,
+        created_at -> BigInt,
+        updated_at -> BigInt,
+    }
+}
+
 diesel::table! {
     clipboard_history (history_id) {
         history_id -> Text,
@@ -36,6 +54,31 @@ diesel::table! {
         updated_date -> Timestamp,
         history_options -> Nullable<Text>,
         copied_from_app -> Nullable<Text>,
+        value_html -> Nullable<Text>,
+        value_rtf -> Nullable<Text>,
+        is_file -> Nullable<Bool>,
+        expires_at -> Nullable<BigInt>,
+        detected_secrets -> Nullable<Text>,
+        source_selection -> Nullable<Text>,
+        duplicate_count -> Integer,
+        image_format -> Nullable<Text>,
+        copied_from_window_title -> Nullable<Text>,
+        copied_from_process_path -> Nullable<Text>,
+        image_size -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    clipboard_history_files (file_id) {
+        file_id -> Text,
+        history_id -> Text,
+        file_path -> Text,
+        file_name -> Nullable<Text>,
+        file_size -> Nullable<BigInt>,
+        mime_type -> Nullable<Text>,
+        is_directory -> Nullable<Bool>,
+        order_number -> Integer,
+        created_at -> BigInt,
     }
 }
 
@@ -73,6 +116,36 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    history_tags (history_id, tag_id) {
+        history_id -> Text,
+        tag_id -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    item_revisions (revision_id) {
+        revision_id -> Text,
+        item_id -> Text,
+        name -> Text,
+        value -> Nullable<Text>,
+        description -> Nullable<Text>,
+        request_options -> Nullable<Text>,
+        form_template_options -> Nullable<Text>,
+        item_options -> Nullable<Text>,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    item_tags (item_id, tag_id) {
+        item_id -> Text,
+        tag_id -> Text,
+        created_at -> BigInt,
+    }
+}
+
 diesel::table! {
     items (item_id) {
         item_id -> Text,
@@ -182,20 +255,69 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    tags (tag_id) {
+        tag_id -> Text,
+        name -> Text,
+        color -> Nullable<Text>,
+        created_at -> BigInt,
+        updated_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    trash (trash_id) {
+        trash_id -> Text,
+        record_type -> Text,
+        record_id -> Text,
+        title -> Nullable<Text>,
+        data -> Text,
+        deleted_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    usage_stats (usage_id) {
+        usage_id -> Text,
+        history_id -> Nullable<Text>,
+        item_id -> Nullable<Text>,
+        use_count -> Integer,
+        last_used_at -> BigInt,
+        frecency_rank -> Double,
+        created_at -> BigInt,
+    }
+}
+
+diesel::joinable!(clipboard_history_files -> clipboard_history (history_id));
 diesel::joinable!(collection_clips -> collections (collection_id));
 diesel::joinable!(collection_clips -> tabs (tab_id));
 diesel::joinable!(collection_menu -> collections (collection_id));
+diesel::joinable!(history_tags -> clipboard_history (history_id));
+diesel::joinable!(history_tags -> tags (tag_id));
+diesel::joinable!(item_revisions -> items (item_id));
+diesel::joinable!(item_tags -> items (item_id));
+diesel::joinable!(item_tags -> tags (tag_id));
 diesel::joinable!(link_metadata -> clipboard_history (history_id));
 diesel::joinable!(link_metadata -> items (item_id));
 diesel::joinable!(tabs -> collections (collection_id));
+diesel::joinable!(usage_stats -> clipboard_history (history_id));
+diesel::joinable!(usage_stats -> items (item_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    capture_rules,
     clipboard_history,
+    clipboard_history_files,
     collection_clips,
     collection_menu,
     collections,
+    history_tags,
+    item_revisions,
+    item_tags,
     items,
     link_metadata,
     settings,
     tabs,
+    tags,
+    trash,
+    usage_stats,
 );
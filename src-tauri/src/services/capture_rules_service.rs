@@ -0,0 +1,359 @@
+use crate::commands::items_commands;
+use crate::db::establish_pool_db_connection;
+use crate::models::models::{CaptureRule, CollectionClips};
+use crate::schema::capture_rules::dsl::*;
+use crate::schema::collection_clips;
+use crate::services::history_service;
+use crate::services::items_service::CreateItem;
+use crate::services::trash_service;
+use crate::services::utils::debug_output;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::Error;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+pub const RULE_ACTION_SKIP: &str = "skip";
+pub const RULE_ACTION_MASK: &str = "mask";
+pub const RULE_ACTION_STAR: &str = "star";
+pub const RULE_ACTION_PIN: &str = "pin";
+pub const RULE_ACTION_EXPIRE: &str = "expire";
+pub const RULE_ACTION_BOARD: &str = "board";
+
+pub const RULE_CONTENT_TYPES: [&str; 6] = ["text", "code", "link", "image", "video", "files"];
+
+lazy_static! {
+  static ref RULE_REGEX_CACHE: Mutex<HashMap<String, Option<Regex>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCaptureRule {
+  pub name: String,
+  pub is_enabled: Option<bool>,
+  pub order_number: Option<i32>,
+  pub match_content_regex: Option<String>,
+  pub match_app: Option<String>,
+  pub match_window_title: Option<String>,
+  pub match_language: Option<String>,
+  pub match_content_type: Option<String>,
+  pub action: String,
+  pub action_value: Option<String>,
+}
+
+/// What is known about a clipboard capture at the time the rules are evaluated
+#[derive(Debug, Default)]
+pub struct CaptureRuleContext<'a> {
+  pub value: Option<&'a str>,
+  pub app_name: Option<&'a str>,
+  pub window_title: Option<&'a str>,
+  pub detected_language: Option<&'a str>,
+  pub content_type: &'a str,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CaptureRuleOutcome {
+  pub skip: bool,
+  pub mask: bool,
+  pub star: bool,
+  pub pin: bool,
+  pub expires_at: Option<i64>,
+  pub move_to_board_id: Option<String>,
+  pub matched_rule_ids: Vec<String>,
+}
+
+/// Runs enabled rules in order against a capture. All matching rules contribute their
+/// action, except `skip` which stops evaluation. The shortest expiry and the first board win.
+pub fn evaluate_capture_rules(context: &CaptureRuleContext) -> CaptureRuleOutcome {
+  let mut outcome = CaptureRuleOutcome::default();
+
+  let rules = match get_enabled_capture_rules() {
+    Ok(rules) => rules,
+    Err(e) => {
+      eprintln!("Error loading capture rules: {}", e);
+      return outcome;
+    }
+  };
+
+  for rule in rules.iter() {
+    if !is_rule_matching(rule, context) {
+      continue;
+    }
+
+    outcome.matched_rule_ids.push(rule.rule_id.clone());
+
+    match rule.action.as_str() {
+      RULE_ACTION_SKIP => {
+        outcome.skip = true;
+        break;
+      }
+      RULE_ACTION_MASK => outcome.mask = true,
+      RULE_ACTION_STAR => outcome.star = true,
+      RULE_ACTION_PIN => outcome.pin = true,
+      RULE_ACTION_EXPIRE => {
+        if let Some(minutes) = parse_expire_minutes(&rule.action_value) {
+          let rule_expires_at = Utc::now().timestamp_millis() + minutes * 60 * 1000;
+          outcome.expires_at = Some(
+            outcome
+              .expires_at
+              .map_or(rule_expires_at, |current| current.min(rule_expires_at)),
+          );
+        }
+      }
+      RULE_ACTION_BOARD => {
+        if outcome.move_to_board_id.is_none() {
+          outcome.move_to_board_id = non_empty(&rule.action_value).map(String::from);
+        }
+      }
+      unknown_action => {
+        debug_output(|| {
+          println!(
+            "Unknown capture rule action {} in rule {}",
+            unknown_action, rule.rule_id
+          );
+        });
+      }
+    }
+  }
+
+  outcome
+}
+
+fn is_rule_matching(rule: &CaptureRule, context: &CaptureRuleContext) -> bool {
+  if let Some(pattern) = non_empty(&rule.match_content_regex) {
+    let is_match = context
+      .value
+      .map_or(false, |text| is_regex_matching(pattern, text));
+    if !is_match {
+      return false;
+    }
+  }
+
+  if let Some(app) = non_empty(&rule.match_app) {
+    let is_match = context
+      .app_name
+      .map_or(false, |app_name| app_name.to_lowercase() == app.to_lowercase());
+    if !is_match {
+      return false;
+    }
+  }
+
+  if let Some(title) = non_empty(&rule.match_window_title) {
+    let is_match = context.window_title.map_or(false, |window_title| {
+      window_title.to_lowercase().contains(&title.to_lowercase())
+    });
+    if !is_match {
+      return false;
+    }
+  }
+
+  if let Some(language) = non_empty(&rule.match_language) {
+    let is_match = context
+      .detected_language
+      .map_or(false, |detected| detected.eq_ignore_ascii_case(language));
+    if !is_match {
+      return false;
+    }
+  }
+
+  if let Some(content_type) = non_empty(&rule.match_content_type) {
+    if !context.content_type.eq_ignore_ascii_case(content_type) {
+      return false;
+    }
+  }
+
+  true
+}
+
+fn is_regex_matching(pattern: &str, text: &str) -> bool {
+  let mut cache = RULE_REGEX_CACHE.lock().unwrap();
+
+  let regex = cache.entry(pattern.to_string()).or_insert_with(|| {
+    Regex::new(pattern)
+      .map_err(|e| eprintln!("Invalid capture rule regex {}: {}", pattern, e))
+      .ok()
+  });
+
+  regex.as_ref().map_or(false, |regex| regex.is_match(text))
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+  value
+    .as_deref()
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+}
+
+fn parse_expire_minutes(value: &Option<String>) -> Option<i64> {
+  non_empty(value)
+    .and_then(|minutes| minutes.parse::<i64>().ok())
+    .filter(|minutes| *minutes > 0)
+}
+
+pub fn validate_capture_rule(rule: &CaptureRule) -> Result<(), String> {
+  if rule.name.trim().is_empty() {
+    return Err("Rule name is required".to_string());
+  }
+
+  if let Some(pattern) = non_empty(&rule.match_content_regex) {
+    Regex::new(pattern).map_err(|e| format!("Invalid content regex: {}", e))?;
+  }
+
+  if let Some(content_type) = non_empty(&rule.match_content_type) {
+    if !RULE_CONTENT_TYPES.contains(&content_type.to_lowercase().as_str()) {
+      return Err(format!("Unknown content type: {}", content_type));
+    }
+  }
+
+  match rule.action.as_str() {
+    RULE_ACTION_SKIP | RULE_ACTION_MASK | RULE_ACTION_STAR | RULE_ACTION_PIN => Ok(()),
+    RULE_ACTION_EXPIRE => parse_expire_minutes(&rule.action_value)
+      .map(|_| ())
+      .ok_or_else(|| "Expire action requires a positive number of minutes".to_string()),
+    RULE_ACTION_BOARD => non_empty(&rule.action_value)
+      .map(|_| ())
+      .ok_or_else(|| "Board action requires a board id".to_string()),
+    unknown_action => Err(format!("Unknown rule action: {}", unknown_action)),
+  }
+}
+
+pub fn get_capture_rules() -> Result<Vec<CaptureRule>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  capture_rules
+    .order((order_number.asc(), created_at.asc()))
+    .load::<CaptureRule>(connection)
+}
+
+fn get_enabled_capture_rules() -> Result<Vec<CaptureRule>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  capture_rules
+    .filter(is_enabled.eq(true))
+    .order((order_number.asc(), created_at.asc()))
+    .load::<CaptureRule>(connection)
+}
+
+pub fn create_capture_rule(new_rule: &CaptureRule) -> Result<String, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::insert_into(capture_rules)
+    .values(new_rule)
+    .execute(connection)?;
+
+  Ok(new_rule.rule_id.clone())
+}
+
+pub fn update_capture_rule(updated_rule: &CaptureRule) -> Result<String, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let mut updated_rule = updated_rule.clone();
+  updated_rule.updated_at = Utc::now().timestamp_millis();
+
+  diesel::update(capture_rules.find(&updated_rule.rule_id))
+    .set(&updated_rule)
+    .execute(connection)?;
+
+  Ok("ok".to_string())
+}
+
+pub fn update_capture_rules_order(rule_ids: &[String]) -> Result<String, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  connection.transaction::<_, Error, _>(|connection| {
+    for (index, _rule_id) in rule_ids.iter().enumerate() {
+      diesel::update(capture_rules.find(_rule_id))
+        .set(order_number.eq(index as i32))
+        .execute(connection)?;
+    }
+    Ok(())
+  })?;
+
+  Ok("ok".to_string())
+}
+
+pub fn delete_capture_rule_by_id(rule_id_value: &String) -> Result<String, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::delete(capture_rules.find(rule_id_value)).execute(connection)?;
+
+  Ok("ok".to_string())
+}
+
+/// Saves a captured history item as a clip on the given board and moves it to the trash once the
+/// clip is saved
+pub fn move_history_item_to_board(history_id: &String, board_id: &String) -> Result<String, String> {
+  let connection = &mut establish_pool_db_connection();
+
+  let board_clip = collection_clips::table
+    .filter(collection_clips::item_id.eq(board_id))
+    .first::<CollectionClips>(connection)
+    .map_err(|e| format!("Board not found: {}", e))?;
+
+  let history = history_service::get_clipboard_history_by_id(history_id)
+    .ok_or_else(|| "History item not found".to_string())?;
+
+  let clip_name = match &history.value {
+    Some(text) if history.is_image != Some(true) => text
+      .lines()
+      .find(|line| !line.trim().is_empty())
+      .map(|line| line.trim().chars().take(50).collect::<String>())
+      .unwrap_or_default(),
+    _ => "Image".to_string(),
+  };
+
+  let new_clip = CreateItem {
+    history_id: Some(history.history_id.clone()),
+    history_options: history.history_options.clone(),
+    item_options: None,
+    name: clip_name,
+    description: None,
+    detected_language: history.detected_language.clone(),
+    value: None,
+    is_folder: None,
+    is_separator: None,
+    is_board: None,
+    is_clip: Some(true),
+    is_menu: None,
+    is_image: history.is_image,
+    is_link: history.is_link,
+    is_video: history.is_video,
+    is_code: history.is_code,
+    is_text: history.is_text,
+    is_form: None,
+    is_template: None,
+    is_protected: None,
+    is_disabled: None,
+    has_emoji: history.has_emoji,
+    has_masked_words: history.has_masked_words,
+    image_data_url: None,
+    image_path_full_res: history.image_path_full_res.clone(),
+    image_height: history.image_height,
+    image_width: history.image_width,
+    image_preview_height: history.image_preview_height,
+    image_hash: None,
+    image_type: None,
+    image_scale: None,
+    links: history.links.clone(),
+    is_image_data: history.is_image_data,
+    is_masked: history.is_masked,
+    color: None,
+    border_width: None,
+    value_type_id: None,
+    order_number: 0,
+    parent_id: Some(board_id.clone()),
+    tab_id: Some(board_clip.tab_id),
+    collection_id: board_clip.collection_id,
+  };
+
+  let clip_id = items_commands::create_item_on_board(new_clip)?;
+
+  trash_service::trash_clipboard_history_by_ids(&[history.history_id])
+    .map_err(|e| format!("Error moving history item to trash: {}", e))?;
+
+  Ok(clip_id)
+}
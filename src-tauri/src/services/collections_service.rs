@@ -670,7 +670,7 @@ pub fn create_default_menu_item(collection_id: String) -> Result<String, diesel:
     updated_date: chrono::Local::now().naive_local(),
   };
 
-  let new_item_id = items_service::create_item(&new_item);
+  let new_item_id = items_service::create_item(&new_item)?;
 
   match add_menu_to_collection(collection_id, new_item_id.clone(), None, 0) {
     Ok(_) => Ok(new_item.item_id),
@@ -746,7 +746,7 @@ pub fn create_default_board_item(
     updated_date: chrono::Local::now().naive_local(),
   };
 
-  let new_item_id = items_service::create_item(&new_item);
+  let new_item_id = items_service::create_item(&new_item)?;
 
   let _ = add_item_to_collection(collection_id, new_item_id.clone(), tab_id, None, 0);
 
@@ -0,0 +1,219 @@
+use crate::models::Setting;
+use sha1::{Digest, Sha1};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+pub const DEDUPE_POLICY_GLOBAL: &str = "global";
+
+const DEFAULT_DEDUPE_RECENT_COUNT: i64 = 10;
+
+/// Which history entries a new capture is compared against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupePolicy {
+  /// Only the given number of most recently used entries, the default. Any policy other than
+  /// "global" in `historyDedupePolicy` uses it.
+  Recent(i64),
+  /// Every entry in history
+  Global,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupeOptions {
+  pub policy: DedupePolicy,
+  pub trim: bool,
+  pub collapse_whitespace: bool,
+  pub normalize_line_endings: bool,
+  pub ignore_case: bool,
+}
+
+impl Default for DedupeOptions {
+  fn default() -> Self {
+    DedupeOptions {
+      policy: DedupePolicy::Recent(DEFAULT_DEDUPE_RECENT_COUNT),
+      trim: true,
+      collapse_whitespace: false,
+      normalize_line_endings: true,
+      ignore_case: false,
+    }
+  }
+}
+
+impl DedupeOptions {
+  pub fn from_settings(settings_map: &HashMap<String, Setting>) -> Self {
+    let defaults = DedupeOptions::default();
+    let get_bool = |setting_name: &str, default: bool| {
+      settings_map
+        .get(setting_name)
+        .and_then(|s| s.value_bool)
+        .unwrap_or(default)
+    };
+
+    let policy = match settings_map
+      .get("historyDedupePolicy")
+      .and_then(|s| s.value_text.as_deref())
+    {
+      // Global merging is opt-in, history was only compared with the 10 most recent entries
+      // before the policy existed
+      Some(DEDUPE_POLICY_GLOBAL) => DedupePolicy::Global,
+      _ => settings_map
+        .get("historyDedupeRecentCount")
+        .and_then(|s| s.value_int)
+        .filter(|count| *count > 0)
+        .map_or(defaults.policy, |count| DedupePolicy::Recent(count as i64)),
+    };
+
+    DedupeOptions {
+      policy,
+      trim: get_bool("isHistoryDedupeTrimEnabled", defaults.trim),
+      collapse_whitespace: get_bool(
+        "isHistoryDedupeCollapseWhitespaceEnabled",
+        defaults.collapse_whitespace,
+      ),
+      normalize_line_endings: get_bool(
+        "isHistoryDedupeLineEndingsEnabled",
+        defaults.normalize_line_endings,
+      ),
+      ignore_case: get_bool("isHistoryDedupeIgnoreCaseEnabled", defaults.ignore_case),
+    }
+  }
+
+  /// Applies the enabled normalizations, text that needs none is hashed as captured
+  /// so entries saved before normalization existed keep matching
+  pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+    let mut normalized = Cow::Borrowed(text);
+
+    if self.normalize_line_endings && normalized.contains('\r') {
+      normalized = Cow::Owned(normalized.replace("\r\n", "\n").replace('\r', "\n"));
+    }
+
+    if self.collapse_whitespace {
+      let collapsed = normalized
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .collect::<Vec<String>>()
+        .join("\n");
+      if collapsed != normalized {
+        normalized = Cow::Owned(collapsed);
+      }
+    }
+
+    if self.trim {
+      let trimmed = normalized.trim();
+      if trimmed.len() != normalized.len() {
+        normalized = Cow::Owned(trimmed.to_string());
+      }
+    }
+
+    if self.ignore_case && normalized.chars().any(char::is_uppercase) {
+      normalized = Cow::Owned(normalized.to_lowercase());
+    }
+
+    normalized
+  }
+
+  /// SHA-1 of the normalized text, stored in `clipboard_history.value_hash`
+  pub fn hash(&self, text: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(self.normalize(text).as_bytes());
+    format!("{:x}", hasher.finalize())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn setting(name: &str, value_text: Option<&str>, value_bool: Option<bool>) -> (String, Setting) {
+    (
+      name.to_string(),
+      Setting {
+        name: name.to_string(),
+        value_text: value_text.map(String::from),
+        value_bool,
+        value_int: None,
+      },
+    )
+  }
+
+  #[test]
+  fn default_options_ignore_line_endings_and_surrounding_whitespace() {
+    let options = DedupeOptions::default();
+
+    assert_eq!(
+      options.hash("line one\r\nline two"),
+      options.hash("line one\nline two")
+    );
+    assert_eq!(
+      options.hash("line one\rline two"),
+      options.hash("line one\nline two")
+    );
+    assert_eq!(options.hash("  text\n"), options.hash("text"));
+    assert_ne!(options.hash("a  b"), options.hash("a b"));
+    assert_ne!(options.hash("Text"), options.hash("text"));
+  }
+
+  #[test]
+  fn collapse_whitespace_keeps_line_breaks() {
+    let options = DedupeOptions {
+      collapse_whitespace: true,
+      ..Default::default()
+    };
+
+    assert_eq!(options.hash("a  \tb\n c"), options.hash("a b\nc"));
+    assert_ne!(options.hash("a b c"), options.hash("a b\nc"));
+  }
+
+  #[test]
+  fn ignore_case_matches_other_casing() {
+    let options = DedupeOptions {
+      ignore_case: true,
+      ..Default::default()
+    };
+
+    assert_eq!(options.hash("Hello World"), options.hash("hello world"));
+  }
+
+  #[test]
+  fn disabled_normalizations_hash_the_text_as_captured() {
+    let options = DedupeOptions {
+      trim: false,
+      normalize_line_endings: false,
+      ..Default::default()
+    };
+
+    assert_ne!(options.hash(" text"), options.hash("text"));
+    assert_ne!(options.hash("a\r\nb"), options.hash("a\nb"));
+    assert!(matches!(options.normalize(" text"), Cow::Borrowed(" text")));
+  }
+
+  #[test]
+  fn global_policy_is_opt_in() {
+    let settings_map = HashMap::from([setting(
+      "historyDedupePolicy",
+      Some(DEDUPE_POLICY_GLOBAL),
+      None,
+    )]);
+
+    assert_eq!(
+      DedupeOptions::from_settings(&settings_map).policy,
+      DedupePolicy::Global
+    );
+    assert_eq!(
+      DedupeOptions::from_settings(&HashMap::new()).policy,
+      DedupePolicy::Recent(DEFAULT_DEDUPE_RECENT_COUNT)
+    );
+  }
+
+  #[test]
+  fn settings_switch_normalizations() {
+    let settings_map = HashMap::from([
+      setting("isHistoryDedupeIgnoreCaseEnabled", None, Some(true)),
+      setting("isHistoryDedupeTrimEnabled", None, Some(false)),
+    ]);
+    let options = DedupeOptions::from_settings(&settings_map);
+
+    assert!(options.ignore_case);
+    assert!(!options.trim);
+    assert!(options.normalize_line_endings);
+  }
+}
@@ -0,0 +1,248 @@
+use active_win_pos_rs::get_active_window;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::menu::{self, DbItems, DbRecentHistoryItems};
+use crate::models::Setting;
+use crate::services::settings_service::{insert_or_update_setting_by_name, SettingUpdatePayload};
+use crate::services::utils::debug_output;
+
+pub const SETTING_PAUSED_UNTIL: &str = "historyCapturePausedUntil";
+pub const SETTING_PAUSED_UNTIL_APP_BLUR: &str = "historyCapturePausedUntilAppBlur";
+
+pub const RESUME_REASON_TIMER: &str = "timer";
+pub const RESUME_REASON_APP_BLUR: &str = "appBlur";
+pub const RESUME_REASON_MANUAL: &str = "manual";
+
+lazy_static! {
+  // The paused app has to be focused at least once before losing focus resumes capture,
+  // pausing from PasteBar itself would resume right away otherwise
+  static ref IS_PAUSED_APP_FOCUSED: Mutex<bool> = Mutex::new(false);
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPauseState {
+  pub paused_until: Option<i64>,
+  pub remaining_ms: Option<i64>,
+  pub until_app_blur: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryCaptureResumedPayload {
+  reason: String,
+}
+
+/// Returns the active pause, if history capture was paused with a deadline or until an app loses focus.
+/// Turning history capture off without a pause mode is not a pause.
+pub fn get_history_pause_state(
+  settings_map: &HashMap<String, Setting>,
+) -> Option<HistoryPauseState> {
+  let is_history_enabled = settings_map
+    .get("isHistoryEnabled")
+    .and_then(|s| s.value_bool)
+    .unwrap_or(true);
+
+  let paused_until = settings_map
+    .get(SETTING_PAUSED_UNTIL)
+    .and_then(|s| s.value_text.as_ref())
+    .and_then(|value| value.parse::<i64>().ok());
+
+  let until_app_blur = settings_map
+    .get(SETTING_PAUSED_UNTIL_APP_BLUR)
+    .and_then(|s| s.value_text.clone())
+    .filter(|app_name| !app_name.trim().is_empty());
+
+  if is_history_enabled || (paused_until.is_none() && until_app_blur.is_none()) {
+    return None;
+  }
+
+  Some(HistoryPauseState {
+    paused_until,
+    remaining_ms: paused_until.map(|until| (until - Utc::now().timestamp_millis()).max(0)),
+    until_app_blur,
+  })
+}
+
+/// Whole minutes left in a timed pause, rounded up so the tray never shows 0 while still paused
+pub fn get_remaining_minutes(remaining_ms: i64) -> i64 {
+  (remaining_ms + 59_999) / 60_000
+}
+
+/// Pauses history capture for a number of minutes or until the given app loses focus.
+/// The deadline is stored in settings so a restart does not lose it.
+pub fn pause_history_capture(
+  app_handle: &tauri::AppHandle,
+  pause_minutes: Option<i64>,
+  until_app_blur: Option<String>,
+) -> Result<HistoryPauseState, String> {
+  let until_app_blur = until_app_blur.filter(|app_name| !app_name.trim().is_empty());
+
+  if pause_minutes.map_or(true, |minutes| minutes <= 0) && until_app_blur.is_none() {
+    return Err("Pause requires a positive number of minutes or an app name".to_string());
+  }
+
+  let paused_until = pause_minutes
+    .filter(|minutes| *minutes > 0)
+    .map(|minutes| Utc::now().timestamp_millis() + minutes * 60 * 1000);
+
+  *IS_PAUSED_APP_FOCUSED.lock().unwrap() = false;
+
+  save_pause_settings(app_handle, paused_until, until_app_blur)?;
+  set_history_enabled(app_handle, false)?;
+  refresh_tray_menu(app_handle);
+
+  let app_settings = app_handle.state::<Mutex<HashMap<String, Setting>>>();
+  let settings_map = app_settings.lock().unwrap();
+
+  get_history_pause_state(&settings_map)
+    .ok_or_else(|| "Failed to pause history capture".to_string())
+}
+
+/// Turns history capture back on, clears the stored pause and notifies the frontend
+pub fn resume_history_capture(app_handle: &tauri::AppHandle, reason: &str) -> Result<(), String> {
+  save_pause_settings(app_handle, None, None)?;
+  set_history_enabled(app_handle, true)?;
+  refresh_tray_menu(app_handle);
+
+  debug_output(|| {
+    println!("History capture resumed: {}", reason);
+  });
+
+  let _ = app_handle.emit_all(
+    "history-capture://resumed",
+    HistoryCaptureResumedPayload {
+      reason: reason.to_string(),
+    },
+  );
+
+  Ok(())
+}
+
+/// Resumes capture when the pause deadline has passed or the paused app lost focus.
+/// History turned back on by hand only clears the leftover pause settings.
+pub fn check_history_pause(app_handle: &tauri::AppHandle) -> Option<HistoryPauseState> {
+  let (pause_state, has_stale_pause) = {
+    let app_settings = app_handle.state::<Mutex<HashMap<String, Setting>>>();
+    let settings_map = app_settings.lock().unwrap();
+
+    let has_pause_settings = [SETTING_PAUSED_UNTIL, SETTING_PAUSED_UNTIL_APP_BLUR]
+      .iter()
+      .any(|setting_name| {
+        settings_map
+          .get(*setting_name)
+          .map_or(false, |s| s.value_text.is_some())
+      });
+
+    let pause_state = get_history_pause_state(&settings_map);
+    (
+      pause_state.clone(),
+      pause_state.is_none() && has_pause_settings,
+    )
+  };
+
+  if has_stale_pause {
+    let _ = save_pause_settings(app_handle, None, None);
+    return None;
+  }
+
+  let pause_state = pause_state?;
+
+  if pause_state.remaining_ms == Some(0) {
+    if let Err(e) = resume_history_capture(app_handle, RESUME_REASON_TIMER) {
+      eprintln!("Error resuming history capture: {}", e);
+    }
+    return None;
+  }
+
+  if let Some(paused_app_name) = &pause_state.until_app_blur {
+    if let Ok(active_window) = get_active_window() {
+      let is_paused_app_active =
+        active_window.app_name.to_lowercase() == paused_app_name.to_lowercase();
+      let mut is_paused_app_focused = IS_PAUSED_APP_FOCUSED.lock().unwrap();
+
+      if is_paused_app_active {
+        *is_paused_app_focused = true;
+      } else if *is_paused_app_focused {
+        *is_paused_app_focused = false;
+        drop(is_paused_app_focused);
+
+        if let Err(e) = resume_history_capture(app_handle, RESUME_REASON_APP_BLUR) {
+          eprintln!("Error resuming history capture: {}", e);
+        }
+        return None;
+      }
+    }
+  }
+
+  Some(pause_state)
+}
+
+fn save_pause_settings(
+  app_handle: &tauri::AppHandle,
+  paused_until: Option<i64>,
+  until_app_blur: Option<String>,
+) -> Result<(), String> {
+  for (setting_name, setting_value) in [
+    (
+      SETTING_PAUSED_UNTIL,
+      paused_until.map(|until| until.to_string()),
+    ),
+    (SETTING_PAUSED_UNTIL_APP_BLUR, until_app_blur),
+  ] {
+    insert_or_update_setting_by_name(
+      &Setting {
+        name: setting_name.to_string(),
+        value_text: setting_value,
+        value_bool: None,
+        value_int: None,
+      },
+      app_handle.clone(),
+    )
+    .map_err(|e| e.to_string())?;
+  }
+
+  Ok(())
+}
+
+fn set_history_enabled(app_handle: &tauri::AppHandle, is_enabled: bool) -> Result<(), String> {
+  insert_or_update_setting_by_name(
+    &Setting {
+      name: "isHistoryEnabled".to_string(),
+      value_text: None,
+      value_bool: Some(is_enabled),
+      value_int: None,
+    },
+    app_handle.clone(),
+  )
+  .map_err(|e| e.to_string())?;
+
+  // Keeps the settings store in the frontend in sync
+  let _ = app_handle.emit_all(
+    "setting:update",
+    SettingUpdatePayload {
+      name: "isHistoryEnabled".to_string(),
+      value_bool: Some(is_enabled),
+      value_string: None,
+      value_number: None,
+    },
+  );
+
+  Ok(())
+}
+
+pub fn refresh_tray_menu(app_handle: &tauri::AppHandle) {
+  if let Err(e) = menu::update_system_menu(
+    app_handle,
+    app_handle.state::<DbItems>(),
+    app_handle.state::<DbRecentHistoryItems>(),
+    app_handle.state::<Mutex<HashMap<String, Setting>>>(),
+  ) {
+    eprintln!("{}", e);
+  }
+}
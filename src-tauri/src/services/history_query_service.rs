@@ -0,0 +1,652 @@
+use chrono::{Local, NaiveDate, TimeDelta, TimeZone, Utc};
+use diesel::dsl::{not, sql};
+use diesel::helper_types::LeftJoinQuerySource;
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Nullable, Text};
+use diesel::sqlite::Sqlite;
+use serde::Serialize;
+use std::fmt;
+
+use crate::clipboard::{SOURCE_SELECTION_CLIPBOARD, SOURCE_SELECTION_PRIMARY};
+use crate::schema::clipboard_history::dsl::*;
+use crate::schema::{clipboard_history, history_tags, link_metadata, tags, usage_stats};
+use crate::services::history_search_service::HISTORY_FTS_FILTER_SQL;
+
+const IS_FLAG_NAMES: &str =
+  "pinned, starred, text, image, link, code, video, file, emoji, secret, audio, primary, clipboard";
+
+/// History joined with link metadata and usage stats, as searched in history_service
+pub type HistoryQuerySource = LeftJoinQuerySource<
+  LeftJoinQuerySource<
+    clipboard_history::table,
+    link_metadata::table,
+    diesel::dsl::Eq<
+      link_metadata::history_id,
+      diesel::dsl::Nullable<clipboard_history::history_id>,
+    >,
+  >,
+  usage_stats::table,
+  diesel::dsl::Eq<usage_stats::history_id, diesel::dsl::Nullable<clipboard_history::history_id>>,
+>;
+
+pub type HistoryQueryFilter =
+  Box<dyn BoxableExpression<HistoryQuerySource, Sqlite, SqlType = Nullable<Bool>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryQueryFlag {
+  Pinned,
+  Starred,
+  Text,
+  Image,
+  Link,
+  Code,
+  Video,
+  File,
+  Emoji,
+  Secret,
+  Audio,
+  Primary,
+  Clipboard,
+}
+
+impl HistoryQueryFlag {
+  pub fn from_name(flag_name: &str) -> Option<Self> {
+    match flag_name.to_lowercase().as_str() {
+      "pinned" => Some(HistoryQueryFlag::Pinned),
+      "starred" | "favorite" => Some(HistoryQueryFlag::Starred),
+      "text" => Some(HistoryQueryFlag::Text),
+      "image" => Some(HistoryQueryFlag::Image),
+      "link" => Some(HistoryQueryFlag::Link),
+      "code" => Some(HistoryQueryFlag::Code),
+      "video" => Some(HistoryQueryFlag::Video),
+      "file" | "files" => Some(HistoryQueryFlag::File),
+      "emoji" => Some(HistoryQueryFlag::Emoji),
+      "secret" | "masked" => Some(HistoryQueryFlag::Secret),
+      "audio" => Some(HistoryQueryFlag::Audio),
+      "primary" => Some(HistoryQueryFlag::Primary),
+      "clipboard" => Some(HistoryQueryFlag::Clipboard),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryQueryTerm {
+  /// Word looked up by prefix in the full-text index
+  Word(String),
+  /// Words that have to appear next to each other in this order
+  Phrase(String),
+  /// Text without letters or digits, matched as a substring of the value
+  Substring(String),
+  /// `app:`, the source app name, `*` matches any characters
+  App(String),
+  /// `window:`, part of the source window title
+  Window(String),
+  /// `lang:`, the detected code language
+  Language(String),
+  /// `tag:`, the name of a tag on the entry, `*` matches any characters
+  Tag(String),
+  /// `is:`
+  Is(HistoryQueryFlag),
+  /// `before:`, last copied before this time in milliseconds
+  Before(i64),
+  /// `after:`, last copied at or after this time in milliseconds
+  After(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryQueryClause {
+  pub term: HistoryQueryTerm,
+  /// Written with a leading `-`, the entry must not match the term
+  pub is_negated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQueryError {
+  pub message: String,
+  /// Character offset in the query where the error was found
+  pub position: Option<usize>,
+}
+
+impl HistoryQueryError {
+  pub fn new(message: impl Into<String>, position: Option<usize>) -> Self {
+    HistoryQueryError {
+      message: message.into(),
+      position,
+    }
+  }
+}
+
+impl fmt::Display for HistoryQueryError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.position {
+      Some(position) => write!(f, "{} at position {}", self.message, position),
+      None => write!(f, "{}", self.message),
+    }
+  }
+}
+
+/// Parses a search such as `app:Slack lang:rust tag:work is:pinned after:7d "exact phrase" -word`.
+/// Every clause has to match, a leading `-` negates a clause. Field values with spaces are
+/// quoted, `app:"Google Chrome"`. Words before a colon that are not a field are searched as text.
+pub fn parse_history_query(query: &str) -> Result<Vec<HistoryQueryClause>, HistoryQueryError> {
+  let chars: Vec<char> = query.chars().collect();
+  let mut clauses = Vec::new();
+  let mut position = 0;
+
+  while position < chars.len() {
+    if chars[position].is_whitespace() {
+      position += 1;
+      continue;
+    }
+
+    // A dash before other punctuation is text, so `->` or `--force` can still be searched
+    let is_negated = chars[position] == '-'
+      && chars
+        .get(position + 1)
+        .map_or(false, |c| c.is_alphanumeric() || *c == '"');
+
+    if is_negated {
+      position += 1;
+    }
+
+    let term_start = position;
+
+    let term = if chars[position] == '"' {
+      let (phrase, next_position) = read_quoted(&chars, position)?;
+      position = next_position;
+      text_term(phrase, true, term_start)?
+    } else {
+      let name_end = (position..chars.len())
+        .find(|&i| chars[i].is_whitespace() || chars[i] == ':')
+        .unwrap_or(chars.len());
+      let field_name: String = chars[position..name_end].iter().collect::<String>();
+
+      if name_end < chars.len() && chars[name_end] == ':' && is_field_name(&field_name) {
+        let value_start = name_end + 1;
+        let (field_value, next_position) = match chars.get(value_start) {
+          Some('"') => read_quoted(&chars, value_start)?,
+          _ => read_word(&chars, value_start),
+        };
+        position = next_position;
+
+        if field_value.trim().is_empty() {
+          return Err(HistoryQueryError::new(
+            format!("Missing value for '{}:'", field_name),
+            Some(value_start),
+          ));
+        }
+
+        field_term(&field_name, field_value, value_start)?
+      } else {
+        let (word, next_position) = read_word(&chars, position);
+        position = next_position;
+        text_term(word, false, term_start)?
+      }
+    };
+
+    clauses.push(HistoryQueryClause { term, is_negated });
+  }
+
+  Ok(clauses)
+}
+
+fn is_field_name(field_name: &str) -> bool {
+  matches!(
+    field_name.to_lowercase().as_str(),
+    "app" | "window" | "lang" | "tag" | "is" | "before" | "after"
+  )
+}
+
+fn read_word(chars: &[char], start: usize) -> (String, usize) {
+  let end = (start..chars.len())
+    .find(|&i| chars[i].is_whitespace())
+    .unwrap_or(chars.len());
+
+  (chars[start..end].iter().collect(), end)
+}
+
+/// Reads text between double quotes starting at the opening quote, `""` inside is a quote
+fn read_quoted(chars: &[char], start: usize) -> Result<(String, usize), HistoryQueryError> {
+  let mut text = String::new();
+  let mut position = start + 1;
+
+  while position < chars.len() {
+    if chars[position] == '"' {
+      if chars.get(position + 1) == Some(&'"') {
+        text.push('"');
+        position += 2;
+        continue;
+      }
+      return Ok((text, position + 1));
+    }
+
+    text.push(chars[position]);
+    position += 1;
+  }
+
+  Err(HistoryQueryError::new("Missing closing quote", Some(start)))
+}
+
+fn text_term(
+  text: String,
+  is_phrase: bool,
+  position: usize,
+) -> Result<HistoryQueryTerm, HistoryQueryError> {
+  if text.trim().is_empty() {
+    return Err(HistoryQueryError::new("Empty phrase", Some(position)));
+  }
+
+  Ok(if !text.chars().any(char::is_alphanumeric) {
+    HistoryQueryTerm::Substring(text)
+  } else if is_phrase {
+    HistoryQueryTerm::Phrase(text)
+  } else {
+    HistoryQueryTerm::Word(text)
+  })
+}
+
+fn field_term(
+  field_name: &str,
+  field_value: String,
+  position: usize,
+) -> Result<HistoryQueryTerm, HistoryQueryError> {
+  let field_name = field_name.to_lowercase();
+
+  match field_name.as_str() {
+    "app" => Ok(HistoryQueryTerm::App(field_value)),
+    "window" => Ok(HistoryQueryTerm::Window(field_value)),
+    "lang" => Ok(HistoryQueryTerm::Language(field_value)),
+    "tag" => Ok(HistoryQueryTerm::Tag(field_value)),
+    "is" => HistoryQueryFlag::from_name(&field_value)
+      .map(HistoryQueryTerm::Is)
+      .ok_or_else(|| {
+        HistoryQueryError::new(
+          format!(
+            "Unknown value '{}' for 'is:', expected one of {}",
+            field_value, IS_FLAG_NAMES
+          ),
+          Some(position),
+        )
+      }),
+    _ => {
+      let timestamp = parse_query_time(&field_value).ok_or_else(|| {
+        HistoryQueryError::new(
+          format!(
+            "Invalid time '{}' for '{}:', expected a date as YYYY-MM-DD or a time ago such as 30m, 12h, 7d or 2w",
+            field_value, field_name
+          ),
+          Some(position),
+        )
+      })?;
+
+      if field_name == "before" {
+        Ok(HistoryQueryTerm::Before(timestamp))
+      } else {
+        Ok(HistoryQueryTerm::After(timestamp))
+      }
+    }
+  }
+}
+
+/// Dates start at local midnight, so `after:` includes the day and `before:` excludes it
+fn parse_query_time(time_value: &str) -> Option<i64> {
+  if let Ok(date) = NaiveDate::parse_from_str(time_value, "%Y-%m-%d") {
+    return Local
+      .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+      .earliest()
+      .map(|date_time| date_time.timestamp_millis());
+  }
+
+  // Negative amounts and amounts too large for a duration or a date are invalid times instead
+  // of a time in the future or an overflow
+  let (unit_index, unit) = time_value.char_indices().last()?;
+  let amount = i64::from(time_value[..unit_index].parse::<u32>().ok()?);
+
+  let duration = match unit {
+    'm' => TimeDelta::try_minutes(amount),
+    'h' => TimeDelta::try_hours(amount),
+    'd' => TimeDelta::try_days(amount),
+    'w' => TimeDelta::try_weeks(amount),
+    _ => None,
+  }?;
+
+  Utc::now()
+    .checked_sub_signed(duration)
+    .map(|date_time| date_time.timestamp_millis())
+}
+
+/// Compiles parsed clauses into filters that all have to match. Every filter is true or false
+/// and never NULL, so negating a clause matches exactly the entries the clause does not match.
+pub fn history_query_filters(clauses: &[HistoryQueryClause]) -> Vec<HistoryQueryFilter> {
+  clauses
+    .iter()
+    .map(|clause| term_filter(&clause.term, clause.is_negated))
+    .collect()
+}
+
+fn query_filter<E>(expression: E, is_negated: bool) -> HistoryQueryFilter
+where
+  E: BoxableExpression<HistoryQuerySource, Sqlite, SqlType = Nullable<Bool>> + 'static,
+  diesel::helper_types::not<E>:
+    BoxableExpression<HistoryQuerySource, Sqlite, SqlType = Nullable<Bool>> + 'static,
+{
+  if is_negated {
+    Box::new(not(expression))
+  } else {
+    Box::new(expression)
+  }
+}
+
+fn term_filter(term: &HistoryQueryTerm, is_negated: bool) -> HistoryQueryFilter {
+  match term {
+    HistoryQueryTerm::Word(word) => fts_filter(format!("{}*", fts_phrase(word)), is_negated),
+    HistoryQueryTerm::Phrase(phrase) => fts_filter(fts_phrase(phrase), is_negated),
+    HistoryQueryTerm::Substring(text) => query_filter(
+      value
+        .is_not_null()
+        .and(value.like(format!("%{}%", like_pattern(text))).escape('\\'))
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryTerm::App(app_name) => query_filter(
+      copied_from_app
+        .is_not_null()
+        .and(copied_from_app.like(like_pattern(app_name)).escape('\\'))
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryTerm::Window(window_title) => query_filter(
+      copied_from_window_title
+        .is_not_null()
+        .and(
+          copied_from_window_title
+            .like(format!("%{}%", like_pattern(window_title)))
+            .escape('\\'),
+        )
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryTerm::Language(language) => query_filter(
+      detected_language
+        .is_not_null()
+        .and(detected_language.like(like_pattern(language)).escape('\\'))
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryTerm::Tag(tag_name) => query_filter(
+      history_id
+        .eq_any(
+          history_tags::table
+            .inner_join(tags::table)
+            .filter(tags::name.like(like_pattern(tag_name)).escape('\\'))
+            .select(history_tags::history_id),
+        )
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryTerm::Is(flag) => flag_filter(*flag, is_negated),
+    HistoryQueryTerm::Before(timestamp) => {
+      query_filter(updated_at.lt(*timestamp).nullable(), is_negated)
+    }
+    HistoryQueryTerm::After(timestamp) => {
+      query_filter(updated_at.ge(*timestamp).nullable(), is_negated)
+    }
+  }
+}
+
+fn flag_filter(flag: HistoryQueryFlag, is_negated: bool) -> HistoryQueryFilter {
+  match flag {
+    HistoryQueryFlag::Pinned => query_filter(
+      is_pinned.is_not_null().and(is_pinned.eq(true)).nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Starred => query_filter(
+      is_favorite
+        .is_not_null()
+        .and(is_favorite.eq(true))
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Text => query_filter(
+      is_text.is_not_null().and(is_text.eq(true)).nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Image => query_filter(
+      is_image.is_not_null().and(is_image.eq(true)).nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Link => query_filter(
+      is_link.is_not_null().and(is_link.eq(true)).nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Code => query_filter(
+      is_code.is_not_null().and(is_code.eq(true)).nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Video => query_filter(
+      is_video.is_not_null().and(is_video.eq(true)).nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::File => query_filter(
+      is_file.is_not_null().and(is_file.eq(true)).nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Emoji => query_filter(
+      clipboard_history::has_emoji
+        .is_not_null()
+        .and(clipboard_history::has_emoji.eq(true))
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Secret => query_filter(
+      clipboard_history::is_masked
+        .is_not_null()
+        .and(clipboard_history::is_masked.eq(true))
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Audio => query_filter(
+      is_link
+        .is_not_null()
+        .and(is_link.eq(true))
+        .and(value.is_not_null())
+        .and(value.like("%.mp3"))
+        .nullable(),
+      is_negated,
+    ),
+    HistoryQueryFlag::Primary => query_filter(
+      source_selection
+        .is_not_null()
+        .and(source_selection.eq(SOURCE_SELECTION_PRIMARY))
+        .nullable(),
+      is_negated,
+    ),
+    // Entries captured before the source selection was recorded are regular clipboard copies
+    HistoryQueryFlag::Clipboard => query_filter(
+      source_selection
+        .is_null()
+        .or(source_selection.eq(SOURCE_SELECTION_CLIPBOARD))
+        .nullable(),
+      is_negated,
+    ),
+  }
+}
+
+fn fts_filter(fts_query: String, is_negated: bool) -> HistoryQueryFilter {
+  query_filter(
+    sql::<Bool>(HISTORY_FTS_FILTER_SQL)
+      .bind::<Text, _>(fts_query)
+      .sql(")")
+      .nullable(),
+    is_negated,
+  )
+}
+
+fn fts_phrase(text: &str) -> String {
+  format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// Escapes LIKE wildcards in the text and turns `*` into a wildcard
+fn like_pattern(text: &str) -> String {
+  text
+    .replace('\\', "\\\\")
+    .replace('%', "\\%")
+    .replace('_', "\\_")
+    .replace('*', "%")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn clause(term: HistoryQueryTerm, is_negated: bool) -> HistoryQueryClause {
+    HistoryQueryClause { term, is_negated }
+  }
+
+  #[test]
+  fn parses_fields_and_words() {
+    assert_eq!(
+      parse_history_query("app:Slack lang:rust tag:work is:pinned window:Inbox deploy").unwrap(),
+      vec![
+        clause(HistoryQueryTerm::App("Slack".to_string()), false),
+        clause(HistoryQueryTerm::Language("rust".to_string()), false),
+        clause(HistoryQueryTerm::Tag("work".to_string()), false),
+        clause(HistoryQueryTerm::Is(HistoryQueryFlag::Pinned), false),
+        clause(HistoryQueryTerm::Window("Inbox".to_string()), false),
+        clause(HistoryQueryTerm::Word("deploy".to_string()), false),
+      ]
+    );
+  }
+
+  #[test]
+  fn words_before_a_colon_that_are_not_fields_are_text() {
+    assert_eq!(
+      parse_history_query("https://example.com TODO:").unwrap(),
+      vec![
+        clause(
+          HistoryQueryTerm::Word("https://example.com".to_string()),
+          false
+        ),
+        clause(HistoryQueryTerm::Word("TODO:".to_string()), false),
+      ]
+    );
+  }
+
+  #[test]
+  fn parses_quoted_phrases_and_field_values() {
+    assert_eq!(
+      parse_history_query(r#"app:"Google Chrome" "exact phrase" "say ""hi""""#).unwrap(),
+      vec![
+        clause(HistoryQueryTerm::App("Google Chrome".to_string()), false),
+        clause(HistoryQueryTerm::Phrase("exact phrase".to_string()), false),
+        clause(HistoryQueryTerm::Phrase(r#"say "hi""#.to_string()), false),
+      ]
+    );
+  }
+
+  #[test]
+  fn parses_negated_clauses() {
+    assert_eq!(
+      parse_history_query(r#"-word -is:image -"two words""#).unwrap(),
+      vec![
+        clause(HistoryQueryTerm::Word("word".to_string()), true),
+        clause(HistoryQueryTerm::Is(HistoryQueryFlag::Image), true),
+        clause(HistoryQueryTerm::Phrase("two words".to_string()), true),
+      ]
+    );
+  }
+
+  #[test]
+  fn dashes_before_punctuation_are_text() {
+    assert_eq!(
+      parse_history_query("-> --force -").unwrap(),
+      vec![
+        clause(HistoryQueryTerm::Substring("->".to_string()), false),
+        clause(HistoryQueryTerm::Word("--force".to_string()), false),
+        clause(HistoryQueryTerm::Substring("-".to_string()), false),
+      ]
+    );
+  }
+
+  #[test]
+  fn reports_an_unclosed_quote() {
+    assert_eq!(
+      parse_history_query(r#"deploy "not closed"#),
+      Err(HistoryQueryError::new("Missing closing quote", Some(7)))
+    );
+    assert_eq!(
+      parse_history_query(r#"app:"Google"#).unwrap_err().position,
+      Some(4)
+    );
+  }
+
+  #[test]
+  fn reports_missing_and_unknown_field_values() {
+    assert_eq!(
+      parse_history_query("app:").unwrap_err(),
+      HistoryQueryError::new("Missing value for 'app:'", Some(4))
+    );
+
+    let error = parse_history_query("is:purple").unwrap_err();
+    assert!(error
+      .message
+      .starts_with("Unknown value 'purple' for 'is:'"));
+    assert_eq!(error.position, Some(3));
+  }
+
+  #[test]
+  fn parses_times() {
+    let now = Utc::now().timestamp_millis();
+
+    match parse_history_query("after:2h").unwrap()[0].term {
+      HistoryQueryTerm::After(timestamp) => {
+        assert!((now - 2 * 60 * 60 * 1000 - timestamp).abs() < 60 * 1000)
+      }
+      ref term => panic!("unexpected term {:?}", term),
+    }
+
+    assert!(matches!(
+      parse_history_query("before:2025-01-31").unwrap()[0].term,
+      HistoryQueryTerm::Before(_)
+    ));
+  }
+
+  #[test]
+  fn reports_negative_relative_times_as_invalid() {
+    for query in ["after:-5d", "before:-1w", "after:-0h"] {
+      let error = parse_history_query(query).unwrap_err();
+      assert!(
+        error.message.starts_with("Invalid time"),
+        "{}: {}",
+        query,
+        error
+      );
+    }
+  }
+
+  #[test]
+  fn reports_invalid_times_without_panicking() {
+    for time_value in [
+      "7x",
+      "d",
+      "7dé",
+      "é",
+      "7€",
+      "2025-13-01",
+      "9223372036854775807w",
+      "153722867280912930m",
+      "106751991167300d",
+      "-9223372036854775807d",
+    ] {
+      let error = parse_history_query(&format!("after:{}", time_value)).unwrap_err();
+      assert!(
+        error.message.starts_with("Invalid time"),
+        "{}: {}",
+        time_value,
+        error
+      );
+    }
+  }
+}
@@ -0,0 +1,266 @@
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::BigInt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use tauri::{Manager, Runtime};
+
+use crate::db::{self, establish_pool_db_connection};
+use crate::models::Setting;
+use crate::schema::clipboard_history::dsl::*;
+use crate::services::history_service;
+use crate::services::utils::debug_output;
+
+const BYTES_IN_MB: u64 = 1024 * 1024;
+
+// Bytes stored in the row itself, images on disk are added separately
+const HISTORY_ROW_SIZE_SQL: &str = "COALESCE(LENGTH(CAST(value AS BLOB)), 0) \
+  + COALESCE(LENGTH(CAST(value_html AS BLOB)), 0) \
+  + COALESCE(LENGTH(CAST(value_rtf AS BLOB)), 0) \
+  + COALESCE(LENGTH(CAST(image_data_url AS BLOB)), 0) \
+  + COALESCE(LENGTH(image_data_low_res), 0)";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryQuotaOptions {
+  pub max_items: Option<u64>,
+  pub max_image_bytes: Option<u64>,
+  pub max_total_bytes: Option<u64>,
+  pub keep_pinned: bool,
+  pub keep_starred: bool,
+}
+
+impl HistoryQuotaOptions {
+  /// Quotas are off unless set to a positive value. Pinned and starred entries are kept
+  /// with the same settings as the auto-clear job.
+  pub fn from_settings(settings_map: &HashMap<String, Setting>) -> Self {
+    let get_positive_int = |setting_name: &str| {
+      settings_map
+        .get(setting_name)
+        .and_then(|s| s.value_int)
+        .filter(|setting_value| *setting_value > 0)
+        .map(|setting_value| setting_value as u64)
+    };
+
+    HistoryQuotaOptions {
+      max_items: get_positive_int("historyQuotaMaxItems"),
+      max_image_bytes: get_positive_int("historyQuotaMaxImagesMb").map(|mb| mb * BYTES_IN_MB),
+      max_total_bytes: get_positive_int("historyQuotaMaxTotalMb").map(|mb| mb * BYTES_IN_MB),
+      keep_pinned: settings_map
+        .get("isKeepPinnedOnClearEnabled")
+        .and_then(|s| s.value_bool)
+        .unwrap_or(false),
+      keep_starred: settings_map
+        .get("isKeepStarredOnClearEnabled")
+        .and_then(|s| s.value_bool)
+        .unwrap_or(false),
+    }
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.max_items.is_some() || self.max_image_bytes.is_some() || self.max_total_bytes.is_some()
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEvictionReport {
+  pub evicted_history_ids: Vec<String>,
+  pub evicted_count: usize,
+  pub freed_bytes: u64,
+  pub freed_image_bytes: u64,
+  pub remaining_count: usize,
+  pub remaining_bytes: u64,
+  pub remaining_image_bytes: u64,
+}
+
+struct HistorySize {
+  history_id: String,
+  is_protected: bool,
+  row_bytes: u64,
+  image_bytes: u64,
+}
+
+struct HistoryTotals {
+  count: u64,
+  total_bytes: u64,
+  image_bytes: u64,
+}
+
+impl HistoryTotals {
+  fn is_over_quota(&self, options: &HistoryQuotaOptions) -> bool {
+    options.max_items.map_or(false, |max| self.count > max)
+      || options
+        .max_total_bytes
+        .map_or(false, |max| self.total_bytes > max)
+      || options
+        .max_image_bytes
+        .map_or(false, |max| self.image_bytes > max)
+  }
+}
+
+/// Evicts least recently used history entries until every configured quota is met.
+/// When only the image quota is exceeded, only entries with an image are evicted.
+pub fn enforce_history_quotas(
+  options: &HistoryQuotaOptions,
+) -> Result<HistoryEvictionReport, Error> {
+  let mut report = HistoryEvictionReport::default();
+
+  if !options.is_enabled() {
+    return Ok(report);
+  }
+
+  backfill_history_image_sizes()?;
+
+  let totals = load_history_totals()?;
+
+  let mut remaining_count = totals.count;
+  let mut remaining_bytes = totals.total_bytes;
+  let mut remaining_image_bytes = totals.image_bytes;
+
+  if totals.is_over_quota(options) {
+    for size in load_history_sizes(options)?
+      .iter()
+      .filter(|size| !size.is_protected)
+    {
+      let is_over_items = options.max_items.map_or(false, |max| remaining_count > max);
+      let is_over_total = options
+        .max_total_bytes
+        .map_or(false, |max| remaining_bytes > max);
+      let is_over_images = options
+        .max_image_bytes
+        .map_or(false, |max| remaining_image_bytes > max);
+
+      if !is_over_items && !is_over_total && !is_over_images {
+        break;
+      }
+
+      if !is_over_items && !is_over_total && size.image_bytes == 0 {
+        continue;
+      }
+
+      remaining_count -= 1;
+      remaining_bytes -= size.row_bytes + size.image_bytes;
+      remaining_image_bytes -= size.image_bytes;
+
+      report.freed_bytes += size.row_bytes + size.image_bytes;
+      report.freed_image_bytes += size.image_bytes;
+      report.evicted_history_ids.push(size.history_id.clone());
+    }
+  }
+
+  if !report.evicted_history_ids.is_empty() {
+    history_service::delete_clipboard_history_by_ids(&report.evicted_history_ids);
+  }
+
+  report.evicted_count = report.evicted_history_ids.len();
+  report.remaining_count = remaining_count as usize;
+  report.remaining_bytes = remaining_bytes;
+  report.remaining_image_bytes = remaining_image_bytes;
+
+  Ok(report)
+}
+
+/// Enforces the quotas from settings and reports evicted entries to the frontend.
+/// Callers refresh the history list themselves after the insert that triggered it.
+pub fn enforce_history_quotas_and_notify<R: Runtime>(
+  app_handle: &tauri::AppHandle<R>,
+  settings_map: &HashMap<String, Setting>,
+) {
+  let options = HistoryQuotaOptions::from_settings(settings_map);
+
+  if !options.is_enabled() {
+    return;
+  }
+
+  match enforce_history_quotas(&options) {
+    Ok(report) if report.evicted_count > 0 => {
+      debug_output(|| {
+        println!(
+          "History quota evicted {} items, freed {} bytes",
+          report.evicted_count, report.freed_bytes
+        );
+      });
+
+      let _ = app_handle.emit_all("clipboard://clipboard-monitor/quota-evicted", &report);
+    }
+    Ok(_) => {}
+    Err(e) => eprintln!("Error enforcing history quotas: {}", e),
+  }
+}
+
+/// Saves the file size of images captured before sizes were stored, missing files count as 0
+fn backfill_history_image_sizes() -> Result<(), Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let missing_sizes = clipboard_history
+    .filter(image_path_full_res.is_not_null())
+    .filter(image_size.is_null())
+    .select((history_id, image_path_full_res.assume_not_null()))
+    .load::<(String, String)>(connection)?;
+
+  for (_history_id, _image_path_full_res) in missing_sizes {
+    let file_size = fs::metadata(db::to_absolute_image_path(&_image_path_full_res))
+      .map_or(0, |metadata| metadata.len() as i64);
+
+    diesel::update(clipboard_history.filter(history_id.eq(&_history_id)))
+      .set(image_size.eq(Some(file_size)))
+      .execute(connection)?;
+  }
+
+  Ok(())
+}
+
+/// Counts history entries and sums their stored sizes in the database
+fn load_history_totals() -> Result<HistoryTotals, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let (count, total_bytes, image_bytes) = clipboard_history
+    .select((
+      sql::<BigInt>("COUNT(*)"),
+      sql::<BigInt>(&format!(
+        "COALESCE(SUM({} + COALESCE(image_size, 0)), 0)",
+        HISTORY_ROW_SIZE_SQL
+      )),
+      sql::<BigInt>("COALESCE(SUM(image_size), 0)"),
+    ))
+    .first::<(i64, i64, i64)>(connection)?;
+
+  Ok(HistoryTotals {
+    count: count.max(0) as u64,
+    total_bytes: total_bytes.max(0) as u64,
+    image_bytes: image_bytes.max(0) as u64,
+  })
+}
+
+/// Loads every history entry with its stored size, least recently used first
+fn load_history_sizes(options: &HistoryQuotaOptions) -> Result<Vec<HistorySize>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let rows = clipboard_history
+    .select((
+      history_id,
+      is_pinned,
+      is_favorite,
+      image_size,
+      sql::<BigInt>(HISTORY_ROW_SIZE_SQL),
+    ))
+    .order((updated_at.asc(), history_id.asc()))
+    .load::<(String, Option<bool>, Option<bool>, Option<i64>, i64)>(connection)?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(
+        |(_history_id, _is_pinned, _is_favorite, _image_size, row_bytes)| HistorySize {
+          history_id: _history_id,
+          is_protected: (options.keep_pinned && _is_pinned == Some(true))
+            || (options.keep_starred && _is_favorite == Some(true)),
+          row_bytes: row_bytes.max(0) as u64,
+          image_bytes: _image_size.unwrap_or(0).max(0) as u64,
+        },
+      )
+      .collect(),
+  )
+}
@@ -0,0 +1,521 @@
+use diesel::connection::DefaultLoadingMode;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{BigInt, Bool, Double, Integer, Nullable, Text};
+use std::collections::HashMap;
+
+use crate::db::establish_pool_db_connection;
+use crate::models::Setting;
+use crate::schema::{clipboard_history, link_metadata};
+
+pub const SEARCH_MODE_TEXT: &str = "text";
+pub const SEARCH_MODE_FUZZY: &str = "fuzzy";
+
+pub const SNIPPET_MATCH_START: &str = "<mark>";
+pub const SNIPPET_MATCH_END: &str = "</mark>";
+/// Private use characters SQLite puts around matches, swapped for the tags once the snippet
+/// text is escaped
+const SNIPPET_MARKER_START: char = '\u{E000}';
+const SNIPPET_MARKER_END: char = '\u{E001}';
+const SNIPPET_ELLIPSIS: &str = "…";
+const SNIPPET_MAX_TOKENS: i32 = 16;
+
+/// Most relevant full-text matches loaded for a search sorted by relevance, filters are applied
+/// to these. Other sorts filter by every match with `HISTORY_FTS_FILTER_SQL`.
+const MAX_TEXT_MATCHES: i64 = 5000;
+
+/// Keeps history entries matching a full-text query, bind the query and close with `)`
+pub const HISTORY_FTS_FILTER_SQL: &str = "clipboard_history.rowid IN \
+  (SELECT rowid FROM clipboard_history_fts WHERE clipboard_history_fts MATCH ";
+
+// Ranks of the matches of the current search, kept on the connection that runs the search so
+// the history query can filter and sort by them before its limit
+const CREATE_SEARCH_RANKS_SQL: &str = "CREATE TEMP TABLE IF NOT EXISTS history_search_ranks \
+  (history_id TEXT PRIMARY KEY NOT NULL, rank REAL NOT NULL)";
+const CLEAR_SEARCH_RANKS_SQL: &str = "DELETE FROM temp.history_search_ranks";
+const INSERT_SEARCH_RANKS_SQL: &str = "INSERT OR IGNORE INTO temp.history_search_ranks \
+  (history_id, rank) \
+  SELECT json_extract(value, '$[0]'), json_extract(value, '$[1]') FROM json_each(?)";
+
+/// Keeps history entries with a rank in `history_search_ranks`
+pub const SEARCH_RANK_FILTER_SQL: &str =
+  "clipboard_history.history_id IN (SELECT history_id FROM temp.history_search_ranks)";
+
+/// Rank of a history entry in `history_search_ranks`, lower is more relevant
+pub const SEARCH_RANK_SQL: &str = "(SELECT ranks.rank FROM temp.history_search_ranks AS ranks \
+  WHERE ranks.history_id = clipboard_history.history_id)";
+
+// Column weights follow the column order of clipboard_history_fts: value, title, window title
+const HISTORY_TEXT_MATCHES_SQL: &str = "SELECT clipboard_history.history_id AS history_id, \
+  bm25(clipboard_history_fts, 1.0, 2.0, 0.5) AS rank, \
+  snippet(clipboard_history_fts, -1, ?, ?, ?, ?) AS snippet \
+  FROM clipboard_history_fts \
+  JOIN clipboard_history ON clipboard_history.rowid = clipboard_history_fts.rowid \
+  WHERE clipboard_history_fts MATCH ? \
+  ORDER BY rank \
+  LIMIT ?";
+
+const HISTORY_TEXT_MATCHES_BY_IDS_SQL: &str = "SELECT clipboard_history.history_id AS history_id, \
+  bm25(clipboard_history_fts, 1.0, 2.0, 0.5) AS rank, \
+  snippet(clipboard_history_fts, -1, ?, ?, ?, ?) AS snippet \
+  FROM clipboard_history_fts \
+  JOIN clipboard_history ON clipboard_history.rowid = clipboard_history_fts.rowid \
+  WHERE clipboard_history_fts MATCH ? \
+  AND clipboard_history.history_id IN (SELECT value FROM json_each(?))";
+
+const DEFAULT_FUZZY_CUTOFF: i32 = 60;
+
+/// Most similar fuzzy matches loaded for one search, filters are applied to these
+const MAX_FUZZY_MATCHES: usize = 1000;
+
+/// Title, start of the value and link titles of an entry, only the start of long values is
+/// compared which keeps fuzzy search fast on large histories
+const FUZZY_TEXT_SQL: &str = "COALESCE(clipboard_history.title, '') || ' ' || \
+  COALESCE(substr(clipboard_history.value, 1, 500), '') || ' ' || \
+  COALESCE(link_metadata.link_title, '') || ' ' || \
+  COALESCE(link_metadata.link_track_title, '')";
+
+/// Longer search words are only matched by prefix or substring
+const FUZZY_MAX_WORD_CHARS: usize = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistorySearchMode {
+  /// Words are looked up in the full-text index
+  Text,
+  /// Typo tolerant matching of words in the value, title and link titles
+  Fuzzy,
+}
+
+impl HistorySearchMode {
+  pub fn from_name(search_mode: Option<&str>) -> Self {
+    match search_mode {
+      Some(SEARCH_MODE_FUZZY) => HistorySearchMode::Fuzzy,
+      _ => HistorySearchMode::Text,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzySearchOptions {
+  /// Lowest similarity from 0 to 1 for an entry to match
+  pub cutoff: f64,
+}
+
+impl FuzzySearchOptions {
+  /// The cutoff is set in percent with `historyFuzzySearchCutoff`
+  pub fn from_settings(settings_map: &HashMap<String, Setting>) -> Self {
+    FuzzySearchOptions {
+      cutoff: settings_map
+        .get("historyFuzzySearchCutoff")
+        .and_then(|s| s.value_int)
+        .unwrap_or(DEFAULT_FUZZY_CUTOFF)
+        .clamp(1, 100) as f64
+        / 100.0,
+    }
+  }
+}
+
+#[derive(QueryableByName, Debug, Clone, PartialEq)]
+pub struct HistoryTextMatch {
+  #[diesel(sql_type = Text)]
+  pub history_id: String,
+  /// Lower is more relevant, BM25 rank for full-text matches and one minus the similarity
+  /// for fuzzy matches
+  #[diesel(sql_type = Double)]
+  pub rank: f64,
+  /// Best matching fragment escaped for HTML, with matches wrapped in `SNIPPET_MATCH_START` and
+  /// `SNIPPET_MATCH_END`
+  #[diesel(sql_type = Nullable<Text>)]
+  pub snippet: Option<String>,
+}
+
+/// Builds an FTS5 query where every word of the search has to match, the last word of a quoted
+/// group matches as a prefix. Returns `None` when the search has no words to look up, such as
+/// a search for punctuation only.
+pub fn to_fts_query(query: &str) -> Option<String> {
+  let terms: Vec<String> = query
+    .split_whitespace()
+    .filter(|term| term.chars().any(char::is_alphanumeric))
+    .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+    .collect();
+
+  Some(terms.join(" ")).filter(|_| !terms.is_empty())
+}
+
+/// Finds history entries matching the full-text query, most relevant first
+pub fn find_history_text_matches(fts_query: &str) -> Result<Vec<HistoryTextMatch>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let text_matches = diesel::sql_query(HISTORY_TEXT_MATCHES_SQL)
+    .bind::<Text, _>(SNIPPET_MARKER_START.to_string())
+    .bind::<Text, _>(SNIPPET_MARKER_END.to_string())
+    .bind::<Text, _>(SNIPPET_ELLIPSIS)
+    .bind::<Integer, _>(SNIPPET_MAX_TOKENS)
+    .bind::<Text, _>(fts_query)
+    .bind::<BigInt, _>(MAX_TEXT_MATCHES)
+    .load::<HistoryTextMatch>(connection)?;
+
+  Ok(with_snippet_html(text_matches))
+}
+
+/// Finds the ranks and snippets of the given history entries for the full-text query, used for
+/// a page of results that was filtered with `HISTORY_FTS_FILTER_SQL`
+pub fn find_history_text_matches_by_ids(
+  connection: &mut SqliteConnection,
+  fts_query: &str,
+  history_ids: &[String],
+) -> Result<Vec<HistoryTextMatch>, Error> {
+  let history_ids_json = serde_json::to_string(history_ids).unwrap_or_else(|_| "[]".to_string());
+
+  let text_matches = diesel::sql_query(HISTORY_TEXT_MATCHES_BY_IDS_SQL)
+    .bind::<Text, _>(SNIPPET_MARKER_START.to_string())
+    .bind::<Text, _>(SNIPPET_MARKER_END.to_string())
+    .bind::<Text, _>(SNIPPET_ELLIPSIS)
+    .bind::<Integer, _>(SNIPPET_MAX_TOKENS)
+    .bind::<Text, _>(fts_query)
+    .bind::<Text, _>(history_ids_json)
+    .load::<HistoryTextMatch>(connection)?;
+
+  Ok(with_snippet_html(text_matches))
+}
+
+/// Checks whether any history entry matches the full-text query
+pub fn has_history_text_matches(
+  connection: &mut SqliteConnection,
+  fts_query: &str,
+) -> Result<bool, Error> {
+  let first_match = clipboard_history::table
+    .filter(
+      sql::<Bool>(HISTORY_FTS_FILTER_SQL)
+        .bind::<Text, _>(fts_query)
+        .sql(")"),
+    )
+    .select(clipboard_history::history_id)
+    .first::<String>(connection)
+    .optional()?;
+
+  Ok(first_match.is_some())
+}
+
+fn with_snippet_html(text_matches: Vec<HistoryTextMatch>) -> Vec<HistoryTextMatch> {
+  text_matches
+    .into_iter()
+    .map(|text_match| HistoryTextMatch {
+      snippet: text_match.snippet.as_deref().map(to_snippet_html),
+      ..text_match
+    })
+    .collect()
+}
+
+/// Escapes the snippet text for HTML and replaces the SQLite match markers with
+/// `SNIPPET_MATCH_START` and `SNIPPET_MATCH_END`, so text of the entry cannot add markup
+fn to_snippet_html(snippet: &str) -> String {
+  let mut snippet_html = String::with_capacity(snippet.len());
+
+  for c in snippet.chars() {
+    match c {
+      SNIPPET_MARKER_START => snippet_html.push_str(SNIPPET_MATCH_START),
+      SNIPPET_MARKER_END => snippet_html.push_str(SNIPPET_MATCH_END),
+      '&' => snippet_html.push_str("&amp;"),
+      '<' => snippet_html.push_str("&lt;"),
+      '>' => snippet_html.push_str("&gt;"),
+      '"' => snippet_html.push_str("&quot;"),
+      '\'' => snippet_html.push_str("&#39;"),
+      _ => snippet_html.push(c),
+    }
+  }
+
+  snippet_html
+}
+
+/// Replaces the ranks in `history_search_ranks` on this connection with the ranks of the matches
+pub fn store_search_ranks(
+  connection: &mut SqliteConnection,
+  text_matches: &[HistoryTextMatch],
+) -> Result<(), Error> {
+  let ranks: Vec<(&str, f64)> = text_matches
+    .iter()
+    .map(|text_match| (text_match.history_id.as_str(), text_match.rank))
+    .collect();
+  let ranks_json = serde_json::to_string(&ranks).unwrap_or_else(|_| "[]".to_string());
+
+  diesel::sql_query(CREATE_SEARCH_RANKS_SQL).execute(connection)?;
+  diesel::sql_query(CLEAR_SEARCH_RANKS_SQL).execute(connection)?;
+  diesel::sql_query(INSERT_SEARCH_RANKS_SQL)
+    .bind::<Text, _>(ranks_json)
+    .execute(connection)?;
+
+  Ok(())
+}
+
+/// Scores recent history entries against the search words and returns the best matches first.
+/// Each search word takes its best similarity with a word of the title, the start of the value
+/// or the link titles, and the entry similarity is the average over the search words. Every
+/// entry sharing a letter pair with a search word is scored, so older entries of large histories
+/// are found too.
+pub fn find_history_fuzzy_matches(
+  query: &str,
+  options: &FuzzySearchOptions,
+) -> Result<Vec<HistoryTextMatch>, Error> {
+  let query_words: Vec<Vec<char>> = query
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(|word| word.to_lowercase().chars().collect())
+    .collect();
+
+  if query_words.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let connection = &mut establish_pool_db_connection();
+
+  let mut query_builder = clipboard_history::table
+    .left_join(
+      link_metadata::table
+        .on(link_metadata::history_id.eq(clipboard_history::history_id.nullable())),
+    )
+    .select((clipboard_history::history_id, sql::<Text>(FUZZY_TEXT_SQL)))
+    .order((
+      clipboard_history::updated_at.desc(),
+      clipboard_history::history_id.desc(),
+    ))
+    .into_boxed();
+
+  for pattern in fuzzy_prefilter_patterns(&query_words) {
+    query_builder = query_builder.or_filter(sql::<Text>(FUZZY_TEXT_SQL).like(pattern));
+  }
+
+  let mut fuzzy_matches: Vec<HistoryTextMatch> = Vec::new();
+
+  let rows = query_builder.load_iter::<(String, String), DefaultLoadingMode>(connection)?;
+
+  for row in rows {
+    let (_history_id, text) = row?;
+    let similarity = fuzzy_similarity(&query_words, &text.to_lowercase());
+
+    if similarity >= options.cutoff {
+      fuzzy_matches.push(HistoryTextMatch {
+        history_id: _history_id,
+        rank: 1.0 - similarity,
+        snippet: None,
+      });
+
+      // Only the best matches are kept, so matching most of a large history stays small
+      if fuzzy_matches.len() >= 2 * MAX_FUZZY_MATCHES {
+        fuzzy_matches.sort_by(|a, b| a.rank.total_cmp(&b.rank));
+        fuzzy_matches.truncate(MAX_FUZZY_MATCHES);
+      }
+    }
+  }
+
+  fuzzy_matches.sort_by(|a, b| a.rank.total_cmp(&b.rank));
+  fuzzy_matches.truncate(MAX_FUZZY_MATCHES);
+
+  Ok(fuzzy_matches)
+}
+
+/// LIKE patterns for the letter pairs of the search words, most typos leave a pair of the word
+/// in place. Words of one or two letters only match as a prefix or substring, so the whole
+/// word is looked up.
+fn fuzzy_prefilter_patterns(query_words: &[Vec<char>]) -> Vec<String> {
+  let mut patterns: Vec<String> = query_words
+    .iter()
+    .flat_map(|query_word| {
+      if query_word.len() <= 2 {
+        vec![query_word.iter().collect::<String>()]
+      } else {
+        query_word
+          .windows(2)
+          .map(|pair| pair.iter().collect::<String>())
+          .collect()
+      }
+    })
+    .map(|part| format!("%{}%", part))
+    .collect();
+
+  patterns.sort();
+  patterns.dedup();
+  patterns
+}
+
+fn fuzzy_similarity(query_words: &[Vec<char>], text: &str) -> f64 {
+  let mut best_similarities = vec![0.0; query_words.len()];
+  let mut word: Vec<char> = Vec::new();
+
+  for text_word in text
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|text_word| !text_word.is_empty())
+  {
+    word.clear();
+    word.extend(text_word.chars());
+
+    for (query_word, best_similarity) in query_words.iter().zip(best_similarities.iter_mut()) {
+      if *best_similarity < 1.0 {
+        *best_similarity = f64::max(*best_similarity, word_similarity(query_word, &word));
+      }
+    }
+  }
+
+  best_similarities.iter().sum::<f64>() / query_words.len() as f64
+}
+
+/// Similarity from 0 to 1 of a search word and a word of the entry. Exact and prefix matches
+/// score highest, then substrings, then words within one or two typos of the search word or of
+/// the start of the entry word. A typo in the first letter is not matched.
+fn word_similarity(query_word: &[char], word: &[char]) -> f64 {
+  if word.starts_with(query_word) {
+    return if word.len() == query_word.len() {
+      1.0
+    } else {
+      0.95
+    };
+  }
+
+  if word.len() > query_word.len()
+    && word
+      .windows(query_word.len())
+      .any(|part| part == query_word)
+  {
+    return 0.85;
+  }
+
+  let max_edits = match query_word.len() {
+    0..=2 => return 0.0,
+    3..=5 => 1,
+    _ => 2,
+  };
+
+  if query_word.len() > FUZZY_MAX_WORD_CHARS
+    || word.len() + max_edits < query_word.len()
+    || !word.iter().take(max_edits + 1).any(|c| *c == query_word[0])
+  {
+    return 0.0;
+  }
+
+  let compared_len = word.len().min(query_word.len() + max_edits);
+
+  prefix_edit_distances(query_word, &word[..compared_len])
+    .into_iter()
+    .enumerate()
+    .skip(1)
+    .filter(|(_, distance)| *distance <= max_edits)
+    .map(|(prefix_len, distance)| {
+      let similarity = 1.0 - distance as f64 / query_word.len().max(prefix_len) as f64;
+
+      if prefix_len == word.len() {
+        0.9 * similarity
+      } else {
+        0.8 * similarity
+      }
+    })
+    .fold(0.0, f64::max)
+}
+
+/// Optimal string alignment distances from the search word to every prefix of the word,
+/// the value at index `n` is the distance to the first `n` characters
+fn prefix_edit_distances(query_word: &[char], word: &[char]) -> Vec<usize> {
+  let mut before_previous_row = vec![0; word.len() + 1];
+  let mut previous_row: Vec<usize> = (0..=word.len()).collect();
+  let mut current_row = vec![0; word.len() + 1];
+
+  for i in 1..=query_word.len() {
+    current_row[0] = i;
+
+    for j in 1..=word.len() {
+      let substitution_cost = if query_word[i - 1] == word[j - 1] {
+        0
+      } else {
+        1
+      };
+      let mut distance = (previous_row[j] + 1)
+        .min(current_row[j - 1] + 1)
+        .min(previous_row[j - 1] + substitution_cost);
+
+      if i > 1 && j > 1 && query_word[i - 1] == word[j - 2] && query_word[i - 2] == word[j - 1] {
+        distance = distance.min(before_previous_row[j - 2] + 1);
+      }
+
+      current_row[j] = distance;
+    }
+
+    std::mem::swap(&mut before_previous_row, &mut previous_row);
+    std::mem::swap(&mut previous_row, &mut current_row);
+  }
+
+  previous_row
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn chars(text: &str) -> Vec<char> {
+    text.chars().collect()
+  }
+
+  #[test]
+  fn word_similarity_ranks_exact_prefix_and_substring_matches() {
+    assert_eq!(word_similarity(&chars("deploy"), &chars("deploy")), 1.0);
+    assert_eq!(word_similarity(&chars("dep"), &chars("deploy")), 0.95);
+    assert_eq!(word_similarity(&chars("ploy"), &chars("deploy")), 0.85);
+  }
+
+  #[test]
+  fn word_similarity_matches_typos() {
+    let missing_letter = word_similarity(&chars("kubctl"), &chars("kubectl"));
+    assert!(missing_letter >= 0.6 && missing_letter < 0.85);
+
+    let swapped_letters = word_similarity(&chars("depoly"), &chars("deploy"));
+    assert!(swapped_letters >= 0.6 && swapped_letters < 0.85);
+
+    assert!(word_similarity(&chars("kubectl"), &chars("kubectlx")) > 0.9);
+  }
+
+  #[test]
+  fn word_similarity_rejects_distant_words() {
+    assert_eq!(word_similarity(&chars("xubectl"), &chars("kubectl")), 0.0);
+    assert_eq!(word_similarity(&chars("ab"), &chars("ba")), 0.0);
+    assert_eq!(word_similarity(&chars("kubectl"), &chars("docker")), 0.0);
+    assert_eq!(word_similarity(&chars("cat"), &chars("cxy")), 0.0);
+  }
+
+  #[test]
+  fn prefix_edit_distances_covers_every_prefix() {
+    assert_eq!(
+      prefix_edit_distances(&chars("kubctl"), &chars("kubectl")),
+      vec![6, 5, 4, 3, 3, 3, 2, 1]
+    );
+    assert_eq!(prefix_edit_distances(&chars("abc"), &chars("")), vec![3]);
+  }
+
+  #[test]
+  fn prefix_edit_distances_counts_a_transposition_as_one_edit() {
+    assert_eq!(
+      prefix_edit_distances(&chars("ab"), &chars("ba")),
+      vec![2, 1, 1]
+    );
+    assert_eq!(
+      *prefix_edit_distances(&chars("depoly"), &chars("deploy"))
+        .last()
+        .unwrap(),
+      1
+    );
+  }
+
+  #[test]
+  fn fuzzy_prefilter_patterns_use_letter_pairs() {
+    assert_eq!(
+      fuzzy_prefilter_patterns(&[chars("kubctl"), chars("go"), chars("kub")]),
+      vec!["%bc%", "%ct%", "%go%", "%ku%", "%tl%", "%ub%"]
+    );
+  }
+
+  #[test]
+  fn snippet_html_escapes_the_entry_text() {
+    assert_eq!(
+      to_snippet_html("\u{E000}a\u{E001} <b>&\"'"),
+      "<mark>a</mark> &lt;b&gt;&amp;&quot;&#39;"
+    );
+  }
+}
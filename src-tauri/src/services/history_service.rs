@@ -1,19 +1,38 @@
-use crate::clipboard::LanguageDetectOptions;
-use diesel::dsl::select;
-
-use lazy_static::lazy_static;
+use crate::clipboard::{
+  CaptureSource, LanguageDetectOptions, RichTextFormats, SOURCE_SELECTION_CLIPBOARD,
+  SOURCE_SELECTION_PRIMARY,
+};
+use diesel::connection::DefaultLoadingMode;
+use diesel::dsl::{select, sql};
+use diesel::sql_types::{Bool, Double, Nullable, Text};
 
 use crate::db::establish_pool_db_connection;
-use crate::models::models::{LinkMetadata, UpdatedHistoryData};
+use crate::models::models::{ClipboardHistoryFile, LinkMetadata, UpdatedHistoryData};
 use crate::models::{ClipboardHistory, Setting};
-use crate::services::link_metadata_service::{
-  delete_all_link_metadata_with_history_ids, delete_link_metadata_by_history_ids,
+use crate::services::capture_rules_service::{
+  evaluate_capture_rules, move_history_item_to_board, CaptureRuleContext, CaptureRuleOutcome,
+};
+use crate::services::history_dedupe::DedupePolicy;
+use crate::services::history_query_service::{history_query_filters, HistoryQueryClause};
+use crate::services::history_search_service::{
+  find_history_fuzzy_matches, find_history_text_matches, find_history_text_matches_by_ids,
+  has_history_text_matches, store_search_ranks, to_fts_query, FuzzySearchOptions,
+  HistorySearchMode, HistoryTextMatch, HISTORY_FTS_FILTER_SQL, SEARCH_RANK_FILTER_SQL,
+  SEARCH_RANK_SQL,
+};
+use crate::services::image_storage::ImageStorageOptions;
+use crate::services::link_metadata_service::delete_link_metadata_by_history_ids;
+use crate::services::regex_search_service::{
+  scan_rows_for_matches, RegexSearchResult, SearchMatchRange, MAX_SEARCHED_VALUE_CHARS,
 };
+use crate::services::secret_detectors::detect_secrets;
+use crate::services::tags_service;
+use crate::services::usage_service::{self, HistorySortOrder};
 use arboard::ImageData;
 use image::GenericImageView;
 use linkify::LinkFinder;
 use regex::Regex;
-use serde_json::to_string;
+use serde_json::{json, to_string};
 
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{Duration, NaiveDateTime, Utc};
@@ -28,21 +47,26 @@ use nanoid::nanoid;
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
 use std::io::Cursor;
 
+use crate::cron_jobs;
 use crate::db::{self, APP_CONSTANTS};
 use crate::schema::clipboard_history;
 use crate::schema::clipboard_history::dsl::*;
+use crate::schema::clipboard_history_files;
+use crate::schema::history_tags;
 use crate::schema::link_metadata;
 use crate::schema::link_metadata::dsl::link_metadata as link_metadata_dsl;
+use crate::schema::usage_stats;
 
 use img_hash::{HasherConfig, ImageHash};
 
 use crate::services::utils::{
   debug_output, delete_file_and_maybe_parent, has_emoji, has_valid_tld, is_base64_image,
-  is_image_url, is_youtube_url, mask_value, remove_dir_if_exists,
+  is_image_url, is_youtube_url, mask_value,
 };
 
 use super::utils::is_valid_json;
@@ -52,18 +76,101 @@ use diesel::sqlite::Sqlite;
 
 type ImageHashSize = [u8; 8];
 
-lazy_static! {
-  pub static ref HISTORY_INSERT_COUNT: Mutex<i32> = Mutex::new(0);
+/// Images compared with a new capture when looking for a near duplicate, exact duplicates are
+/// found in the whole history
+const MAX_NEAR_DUPLICATE_IMAGE_CANDIDATES: i64 = 500;
+
+pub const CAPTURE_REASON_DUPLICATE: &str = "duplicate";
+pub const CAPTURE_REASON_NEAR_DUPLICATE: &str = "nearDuplicate";
+pub const CAPTURE_REASON_DOUBLE_COPY: &str = "doubleCopy";
+pub const CAPTURE_REASON_CAPTURE_RULE: &str = "captureRule";
+pub const CAPTURE_REASON_EMPTY: &str = "empty";
+pub const CAPTURE_REASON_TEXT_LENGTH: &str = "textLength";
+pub const CAPTURE_REASON_EXCLUSION_LIST: &str = "exclusionList";
+pub const CAPTURE_REASON_EXCLUDED_APP: &str = "excludedApp";
+
+/// What a clipboard capture did to history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryCaptureStatus {
+  /// A new history entry was created
+  Inserted,
+  /// The capture was merged into an existing entry
+  Updated,
+  /// The capture was merged into an existing entry and starred it
+  Starred,
+  /// Nothing was saved, `reason` tells which setting or rule excluded it
+  Excluded,
+  Failed,
 }
 
-pub fn increment_history_insert_count() {
-  let mut count = HISTORY_INSERT_COUNT.lock().unwrap();
-  *count += 1;
+/// Result of capturing clipboard content, emitted to the frontend as
+/// `clipboard://clipboard-monitor/captured`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryCaptureOutcome {
+  pub status: HistoryCaptureStatus,
+  pub history_id: Option<String>,
+  pub reason: Option<String>,
 }
 
-pub fn reset_history_insert_count() {
-  let mut count = HISTORY_INSERT_COUNT.lock().unwrap();
-  *count = 0;
+impl HistoryCaptureOutcome {
+  pub fn inserted(inserted_history_id: &str) -> Self {
+    HistoryCaptureOutcome {
+      status: HistoryCaptureStatus::Inserted,
+      history_id: Some(inserted_history_id.to_string()),
+      reason: None,
+    }
+  }
+
+  /// Outcome of merging a capture into `existing_history`. Starring by a capture rule or a
+  /// double copy is reported as `Starred` with that as the reason.
+  fn merged(
+    existing_history: &ClipboardHistory,
+    is_favorite_item: bool,
+    rule_outcome: &CaptureRuleOutcome,
+    duplicate_reason: &str,
+  ) -> Self {
+    let is_starred = is_favorite_item && existing_history.is_favorite != Some(true);
+
+    let (status, reason) = match (is_starred, rule_outcome.star) {
+      (true, true) => (HistoryCaptureStatus::Starred, CAPTURE_REASON_CAPTURE_RULE),
+      (true, false) => (HistoryCaptureStatus::Starred, CAPTURE_REASON_DOUBLE_COPY),
+      (false, _) => (HistoryCaptureStatus::Updated, duplicate_reason),
+    };
+
+    HistoryCaptureOutcome {
+      status,
+      history_id: Some(existing_history.history_id.clone()),
+      reason: Some(reason.to_string()),
+    }
+  }
+
+  pub fn excluded(reason: &str) -> Self {
+    HistoryCaptureOutcome {
+      status: HistoryCaptureStatus::Excluded,
+      history_id: None,
+      reason: Some(reason.to_string()),
+    }
+  }
+
+  pub fn failed(reason: &str) -> Self {
+    HistoryCaptureOutcome {
+      status: HistoryCaptureStatus::Failed,
+      history_id: None,
+      reason: Some(reason.to_string()),
+    }
+  }
+
+  /// Whether history changed and the frontend should refresh
+  pub fn is_history_changed(&self) -> bool {
+    matches!(
+      self.status,
+      HistoryCaptureStatus::Inserted
+        | HistoryCaptureStatus::Updated
+        | HistoryCaptureStatus::Starred
+    )
+  }
 }
 
 #[derive(Queryable, Serialize, Deserialize, Debug)]
@@ -99,14 +206,60 @@ pub struct RecentClipboardHistoryData {
   pub is_link: Option<bool>,
 }
 
+/// Position in the history ordered by recency, the next page starts after the entry with this
+/// `updated_at` and `history_id`. Without a `history_id` the page starts with the entries copied
+/// before `updated_at`, which jumps to a date. Only pages sorted by recency can be continued,
+/// a cursor with frecency or relevance sorting is rejected with `CURSOR_SORT_ORDER_ERROR`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryCursor {
+  pub updated_at: i64,
+  pub history_id: Option<String>,
+}
+
+type HistoryCursorFilter = diesel::dsl::Or<
+  diesel::dsl::Lt<updated_at, i64>,
+  diesel::dsl::And<diesel::dsl::Eq<updated_at, i64>, diesel::dsl::Lt<history_id, String>>,
+>;
+
+/// Entries after the cursor in the `(updated_at, history_id)` order, an empty id sorts before
+/// every id so only the time is compared
+fn history_cursor_filter(cursor: &HistoryCursor) -> HistoryCursorFilter {
+  updated_at.lt(cursor.updated_at).or(
+    updated_at
+      .eq(cursor.updated_at)
+      .and(history_id.lt(cursor.history_id.clone().unwrap_or_default())),
+  )
+}
+
+pub const CURSOR_SORT_ORDER_ERROR: &str =
+  "A cursor can only continue entries sorted by recency, not by frecency or search relevance";
+
+/// Cursors are positions in the `(updated_at, history_id)` order. Pages sorted by frecency or
+/// ranked by search relevance are in another order, so a cursor cannot continue them.
+fn check_cursor_sort_order(
+  cursor: Option<&HistoryCursor>,
+  is_sorted_by_recency: bool,
+) -> Result<(), Error> {
+  if cursor.is_some() && !is_sorted_by_recency {
+    return Err(Error::QueryBuilderError(CURSOR_SORT_ORDER_ERROR.into()));
+  }
+
+  Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ClipboardHistoryWithMetaData {
   pub history_id: String,
   pub history_options: Option<String>,
   pub copied_from_app: Option<String>,
+  pub copied_from_window_title: Option<String>,
+  pub copied_from_process_path: Option<String>,
   pub title: Option<String>,
   pub value: Option<String>,
+  pub has_html: bool,
+  pub has_rtf: bool,
   pub value_preview: Option<String>,
   pub value_more_preview_lines: Option<i32>,
   pub value_more_preview_chars: Option<i32>,
@@ -125,6 +278,11 @@ pub struct ClipboardHistoryWithMetaData {
   pub is_code: Option<bool>,
   pub is_link: Option<bool>,
   pub is_video: Option<bool>,
+  pub is_file: Option<bool>,
+  pub detected_secrets: Option<String>,
+  pub source_selection: Option<String>,
+  pub duplicate_count: i32,
+  pub image_format: Option<String>,
   pub has_emoji: Option<bool>,
   pub has_masked_words: Option<bool>,
   pub is_pinned: Option<bool>,
@@ -133,6 +291,11 @@ pub struct ClipboardHistoryWithMetaData {
   pub detected_language: Option<String>,
   pub pinned_order_number: Option<i32>,
   pub link_metadata: Option<LinkMetadata>,
+  pub use_count: i32,
+  pub last_used_at: Option<i64>,
+  pub search_rank: Option<f64>,
+  pub search_snippet: Option<String>,
+  pub search_match_ranges: Option<Vec<SearchMatchRange>>,
   pub created_at: i64,
   pub updated_at: i64,
   pub created_date: NaiveDateTime,
@@ -150,6 +313,8 @@ impl ClipboardHistoryWithMetaData {
       history_id: history.history_id,
       title: history.title,
       value: history.value,
+      has_html: history.value_html.is_some(),
+      has_rtf: history.value_rtf.is_some(),
       value_preview: None,
       value_more_preview_lines: None,
       value_more_preview_chars: None,
@@ -168,6 +333,11 @@ impl ClipboardHistoryWithMetaData {
       is_code: history.is_code,
       is_link: history.is_link,
       is_video: history.is_video,
+      is_file: history.is_file,
+      detected_secrets: history.detected_secrets,
+      source_selection: history.source_selection,
+      duplicate_count: history.duplicate_count,
+      image_format: history.image_format,
       has_emoji: history.has_emoji,
       has_masked_words: history.has_masked_words,
       is_pinned: history.is_pinned,
@@ -181,13 +351,44 @@ impl ClipboardHistoryWithMetaData {
       updated_date: history.updated_date,
       history_options: history.history_options,
       copied_from_app: history.copied_from_app,
+      copied_from_window_title: history.copied_from_window_title,
+      copied_from_process_path: history.copied_from_process_path,
       link_metadata,
+      use_count: 0,
+      last_used_at: None,
+      search_rank: None,
+      search_snippet: None,
+      search_match_ranges: None,
     };
 
     process_history_item(&mut history_with_metadata, auto_mask_words_list);
     history_with_metadata
   }
 
+  /// Adds how often and when the entry was last copied or pasted from PasteBar
+  pub fn with_usage(mut self, _use_count: Option<i32>, _last_used_at: Option<i64>) -> Self {
+    self.use_count = _use_count.unwrap_or(0);
+    self.last_used_at = _last_used_at;
+    self
+  }
+
+  /// Adds the full-text rank and snippet, masked entries get no snippet so it cannot reveal them
+  pub fn with_text_match(mut self, text_match: Option<&HistoryTextMatch>) -> Self {
+    if let Some(text_match) = text_match {
+      let is_masked_value = self.is_masked == Some(true) || self.has_masked_words == Some(true);
+
+      self.search_rank = Some(text_match.rank);
+      self.search_snippet = text_match.snippet.clone().filter(|_| !is_masked_value);
+    }
+    self
+  }
+
+  /// Adds regex match ranges in the value, dropped when masked words changed the value
+  pub fn with_match_ranges(mut self, match_ranges: Option<Vec<SearchMatchRange>>) -> Self {
+    self.search_match_ranges = match_ranges.filter(|_| self.has_masked_words != Some(true));
+    self
+  }
+
   /// Transforms the image_path_full_res field from relative to absolute path for frontend consumption
   pub fn transform_image_path_for_frontend(&mut self) {
     if let Some(ref mut path) = self.image_path_full_res {
@@ -207,11 +408,61 @@ pub fn get_source_apps_list() -> Result<Vec<Option<String>>, Error> {
   Ok(source_apps)
 }
 
+/// Distinct window titles history was copied from, limited to the given apps when not empty
+pub fn get_source_window_titles_list(app_names: &Vec<String>) -> Result<Vec<String>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let mut query_builder = clipboard_history
+    .select(copied_from_window_title)
+    .filter(copied_from_window_title.is_not_null())
+    .distinct()
+    .into_boxed();
+
+  if !app_names.is_empty() {
+    query_builder = query_builder.filter(copied_from_app.eq_any(app_names));
+  }
+
+  let window_titles = query_builder
+    .order(copied_from_window_title.asc())
+    .load::<Option<String>>(connection)?;
+
+  Ok(window_titles.into_iter().flatten().collect())
+}
+
+/// Removes saved window titles and process paths from all history entries
+pub fn clear_source_window_context() -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::update(clipboard_history)
+    .set((
+      copied_from_window_title.eq::<Option<String>>(None),
+      copied_from_process_path.eq::<Option<String>>(None),
+    ))
+    .execute(connection)
+}
+
 pub fn add_clipboard_history_from_image(
   image_data: ImageData,
   should_auto_star_on_double_copy: bool,
-  _copied_from_app: Option<String>,
-) -> String {
+  near_duplicate_max_distance: u32,
+  image_storage_options: ImageStorageOptions,
+  capture_source: CaptureSource,
+) -> HistoryCaptureOutcome {
+  let rule_outcome = evaluate_capture_rules(&CaptureRuleContext {
+    value: None,
+    app_name: capture_source.app_name.as_deref(),
+    window_title: capture_source.window_title.as_deref(),
+    detected_language: None,
+    content_type: "image",
+  });
+
+  if rule_outcome.skip {
+    debug_output(|| {
+      println!("Clipboard image skipped by capture rules: {:?}", rule_outcome.matched_rule_ids);
+    });
+    return HistoryCaptureOutcome::excluded(CAPTURE_REASON_CAPTURE_RULE);
+  }
+
   let image = match ImageBuffer::from_raw(
     image_data
       .width
@@ -226,7 +477,7 @@ pub fn add_clipboard_history_from_image(
     Some(image) => image,
     None => {
       eprintln!("Error converting image data to ImageBuffer");
-      return "Error converting image data to ImageBuffer".to_string();
+      return HistoryCaptureOutcome::failed("Error converting image data to ImageBuffer");
     }
   };
 
@@ -248,67 +499,114 @@ pub fn add_clipboard_history_from_image(
   let (_, _preview_height) = resized_img.dimensions();
   let _image_data_low_res = convert_to_vec_u8(resized_img.clone());
   let image_hash_string = compute_image_hash(resized_img);
-  let matching_histories =
-    get_recent_image_hashes(1000, image_hash_string.clone()).unwrap_or_default();
-  if let Some(existing_history) = matching_histories.first() {
-    let first_item = get_first_clipboard_item();
-    let mut is_favorite_item = existing_history.is_favorite.unwrap_or(false);
-
-    if !is_favorite_item && should_auto_star_on_double_copy {
-      match first_item {
-        Ok(first_item) => {
-          let current_time = Utc::now().timestamp_millis();
-          let time_difference = current_time - first_item.updated_at;
-
-          if first_item.history_id == existing_history.history_id
-            && time_difference < 1200
-            && time_difference > 200
-          {
-            is_favorite_item = true;
-          }
-        }
-        Err(_) => {
-          println!("Error getting first clipboard item")
-        }
-      }
-    }
+  let matching_history =
+    find_similar_image_history(&image_hash_string, near_duplicate_max_distance)
+      .unwrap_or_default();
+  if let Some(existing_history) = &matching_history {
+    let is_favorite_item = existing_history.is_favorite.unwrap_or(false)
+      || rule_outcome.star
+      || (should_auto_star_on_double_copy && is_double_copy(existing_history));
 
     let connection = &mut establish_pool_db_connection();
     let _ = diesel::update(clipboard_history.filter(history_id.eq(&existing_history.history_id)))
       .set((
         is_favorite.eq(is_favorite_item),
+        duplicate_count.eq(duplicate_count + 1),
         updated_at.eq(Utc::now().timestamp_millis()),
         updated_date.eq(Utc::now().naive_utc()),
       ))
       .execute(connection);
-  } else {
-    let folder_path = base_dir.join(folder_name);
-    ensure_dir_exists(&folder_path);
 
-    let image_file_name = folder_path.join(format!("{}.png", &_history_id));
-    let _ = image.save(&image_file_name);
+    apply_capture_rule_outcome(existing_history, &rule_outcome, false);
 
-    // Convert absolute path to relative path before storing
-    let relative_image_path = image_file_name
-      .to_str()
-      .map(|path| db::to_relative_image_path(path))
-      .unwrap_or_default();
+    let duplicate_reason = if existing_history.image_hash.as_ref() == Some(&image_hash_string) {
+      CAPTURE_REASON_DUPLICATE
+    } else {
+      CAPTURE_REASON_NEAR_DUPLICATE
+    };
+    HistoryCaptureOutcome::merged(
+      existing_history,
+      is_favorite_item,
+      &rule_outcome,
+      duplicate_reason,
+    )
+  } else {
+    let stored_image = image_storage_options.fit_to_max_dimension(DynamicImage::ImageRgba8(image));
+    let (_stored_width, _stored_height) = stored_image.dimensions();
+
+    let mut relative_image_path = None;
+    let mut saved_image_size = None;
+
+    if !image_storage_options.is_thumbnail_only {
+      match image_storage_options.encode(&stored_image) {
+        Ok(encoded_image) => {
+          let folder_path = base_dir.join(folder_name);
+          ensure_dir_exists(&folder_path);
+
+          let image_file_name = folder_path.join(format!(
+            "{}.{}",
+            &_history_id,
+            image_storage_options.format.extension()
+          ));
+
+          let encoded_image_size = encoded_image.len() as i64;
+
+          match fs::write(&image_file_name, encoded_image) {
+            // Convert absolute path to relative path before storing
+            Ok(_) => {
+              relative_image_path = image_file_name
+                .to_str()
+                .map(|path| db::to_relative_image_path(path));
+              saved_image_size = Some(encoded_image_size);
+            }
+            Err(e) => eprintln!("Error saving clipboard image: {}", e),
+          }
+        }
+        Err(e) => eprintln!("Error encoding clipboard image: {}", e),
+      }
+    }
+
+    let (source_window_title, source_process_path) = capture_source.stored_window_context();
 
-    let new_history = create_new_history(
+    let mut new_history = create_new_history(
       _history_id,
       _image_data_low_res,
-      PathBuf::from(relative_image_path),
+      relative_image_path.map(PathBuf::from),
       image_hash_string,
       _preview_height.try_into().unwrap(),
-      _image_height.try_into().unwrap(),
-      _image_width.try_into().unwrap(),
-      _copied_from_app,
+      _stored_height.try_into().unwrap(),
+      _stored_width.try_into().unwrap(),
+      capture_source.app_name,
     );
+    new_history.image_format = new_history
+      .image_path_full_res
+      .as_ref()
+      .map(|_| image_storage_options.format.name().to_string());
+    new_history.image_size = saved_image_size;
+    new_history.is_favorite = Some(rule_outcome.star);
+    new_history.is_masked = Some(rule_outcome.mask);
+    new_history.expires_at = rule_outcome.expires_at;
+    new_history.copied_from_window_title = source_window_title;
+    new_history.copied_from_process_path = source_process_path;
+    new_history.source_selection = capture_source.selection;
+
+    match insert_clipboard_history(&new_history) {
+      Ok(_) => {
+        apply_capture_rule_outcome(&new_history, &rule_outcome, true);
+        HistoryCaptureOutcome::inserted(&new_history.history_id)
+      }
+      Err(e) => {
+        eprintln!("Error inserting image history: {}", e);
 
-    let _ = insert_clipboard_history(&new_history);
-  }
+        // The saved image file has no history entry to use it
+        if let Some(path) = &new_history.image_path_full_res {
+          let _ = delete_file_and_maybe_parent(Path::new(&db::to_absolute_image_path(path)));
+        }
 
-  "ok".to_string()
+        HistoryCaptureOutcome::failed("Error inserting image history")
+      }
+    }
+  }
 }
 
 fn resize_image_if_necessary(image: RgbaImage) -> DynamicImage {
@@ -320,6 +618,11 @@ fn resize_image_if_necessary(image: RgbaImage) -> DynamicImage {
   }
 }
 
+/// PNG preview stored in `image_data_low_res` of an image history entry
+pub fn create_history_image_preview(image: &DynamicImage) -> Vec<u8> {
+  convert_to_vec_u8(resize_image_if_necessary(image.to_rgba8()))
+}
+
 fn convert_to_vec_u8(resized_img: DynamicImage) -> Vec<u8> {
   let mut buffer = Cursor::new(Vec::new());
   resized_img
@@ -341,7 +644,7 @@ pub fn compute_image_hash(img: DynamicImage) -> String {
 fn create_new_history(
   _history_id: String,
   _image_data_low_res: Vec<u8>,
-  image_file_name: PathBuf,
+  image_file_name: Option<PathBuf>,
   image_hash_string: String,
   _image_preview_height: i32,
   _image_height: i32,
@@ -366,7 +669,7 @@ fn create_new_history(
     has_masked_words: None,
     is_pinned: None,
     is_favorite: None,
-    image_path_full_res: image_file_name.to_str().map(|s| s.to_string()),
+    image_path_full_res: image_file_name.and_then(|path| path.to_str().map(|s| s.to_string())),
     image_data_low_res: Some(_image_data_low_res),
     image_data_url: None,
     image_preview_height: Some(_image_preview_height),
@@ -376,6 +679,17 @@ fn create_new_history(
     is_image: Some(true),
     is_image_data: None,
     is_masked: None,
+    value_html: None,
+    value_rtf: None,
+    is_file: None,
+    expires_at: None,
+    detected_secrets: None,
+    source_selection: None,
+    duplicate_count: 0,
+    image_format: None,
+    copied_from_window_title: None,
+    copied_from_process_path: None,
+    image_size: None,
     links: None,
     detected_language: None,
     pinned_order_number: None,
@@ -386,12 +700,26 @@ fn create_new_history(
   }
 }
 
+/// Columns changed on an entry when the same text is copied again, `None` leaves a column as is
+#[derive(AsChangeset)]
+#[diesel(table_name = clipboard_history)]
+struct DuplicateTextChanges {
+  is_favorite: bool,
+  updated_at: i64,
+  updated_date: chrono::NaiveDateTime,
+  value_html: Option<String>,
+  value_rtf: Option<String>,
+  source_selection: Option<String>,
+  detected_secrets: Option<String>,
+}
+
 pub fn add_clipboard_history_from_text(
-  text: String,
+  mut text: String,
+  rich_text_formats: RichTextFormats,
   detect_options: LanguageDetectOptions,
   should_auto_star_on_double_copy: bool,
-  _copied_from_app: Option<String>,
-) -> String {
+  capture_source: CaptureSource,
+) -> HistoryCaptureOutcome {
   let mut _is_image_data = is_base64_image(&text);
   let mut _text_as_json = String::new();
 
@@ -434,85 +762,154 @@ pub fn add_clipboard_history_from_text(
     }
   };
 
-  let mut hasher = Sha1::new();
-  hasher.update(&text);
-  let result = hasher.finalize();
-  let _value_hash = format!("{:x}", result);
-
-  let matching_histories = get_recent_value_hashes(10, _value_hash.clone()).unwrap_or_default();
-  if let Some(existing_history) = matching_histories.first() {
-    let first_item = get_first_clipboard_item();
-    let mut is_favorite_item = existing_history.is_favorite.unwrap_or(false);
-
-    if !is_favorite_item && should_auto_star_on_double_copy {
-      match first_item {
-        Ok(first_item) => {
-          let current_time = Utc::now().timestamp_millis();
-          let time_difference = current_time - first_item.updated_at;
-
-          if first_item.history_id == existing_history.history_id
-            && time_difference < 1200
-            && time_difference > 200
-          {
-            is_favorite_item = true;
-          }
-        }
-        Err(_) => {
-          debug_output(|| {
-            println!("Error getting first clipboard item");
-          });
-        }
-      }
+  let mut _is_link = false;
+  let mut _is_video = false;
+  let mut _is_image = false;
+  let mut _has_masked_words = false;
+
+  let mut _has_emoji = false;
+  let mut found_links_json = String::new();
+  let mut original_text: Option<String> = None;
+
+  let _is_code = !detected_language_str.is_none();
+
+  if !_is_code {
+    let mut links_finder = LinkFinder::new();
+    links_finder.url_must_have_scheme(true);
+
+    let link_spans: Vec<Range<usize>> = links_finder
+      .links(&text)
+      .filter(|link| has_valid_tld(link.as_str()))
+      .map(|link| link.start()..link.end())
+      .collect();
+    let mut found_links: Vec<String> = link_spans
+      .iter()
+      .map(|link_span| text[link_span.clone()].to_string())
+      .collect();
+
+    if let Some((cleaned_text, cleaned_links)) = detect_options
+      .url_cleaner_options
+      .clean_links_in_text(&text, &link_spans)
+    {
+      debug_output(|| {
+        println!("Removed tracking parameters from links: {:?}", cleaned_links);
+      });
+      original_text = Some(std::mem::replace(&mut text, cleaned_text));
+      found_links = cleaned_links;
     }
 
-    let connection = &mut establish_pool_db_connection();
+    found_links_json = to_string(&found_links).expect("Failed to serialize links to JSON");
+    _is_link = !found_links.is_empty();
+    if !_is_link {
+      _has_emoji = has_emoji(&text);
+    } else {
+      _is_image = is_image_url(&text);
+    }
+    _is_video = found_links.iter().any(|link| is_youtube_url(link));
+  }
 
-    let _ = diesel::update(clipboard_history.filter(history_id.eq(&existing_history.history_id)))
-      .set((
-        is_favorite.eq(is_favorite_item),
-        updated_at.eq(Utc::now().timestamp_millis()),
-        updated_date.eq(Utc::now().naive_utc()),
-      ))
-      .execute(connection);
+  let _is_text = !_is_code && !_is_link && !_is_image && !_is_video;
 
-    "ok".to_string()
+  let content_type = if _is_image_data || _is_image {
+    "image"
+  } else if _is_video {
+    "video"
+  } else if _is_link {
+    "link"
+  } else if _is_code {
+    "code"
   } else {
-    let mut _is_link = false;
-    let mut _is_video = false;
-    let mut _is_image = false;
-    let mut _has_masked_words = false;
-
-    let mut _has_emoji = false;
-    let mut found_links_json = String::new();
-
-    let _is_code = !detected_language_str.is_none();
-
-    if !_is_code {
-      let mut links_finder = LinkFinder::new();
-      links_finder.url_must_have_scheme(true);
-
-      let found_links: Vec<_> = links_finder
-        .links(&text)
-        .filter(|link| has_valid_tld(link.as_str()))
-        .map(|link| link.as_str())
-        .collect();
-
-      found_links_json = to_string(&found_links).expect("Failed to serialize links to JSON");
-      _is_link = !found_links.is_empty();
-      if !_is_link {
-        _has_emoji = has_emoji(&text);
-      } else {
-        _is_image = is_image_url(&text);
-      }
-      _is_video = found_links.iter().any(|link| is_youtube_url(link));
+    "text"
+  };
+
+  let mut rule_outcome = evaluate_capture_rules(&CaptureRuleContext {
+    value: Some(&text),
+    app_name: capture_source.app_name.as_deref(),
+    window_title: capture_source.window_title.as_deref(),
+    detected_language: detected_language_str.as_deref(),
+    content_type,
+  });
+
+  if rule_outcome.skip {
+    debug_output(|| {
+      println!("Clipboard text skipped by capture rules: {:?}", rule_outcome.matched_rule_ids);
+    });
+    return HistoryCaptureOutcome::excluded(CAPTURE_REASON_CAPTURE_RULE);
+  }
+
+  // Secrets mask and expire a copy merged into an existing entry the same as a new one
+  let found_secrets = if !_is_image_data {
+    detect_secrets(&text, &detect_options.secret_detectors)
+  } else {
+    Vec::new()
+  };
+
+  if !found_secrets.is_empty() {
+    debug_output(|| {
+      println!("Secret detectors matched clipboard text: {:?}", found_secrets);
+    });
+    rule_outcome.mask = true;
+
+    if let Some(minutes) = detect_options.secrets_expire_after_minutes {
+      let secret_expires_at = Utc::now().timestamp_millis() + minutes * 60 * 1000;
+      rule_outcome.expires_at = Some(
+        rule_outcome
+          .expires_at
+          .map_or(secret_expires_at, |current| current.min(secret_expires_at)),
+      );
+    }
+  }
+
+  let _value_hash = detect_options.dedupe_options.hash(&text);
+
+  let matching_history =
+    get_duplicate_history_by_value_hash(&_value_hash, false, detect_options.dedupe_options.policy)
+      .unwrap_or_default();
+  if let Some(existing_history) = &matching_history {
+    let is_favorite_item = existing_history.is_favorite.unwrap_or(false)
+      || rule_outcome.star
+      || (should_auto_star_on_double_copy && is_double_copy(existing_history));
+
+    // Copying text that was recorded from the primary selection makes it a regular clipboard
+    // entry, and only the formats read from the latest copy replace the stored formatting
+    let is_copied_from_clipboard = capture_source.selection.as_deref()
+      == Some(SOURCE_SELECTION_CLIPBOARD)
+      && existing_history.source_selection.as_deref() == Some(SOURCE_SELECTION_PRIMARY);
+    let is_new_secret = !found_secrets.is_empty() && existing_history.detected_secrets.is_none();
+
+    let duplicate_changes = DuplicateTextChanges {
+      is_favorite: is_favorite_item,
+      updated_at: Utc::now().timestamp_millis(),
+      updated_date: Utc::now().naive_utc(),
+      value_html: rich_text_formats.html,
+      value_rtf: rich_text_formats.rtf,
+      source_selection: is_copied_from_clipboard.then(|| SOURCE_SELECTION_CLIPBOARD.to_string()),
+      detected_secrets: is_new_secret
+        .then(|| to_string(&found_secrets).expect("Failed to serialize detected secrets to JSON")),
+    };
+
+    let connection = &mut establish_pool_db_connection();
+    let result = connection.transaction::<_, Error, _>(|connection| {
+      diesel::update(clipboard_history.filter(history_id.eq(&existing_history.history_id)))
+        .set((duplicate_count.eq(duplicate_count + 1), &duplicate_changes))
+        .execute(connection)
+    });
+
+    if let Err(e) = result {
+      eprintln!("Error updating duplicate clipboard history: {}", e);
     }
 
-    let _is_text = !_is_code && !_is_link && !_is_image && !_is_video;
+    apply_capture_rule_outcome(existing_history, &rule_outcome, false);
 
+    HistoryCaptureOutcome::merged(
+      existing_history,
+      is_favorite_item,
+      &rule_outcome,
+      CAPTURE_REASON_DUPLICATE,
+    )
+  } else {
     if _is_text && !detect_options.auto_mask_words_list.is_empty() {
-      let mut _is_masked = false;
-      let mut _value = text.clone();
-      let _value_lower = _value.to_lowercase();
+      let _value_lower = text.to_lowercase();
 
       for word in detect_options.auto_mask_words_list.iter() {
         if _value_lower.contains(&word.to_lowercase()) {
@@ -522,12 +919,23 @@ pub fn add_clipboard_history_from_text(
       }
     }
 
+    if !found_secrets.is_empty() {
+      _has_masked_words = true;
+    }
+
     let new_history_id = nanoid!().to_string();
 
+    // Links were cleaned of tracking parameters, keep what was actually copied
+    let _history_options = original_text
+      .filter(|_| detect_options.url_cleaner_options.keep_original)
+      .map(|original_value| json!({ "originalValue": original_value }).to_string());
+
+    let (source_window_title, source_process_path) = capture_source.stored_window_context();
+
     let new_history = ClipboardHistory {
       history_id: new_history_id,
-      history_options: None,
-      copied_from_app: _copied_from_app,
+      history_options: _history_options,
+      copied_from_app: capture_source.app_name,
       title: None,
       value: if !_text_as_json.is_empty() {
         Some(_text_as_json)
@@ -545,7 +953,7 @@ pub fn add_clipboard_history_from_text(
       has_emoji: Some(_has_emoji),
       has_masked_words: Some(_has_masked_words),
       is_pinned: None,
-      is_favorite: None,
+      is_favorite: Some(rule_outcome.star),
       is_image: Some(_is_image),
       image_data_low_res: None,
       image_path_full_res: None,
@@ -555,7 +963,20 @@ pub fn add_clipboard_history_from_text(
       image_width: None,
       image_hash: None,
       is_image_data: Some(_is_image_data),
-      is_masked: None,
+      is_masked: Some(rule_outcome.mask),
+      value_html: rich_text_formats.html,
+      value_rtf: rich_text_formats.rtf,
+      is_file: Some(false),
+      expires_at: rule_outcome.expires_at,
+      detected_secrets: Some(found_secrets)
+        .filter(|secrets| !secrets.is_empty())
+        .map(|secrets| to_string(&secrets).expect("Failed to serialize detected secrets to JSON")),
+      source_selection: capture_source.selection,
+      duplicate_count: 0,
+      image_format: None,
+      copied_from_window_title: source_window_title,
+      copied_from_process_path: source_process_path,
+      image_size: None,
       links: Some(found_links_json).filter(|_| _is_link),
       detected_language: detected_language_str,
       pinned_order_number: None,
@@ -565,11 +986,259 @@ pub fn add_clipboard_history_from_text(
       updated_date: Utc::now().naive_utc(),
     };
 
-    let _ = insert_clipboard_history(&new_history);
-    "ok".to_string()
+    match insert_clipboard_history(&new_history) {
+      Ok(_) => {
+        apply_capture_rule_outcome(&new_history, &rule_outcome, true);
+        HistoryCaptureOutcome::inserted(&new_history.history_id)
+      }
+      Err(e) => {
+        eprintln!("Error inserting text history: {}", e);
+        HistoryCaptureOutcome::failed("Error inserting text history")
+      }
+    }
+  }
+}
+
+/// Checks whether the same content was copied twice in a row quickly enough to auto-star it
+fn is_double_copy(existing_history: &ClipboardHistory) -> bool {
+  match get_first_clipboard_item() {
+    Ok(first_item) => {
+      let time_difference = Utc::now().timestamp_millis() - first_item.updated_at;
+
+      first_item.history_id == existing_history.history_id
+        && time_difference < 1200
+        && time_difference > 200
+    }
+    Err(_) => {
+      debug_output(|| {
+        println!("Error getting first clipboard item");
+      });
+      false
+    }
+  }
+}
+
+/// Applies capture rule actions which need the stored history row: pinning, expiry and moving to a
+/// board. Only a newly inserted entry is moved, never an older entry a duplicate was merged into.
+fn apply_capture_rule_outcome(
+  history: &ClipboardHistory,
+  rule_outcome: &CaptureRuleOutcome,
+  is_new_history: bool,
+) {
+  if rule_outcome.pin && history.is_pinned != Some(true) {
+    update_pinned_clipboard_history_by_ids(&[history.history_id.clone()], true);
+  }
+
+  if rule_outcome.mask && history.is_masked != Some(true) {
+    let connection = &mut establish_pool_db_connection();
+    let _ = diesel::update(clipboard_history.filter(history_id.eq(&history.history_id)))
+      .set(is_masked.eq(true))
+      .execute(connection);
+  }
+
+  if let Some(_expires_at) = rule_outcome.expires_at {
+    if history.expires_at != Some(_expires_at) {
+      let connection = &mut establish_pool_db_connection();
+      let _ = diesel::update(clipboard_history.filter(history_id.eq(&history.history_id)))
+        .set(expires_at.eq(_expires_at))
+        .execute(connection);
+    }
+    cron_jobs::notify_history_expiry_changed();
+  }
+
+  if let (true, Some(board_id)) = (is_new_history, &rule_outcome.move_to_board_id) {
+    if let Err(e) = move_history_item_to_board(&history.history_id, board_id) {
+      eprintln!("Error moving history item to board {}: {}", board_id, e);
+    }
+  }
+}
+
+pub fn add_clipboard_history_from_files(
+  files: Vec<String>,
+  should_auto_star_on_double_copy: bool,
+  dedupe_policy: DedupePolicy,
+  capture_source: CaptureSource,
+) -> HistoryCaptureOutcome {
+  let files_value = files.join("\n");
+
+  let rule_outcome = evaluate_capture_rules(&CaptureRuleContext {
+    value: Some(&files_value),
+    app_name: capture_source.app_name.as_deref(),
+    window_title: capture_source.window_title.as_deref(),
+    detected_language: None,
+    content_type: "files",
+  });
+
+  if rule_outcome.skip {
+    return HistoryCaptureOutcome::excluded(CAPTURE_REASON_CAPTURE_RULE);
+  }
+
+  let mut hasher = Sha1::new();
+  hasher.update(&files_value);
+  let _value_hash = format!("{:x}", hasher.finalize());
+
+  let matching_history =
+    get_duplicate_history_by_value_hash(&_value_hash, true, dedupe_policy).unwrap_or_default();
+  if let Some(existing_history) = &matching_history {
+    let is_favorite_item = existing_history.is_favorite.unwrap_or(false)
+      || rule_outcome.star
+      || (should_auto_star_on_double_copy && is_double_copy(existing_history));
+
+    let connection = &mut establish_pool_db_connection();
+    let _ = diesel::update(clipboard_history.filter(history_id.eq(&existing_history.history_id)))
+      .set((
+        is_favorite.eq(is_favorite_item),
+        duplicate_count.eq(duplicate_count + 1),
+        updated_at.eq(Utc::now().timestamp_millis()),
+        updated_date.eq(Utc::now().naive_utc()),
+      ))
+      .execute(connection);
+
+    apply_capture_rule_outcome(existing_history, &rule_outcome, false);
+
+    return HistoryCaptureOutcome::merged(
+      existing_history,
+      is_favorite_item,
+      &rule_outcome,
+      CAPTURE_REASON_DUPLICATE,
+    );
+  }
+
+  let new_history_id = nanoid!().to_string();
+
+  let history_files: Vec<ClipboardHistoryFile> = files
+    .iter()
+    .enumerate()
+    .map(|(index, file)| {
+      let path = Path::new(file);
+      let metadata = fs::metadata(path).ok();
+      let is_dir = metadata.as_ref().map_or(false, |m| m.is_dir());
+
+      ClipboardHistoryFile {
+        file_id: nanoid!().to_string(),
+        history_id: new_history_id.clone(),
+        file_path: file.clone(),
+        file_name: path
+          .file_name()
+          .and_then(|name| name.to_str())
+          .map(String::from),
+        file_size: metadata.as_ref().filter(|_| !is_dir).map(|m| m.len() as i64),
+        mime_type: if is_dir {
+          Some("inode/directory".to_string())
+        } else {
+          Some(
+            mime_guess::from_path(path)
+              .first_or_octet_stream()
+              .to_string(),
+          )
+        },
+        is_directory: Some(is_dir),
+        order_number: index as i32,
+        created_at: Utc::now().timestamp_millis(),
+      }
+    })
+    .collect();
+
+  let files_title = history_files
+    .iter()
+    .filter_map(|file| file.file_name.clone())
+    .collect::<Vec<String>>()
+    .join(", ");
+
+  let (source_window_title, source_process_path) = capture_source.stored_window_context();
+
+  let new_history = ClipboardHistory {
+    history_id: new_history_id,
+    history_options: None,
+    copied_from_app: capture_source.app_name,
+    title: Some(files_title).filter(|t| !t.is_empty()),
+    value: Some(files_value),
+    value_preview: None,
+    value_more_preview_lines: None,
+    value_more_preview_chars: None,
+    value_hash: Some(_value_hash),
+    is_text: Some(false),
+    is_code: Some(false),
+    is_link: Some(false),
+    is_video: Some(false),
+    has_emoji: Some(false),
+    has_masked_words: Some(false),
+    is_pinned: None,
+    is_favorite: Some(rule_outcome.star),
+    is_image: Some(false),
+    image_data_low_res: None,
+    image_path_full_res: None,
+    image_data_url: None,
+    image_preview_height: None,
+    image_height: None,
+    image_width: None,
+    image_hash: None,
+    is_image_data: Some(false),
+    is_masked: Some(rule_outcome.mask),
+    value_html: None,
+    value_rtf: None,
+    is_file: Some(true),
+    expires_at: rule_outcome.expires_at,
+    detected_secrets: None,
+    source_selection: capture_source.selection,
+    duplicate_count: 0,
+    image_format: None,
+    copied_from_window_title: source_window_title,
+    copied_from_process_path: source_process_path,
+    image_size: None,
+    links: None,
+    detected_language: None,
+    pinned_order_number: None,
+    created_at: Utc::now().timestamp_millis(),
+    updated_at: Utc::now().timestamp_millis(),
+    created_date: Utc::now().naive_utc(),
+    updated_date: Utc::now().naive_utc(),
+  };
+
+  let connection = &mut establish_pool_db_connection();
+  let result = connection.transaction::<_, Error, _>(|connection| {
+    diesel::insert_into(clipboard_history)
+      .values(&new_history)
+      .execute(connection)?;
+    diesel::insert_into(clipboard_history_files::table)
+      .values(&history_files)
+      .execute(connection)?;
+    Ok(())
+  });
+
+  match result {
+    Ok(_) => {
+      apply_capture_rule_outcome(&new_history, &rule_outcome, true);
+      HistoryCaptureOutcome::inserted(&new_history.history_id)
+    }
+    Err(e) => {
+      eprintln!("Error inserting file list history: {}", e);
+      HistoryCaptureOutcome::failed("Error inserting file list history")
+    }
   }
 }
 
+pub fn get_clipboard_history_files(
+  history_id_value: &String,
+) -> Result<Vec<ClipboardHistoryFile>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  clipboard_history_files::table
+    .filter(clipboard_history_files::history_id.eq(history_id_value))
+    .order(clipboard_history_files::order_number.asc())
+    .load::<ClipboardHistoryFile>(connection)
+}
+
+pub fn delete_clipboard_history_files_by_history_ids(history_ids_value: &[String]) {
+  let connection = &mut establish_pool_db_connection();
+
+  let _ = diesel::delete(
+    clipboard_history_files::table
+      .filter(clipboard_history_files::history_id.eq_any(history_ids_value)),
+  )
+  .execute(connection);
+}
+
 pub fn get_pinned_clipboard_histories(
   auto_mask_words_list: Vec<String>,
 ) -> Result<Vec<ClipboardHistoryWithMetaData>, Error> {
@@ -600,26 +1269,54 @@ pub fn get_pinned_clipboard_histories(
 pub fn get_clipboard_histories(
   limit: Option<i64>,
   offset: Option<i64>,
+  cursor: Option<&HistoryCursor>,
   auto_mask_words_list: Vec<String>,
+  sort_order: HistorySortOrder,
 ) -> Result<Vec<ClipboardHistoryWithMetaData>, Error> {
   let limit = limit.unwrap_or(100);
   let offset = offset.unwrap_or(0);
+  check_cursor_sort_order(cursor, sort_order != HistorySortOrder::Frecency)?;
 
   let connection = &mut establish_pool_db_connection();
 
-  let query_results = clipboard_history
+  let mut query_builder = clipboard_history
     .left_join(
       link_metadata_dsl.on(link_metadata::history_id.eq(clipboard_history::history_id.nullable())),
     )
-    .order(updated_date.desc())
+    .left_join(
+      usage_stats::table.on(usage_stats::history_id.eq(clipboard_history::history_id.nullable())),
+    )
+    .select((
+      ClipboardHistory::as_select(),
+      Option::<LinkMetadata>::as_select(),
+      usage_stats::use_count.nullable(),
+      usage_stats::last_used_at.nullable(),
+    ))
+    .into_boxed();
+
+  if let Some(cursor) = cursor {
+    query_builder = query_builder.filter(history_cursor_filter(cursor));
+  }
+
+  query_builder = match sort_order {
+    HistorySortOrder::Recent | HistorySortOrder::Relevance => {
+      query_builder.order((updated_at.desc(), history_id.desc()))
+    }
+    HistorySortOrder::Frecency => {
+      query_builder.order((usage_stats::frecency_rank.desc(), updated_date.desc()))
+    }
+  };
+
+  let query_results = query_builder
     .limit(limit)
     .offset(offset)
-    .load::<(ClipboardHistory, Option<LinkMetadata>)>(connection)?;
+    .load::<(ClipboardHistory, Option<LinkMetadata>, Option<i32>, Option<i64>)>(connection)?;
 
   let mut histories: Vec<ClipboardHistoryWithMetaData> = query_results
     .into_iter()
-    .map(|(history, link_metadata)| {
+    .map(|(history, link_metadata, _use_count, _last_used_at)| {
       ClipboardHistoryWithMetaData::from(history, link_metadata, &auto_mask_words_list)
+        .with_usage(_use_count, _last_used_at)
     })
     .collect();
 
@@ -675,7 +1372,8 @@ pub fn delete_clipboard_history_older_than(
 
   for item in image_items_to_delete.iter() {
     if let Some(ref path) = item.image_path_full_res {
-      if let Err(e) = delete_file_and_maybe_parent(&Path::new(path)) {
+      let path = db::to_absolute_image_path(path);
+      if let Err(e) = delete_file_and_maybe_parent(&Path::new(&path)) {
         eprintln!("Error deleting image file {}: {}", path, e);
       }
     }
@@ -729,8 +1427,11 @@ pub fn delete_clipboard_history_older_than(
     0
   };
 
-  // Delete associated link metadata
+  // Delete associated link metadata, file lists, usage and tags
   delete_link_metadata_by_history_ids(&history_ids_to_delete);
+  delete_clipboard_history_files_by_history_ids(&ids_to_delete);
+  let _ = usage_service::delete_usage_by_history_ids(&ids_to_delete);
+  let _ = tags_service::delete_history_tags_by_history_ids(&ids_to_delete);
 
   Ok(format!("Successfully deleted {} items", deleted_count))
 }
@@ -769,7 +1470,8 @@ pub fn delete_recent_clipboard_history(
 
   for item in image_items {
     if let Some(ref path) = item.image_path_full_res {
-      if let Err(e) = delete_file_and_maybe_parent(&Path::new(path)) {
+      let path = db::to_absolute_image_path(path);
+      if let Err(e) = delete_file_and_maybe_parent(&Path::new(&path)) {
         eprintln!("Error deleting image file {}: {}", path, e);
       }
     }
@@ -810,8 +1512,11 @@ pub fn delete_recent_clipboard_history(
     0
   };
 
-  // Delete associated link metadata
+  // Delete associated link metadata, file lists, usage and tags
   delete_link_metadata_by_history_ids(&history_ids_to_delete);
+  delete_clipboard_history_files_by_history_ids(&ids_to_delete_recent);
+  let _ = usage_service::delete_usage_by_history_ids(&ids_to_delete_recent);
+  let _ = tags_service::delete_history_tags_by_history_ids(&ids_to_delete_recent);
 
   Ok(format!(
     "Successfully deleted {} recent items",
@@ -822,66 +1527,59 @@ pub fn delete_recent_clipboard_history(
 pub fn delete_all_clipboard_histories(keep_pinned: bool, keep_starred: bool) -> String {
   let connection = &mut establish_pool_db_connection();
 
-  if !keep_pinned && !keep_starred {
-    // Delete everything - original behavior
-    let folder_path = db::get_clipboard_images_dir();
-    let _ = remove_dir_if_exists(&folder_path);
-    let _ = diesel::delete(clipboard_history)
-      .execute(connection)
-      .expect("Error deleting all clipboard histories");
-    delete_all_link_metadata_with_history_ids();
-  } else {
-    // Selective deletion - preserve pinned and/or starred items
-    // First, get all items that will be deleted
-    let mut items_query = clipboard_history.into_boxed();
-    
-    if keep_pinned {
-      items_query = items_query.filter(is_pinned.ne(true).or(is_pinned.is_null()));
-    }
-    
-    if keep_starred {
-      items_query = items_query.filter(is_favorite.ne(true).or(is_favorite.is_null()));
-    }
+  // Only the files of the deleted rows are removed, the images folder also holds trashed images
+  let mut items_query = clipboard_history.into_boxed();
 
-    // Get items to delete for image and link cleanup
-    let items_to_delete = items_query
-      .load::<ClipboardHistory>(connection)
-      .expect("Error loading items to delete");
+  if keep_pinned {
+    items_query = items_query.filter(is_pinned.ne(true).or(is_pinned.is_null()));
+  }
 
-    // Delete image files for items being deleted
-    for item in items_to_delete.iter() {
-      if item.is_image == Some(true) {
-        if let Some(ref path) = item.image_path_full_res {
-          if let Err(e) = delete_file_and_maybe_parent(&Path::new(path)) {
-            eprintln!("Error deleting image file {}: {}", path, e);
-          }
+  if keep_starred {
+    items_query = items_query.filter(is_favorite.ne(true).or(is_favorite.is_null()));
+  }
+
+  // Get items to delete for image and link cleanup
+  let items_to_delete = items_query
+    .load::<ClipboardHistory>(connection)
+    .expect("Error loading items to delete");
+
+  // Delete image files for items being deleted
+  for item in items_to_delete.iter() {
+    if item.is_image == Some(true) {
+      if let Some(ref path) = item.image_path_full_res {
+        let path = db::to_absolute_image_path(path);
+        if let Err(e) = delete_file_and_maybe_parent(&Path::new(&path)) {
+          eprintln!("Error deleting image file {}: {}", path, e);
         }
       }
     }
+  }
 
-    // Get history IDs for link metadata cleanup
-    let history_ids_to_delete: Vec<String> = items_to_delete
-      .iter()
-      .filter(|item| item.is_link == Some(true))
-      .map(|item| item.history_id.clone())
-      .collect();
-
-    // Delete the filtered records by ID
-    let item_ids_to_delete: Vec<String> = items_to_delete
-      .iter()
-      .map(|item| item.history_id.clone())
-      .collect();
+  // Get history IDs for link metadata cleanup
+  let history_ids_to_delete: Vec<String> = items_to_delete
+    .iter()
+    .filter(|item| item.is_link == Some(true))
+    .map(|item| item.history_id.clone())
+    .collect();
 
-    if !item_ids_to_delete.is_empty() {
-      let _ = diesel::delete(clipboard_history.filter(history_id.eq_any(&item_ids_to_delete)))
-        .execute(connection)
-        .expect("Error deleting filtered clipboard histories");
-    }
+  // Delete the filtered records by ID
+  let item_ids_to_delete: Vec<String> = items_to_delete
+    .iter()
+    .map(|item| item.history_id.clone())
+    .collect();
 
-    // Delete associated link metadata
-    delete_link_metadata_by_history_ids(&history_ids_to_delete);
+  if !item_ids_to_delete.is_empty() {
+    let _ = diesel::delete(clipboard_history.filter(history_id.eq_any(&item_ids_to_delete)))
+      .execute(connection)
+      .expect("Error deleting filtered clipboard histories");
   }
 
+  // Delete associated link metadata, file lists, usage and tags
+  delete_link_metadata_by_history_ids(&history_ids_to_delete);
+  delete_clipboard_history_files_by_history_ids(&item_ids_to_delete);
+  let _ = usage_service::delete_usage_by_history_ids(&item_ids_to_delete);
+  let _ = tags_service::delete_history_tags_by_history_ids(&item_ids_to_delete);
+
   "ok".to_string()
 }
 
@@ -895,7 +1593,8 @@ pub fn delete_clipboard_history_by_ids(history_ids_value: &[String]) -> String {
 
   for item in image_items_to_delete.iter() {
     if let Some(ref path) = item.image_path_full_res {
-      match delete_file_and_maybe_parent(&Path::new(path)) {
+      let path = db::to_absolute_image_path(path);
+      match delete_file_and_maybe_parent(&Path::new(&path)) {
         Ok(_) => println!("Successfully deleted image file: {}", path),
         Err(e) => eprintln!("Error deleting image file {}: {}", path, e),
       }
@@ -903,6 +1602,9 @@ pub fn delete_clipboard_history_by_ids(history_ids_value: &[String]) -> String {
   }
 
   delete_link_metadata_by_history_ids(&history_ids_value);
+  delete_clipboard_history_files_by_history_ids(history_ids_value);
+  let _ = usage_service::delete_usage_by_history_ids(history_ids_value);
+  let _ = tags_service::delete_history_tags_by_history_ids(history_ids_value);
 
   let _ = diesel::delete(clipboard_history.filter(history_id.eq_any(history_ids_value)))
     .execute(connection);
@@ -910,20 +1612,74 @@ pub fn delete_clipboard_history_by_ids(history_ids_value: &[String]) -> String {
   "ok".to_string()
 }
 
+pub fn delete_expired_clipboard_histories() -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let expired_ids = clipboard_history
+    .filter(expires_at.le(Utc::now().timestamp_millis()))
+    .select(history_id)
+    .load::<String>(connection)?;
+
+  if !expired_ids.is_empty() {
+    delete_clipboard_history_by_ids(&expired_ids);
+  }
+
+  Ok(expired_ids.len())
+}
+
+pub fn get_next_clipboard_history_expiry() -> Result<Option<i64>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  clipboard_history
+    .select(diesel::dsl::min(expires_at))
+    .first::<Option<i64>>(connection)
+}
+
+pub fn update_clipboard_history_expiry_by_ids(
+  history_ids_value: &[String],
+  expires_at_value: Option<i64>,
+) -> String {
+  let connection = &mut establish_pool_db_connection();
+
+  let _ = diesel::update(clipboard_history.filter(history_id.eq_any(history_ids_value)))
+    .set(expires_at.eq(expires_at_value))
+    .execute(connection);
+
+  cron_jobs::notify_history_expiry_changed();
+
+  "ok".to_string()
+}
+
 pub fn find_clipboard_histories_by_value_or_filter(
   query: &String,
   filters: &Vec<String>,
   code_filters: &Vec<String>,
   app_filters: &Vec<String>,
+  window_filters: &Vec<String>,
+  tag_filters: &Vec<String>,
   max_results: i64,
+  cursor: Option<&HistoryCursor>,
+  sort_order: HistorySortOrder,
+  search_mode: HistorySearchMode,
   app_settings: tauri::State<Mutex<HashMap<String, Setting>>>,
 ) -> Result<Vec<ClipboardHistoryWithMetaData>, Error> {
+  check_cursor_sort_order(cursor, sort_order != HistorySortOrder::Frecency)?;
+
   let connection = &mut establish_pool_db_connection();
 
   let mut query_builder = clipboard_history
     .left_join(
       link_metadata_dsl.on(link_metadata::history_id.eq(clipboard_history::history_id.nullable())),
     )
+    .left_join(
+      usage_stats::table.on(usage_stats::history_id.eq(clipboard_history::history_id.nullable())),
+    )
+    .select((
+      ClipboardHistory::as_select(),
+      Option::<LinkMetadata>::as_select(),
+      usage_stats::use_count.nullable(),
+      usage_stats::last_used_at.nullable(),
+    ))
     .into_boxed();
 
   let is_item_starred = filters.contains(&"starred".to_string());
@@ -946,6 +1702,10 @@ pub fn find_clipboard_histories_by_value_or_filter(
       query_builder = query_builder.filter(is_image.eq(true));
     }
 
+    if filters.contains(&"files".to_string()) {
+      query_builder = query_builder.filter(is_file.eq(true));
+    }
+
     if filters.contains(&"link".to_string()) {
       query_builder = query_builder.filter(is_link.eq(true));
     }
@@ -978,6 +1738,10 @@ pub fn find_clipboard_histories_by_value_or_filter(
       query_builder = query_builder.or_filter(is_image.eq(true));
     }
 
+    if filters.contains(&"files".to_string()) {
+      query_builder = query_builder.or_filter(is_file.eq(true));
+    }
+
     if filters.contains(&"link".to_string()) {
       query_builder = query_builder.or_filter(is_link.eq(true));
     }
@@ -1003,10 +1767,71 @@ pub fn find_clipboard_histories_by_value_or_filter(
     }
   }
 
-  if !query.is_empty() {
-    query_builder = query_builder.filter(value.like(format!("%{}%", query)));
+  // Source selection filters narrow any of the filters above, entries captured before
+  // the column existed count as regular clipboard copies
+  if filters.contains(&"primary".to_string()) {
+    query_builder = query_builder.filter(source_selection.eq(SOURCE_SELECTION_PRIMARY));
+  } else if filters.contains(&"clipboard".to_string()) {
+    query_builder = query_builder.filter(
+      source_selection
+        .is_null()
+        .or(source_selection.eq(SOURCE_SELECTION_CLIPBOARD)),
+    );
+  }
+
+  // Words are looked up in the full-text index or matched fuzzily, searches that find nothing
+  // this way such as punctuation or the middle of a word fall back to a substring match. Only
+  // relevance sorting loads ranked matches, other sorts filter by every full-text match.
+  let fts_query = to_fts_query(query);
+  let is_unranked_text_search =
+    search_mode == HistorySearchMode::Text && sort_order != HistorySortOrder::Relevance;
+
+  let text_matches_list = match (search_mode, &fts_query) {
+    (_, None) => Vec::new(),
+    (HistorySearchMode::Text, Some(_)) if is_unranked_text_search => Vec::new(),
+    (HistorySearchMode::Text, Some(fts_query)) => find_history_text_matches(fts_query)?,
+    (HistorySearchMode::Fuzzy, Some(_)) => {
+      let fuzzy_options = FuzzySearchOptions::from_settings(&app_settings.lock().unwrap());
+      find_history_fuzzy_matches(query, &fuzzy_options)?
+    }
+  };
+
+  let unranked_fts_query = match fts_query {
+    Some(fts_query)
+      if is_unranked_text_search && has_history_text_matches(connection, &fts_query)? =>
+    {
+      Some(fts_query)
+    }
+    _ => None,
+  };
+
+  if let Some(fts_query) = &unranked_fts_query {
+    query_builder = query_builder.filter(
+      sql::<Bool>(HISTORY_FTS_FILTER_SQL)
+        .bind::<Text, _>(fts_query.clone())
+        .sql(")"),
+    );
+  } else if !text_matches_list.is_empty() {
+    store_search_ranks(connection, &text_matches_list)?;
+    query_builder = query_builder.filter(sql::<Bool>(SEARCH_RANK_FILTER_SQL));
+  } else if !query.is_empty() {
+    let query_pattern = format!("%{}%", query);
+    query_builder = query_builder.filter(
+      value
+        .like(query_pattern.clone())
+        .or(copied_from_window_title.like(query_pattern)),
+    );
   }
 
+  let is_ranked_by_relevance =
+    sort_order == HistorySortOrder::Relevance && !text_matches_list.is_empty();
+  check_cursor_sort_order(cursor, !is_ranked_by_relevance)?;
+
+  let mut text_matches: HashMap<String, HistoryTextMatch> = text_matches_list
+    .into_iter()
+    .map(|text_match| (text_match.history_id.clone(), text_match))
+    .collect();
+
   if !code_filters.is_empty() {
     query_builder = query_builder.filter(detected_language.eq_any(code_filters));
   }
@@ -1015,6 +1840,24 @@ pub fn find_clipboard_histories_by_value_or_filter(
     query_builder = query_builder.filter(copied_from_app.eq_any(app_filters));
   }
 
+  if !window_filters.is_empty() {
+    query_builder = query_builder.filter(copied_from_window_title.eq_any(window_filters));
+  }
+
+  if !tag_filters.is_empty() {
+    query_builder = query_builder.filter(
+      history_id.eq_any(
+        history_tags::table
+          .filter(history_tags::tag_id.eq_any(tag_filters.clone()))
+          .select(history_tags::history_id),
+      ),
+    );
+  }
+
+  if let Some(cursor) = cursor {
+    query_builder = query_builder.filter(history_cursor_filter(cursor));
+  }
+
   let settings_map = app_settings.lock().unwrap();
   let mut auto_mask_words_list = Vec::new();
 
@@ -1031,15 +1874,46 @@ pub fn find_clipboard_histories_by_value_or_filter(
     }
   }
 
+  query_builder = match sort_order {
+    HistorySortOrder::Relevance if is_ranked_by_relevance => query_builder.order((
+      sql::<Double>(SEARCH_RANK_SQL).asc(),
+      updated_at.desc(),
+      history_id.desc(),
+    )),
+    HistorySortOrder::Recent | HistorySortOrder::Relevance => {
+      query_builder.order((updated_at.desc(), history_id.desc()))
+    }
+    HistorySortOrder::Frecency => {
+      query_builder.order((usage_stats::frecency_rank.desc(), updated_date.desc()))
+    }
+  };
+
+  query_builder = query_builder.limit(max_results);
+
   let query_results = query_builder
-    .order(updated_date.desc())
-    .limit(max_results)
-    .load::<(ClipboardHistory, Option<LinkMetadata>)>(connection)?;
+    .load::<(ClipboardHistory, Option<LinkMetadata>, Option<i32>, Option<i64>)>(connection)?;
+
+  if let Some(fts_query) = &unranked_fts_query {
+    let page_history_ids: Vec<String> = query_results
+      .iter()
+      .map(|(history, ..)| history.history_id.clone())
+      .collect();
+
+    text_matches.extend(
+      find_history_text_matches_by_ids(connection, fts_query, &page_history_ids)?
+        .into_iter()
+        .map(|text_match| (text_match.history_id.clone(), text_match)),
+    );
+  }
 
   let mut histories: Vec<ClipboardHistoryWithMetaData> = query_results
     .into_iter()
-    .map(|(history, link_metadata)| {
+    .map(|(history, link_metadata, _use_count, _last_used_at)| {
+      let text_match = text_matches.get(&history.history_id);
+
       ClipboardHistoryWithMetaData::from(history, link_metadata, &auto_mask_words_list)
+        .with_usage(_use_count, _last_used_at)
+        .with_text_match(text_match)
     })
     .collect();
 
@@ -1051,6 +1925,156 @@ pub fn find_clipboard_histories_by_value_or_filter(
   Ok(histories)
 }
 
+/// Runs a parsed search query as a single query, see `history_query_service` for the syntax
+pub fn find_clipboard_histories_by_query(
+  clauses: &[HistoryQueryClause],
+  max_results: i64,
+  cursor: Option<&HistoryCursor>,
+  sort_order: HistorySortOrder,
+  auto_mask_words_list: &Vec<String>,
+) -> Result<Vec<ClipboardHistoryWithMetaData>, Error> {
+  check_cursor_sort_order(cursor, sort_order != HistorySortOrder::Frecency)?;
+
+  let connection = &mut establish_pool_db_connection();
+
+  let mut query_builder = clipboard_history
+    .left_join(
+      link_metadata_dsl.on(link_metadata::history_id.eq(clipboard_history::history_id.nullable())),
+    )
+    .left_join(
+      usage_stats::table.on(usage_stats::history_id.eq(clipboard_history::history_id.nullable())),
+    )
+    .select((
+      ClipboardHistory::as_select(),
+      Option::<LinkMetadata>::as_select(),
+      usage_stats::use_count.nullable(),
+      usage_stats::last_used_at.nullable(),
+    ))
+    .into_boxed();
+
+  for query_filter in history_query_filters(clauses) {
+    query_builder = query_builder.filter(query_filter);
+  }
+
+  if let Some(cursor) = cursor {
+    query_builder = query_builder.filter(history_cursor_filter(cursor));
+  }
+
+  query_builder = match sort_order {
+    HistorySortOrder::Recent | HistorySortOrder::Relevance => {
+      query_builder.order((updated_at.desc(), history_id.desc()))
+    }
+    HistorySortOrder::Frecency => {
+      query_builder.order((usage_stats::frecency_rank.desc(), updated_date.desc()))
+    }
+  };
+
+  let query_results = query_builder
+    .limit(max_results)
+    .load::<(ClipboardHistory, Option<LinkMetadata>, Option<i32>, Option<i64>)>(connection)?;
+
+  let mut histories: Vec<ClipboardHistoryWithMetaData> = query_results
+    .into_iter()
+    .map(|(history, link_metadata, _use_count, _last_used_at)| {
+      ClipboardHistoryWithMetaData::from(history, link_metadata, auto_mask_words_list)
+        .with_usage(_use_count, _last_used_at)
+    })
+    .collect();
+
+  for history in &mut histories {
+    history.transform_image_path_for_frontend();
+  }
+
+  Ok(histories)
+}
+
+/// Finds entries whose value matches the regex, most recently copied first and after the cursor
+/// when one is given. Masked entries are not searched and the scan stops at the limits of
+/// `regex_search_service`.
+pub fn find_clipboard_histories_by_regex(
+  regex: &Regex,
+  max_results: i64,
+  cursor: Option<&HistoryCursor>,
+  auto_mask_words_list: &Vec<String>,
+) -> Result<RegexSearchResult<ClipboardHistoryWithMetaData>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let scan = {
+    let mut rows_query = clipboard_history
+      .filter(value.is_not_null())
+      .filter(
+        clipboard_history::is_masked
+          .is_null()
+          .or(clipboard_history::is_masked.eq(false)),
+      )
+      .into_boxed();
+
+    if let Some(cursor) = cursor {
+      rows_query = rows_query.filter(history_cursor_filter(cursor));
+    }
+
+    let rows = rows_query
+      .order((updated_at.desc(), history_id.desc()))
+      .select((
+        history_id,
+        sql::<Nullable<Text>>(&format!(
+          "substr(clipboard_history.value, 1, {})",
+          MAX_SEARCHED_VALUE_CHARS
+        )),
+      ))
+      .load_iter::<(String, Option<String>), DefaultLoadingMode>(connection)?;
+
+    scan_rows_for_matches(rows, regex, max_results.max(0) as usize)?
+  };
+
+  let matched_history_ids: Vec<String> = scan.matches.iter().map(|(id, _)| id.clone()).collect();
+  let mut match_ranges_by_id: HashMap<String, Vec<SearchMatchRange>> =
+    scan.matches.into_iter().collect();
+
+  let query_results = clipboard_history
+    .left_join(
+      link_metadata_dsl.on(link_metadata::history_id.eq(clipboard_history::history_id.nullable())),
+    )
+    .left_join(
+      usage_stats::table.on(usage_stats::history_id.eq(clipboard_history::history_id.nullable())),
+    )
+    .filter(history_id.eq_any(&matched_history_ids))
+    .select((
+      ClipboardHistory::as_select(),
+      Option::<LinkMetadata>::as_select(),
+      usage_stats::use_count.nullable(),
+      usage_stats::last_used_at.nullable(),
+    ))
+    .order((updated_at.desc(), history_id.desc()))
+    .load::<(
+      ClipboardHistory,
+      Option<LinkMetadata>,
+      Option<i32>,
+      Option<i64>,
+    )>(connection)?;
+
+  let mut histories: Vec<ClipboardHistoryWithMetaData> = query_results
+    .into_iter()
+    .map(|(history, link_metadata, _use_count, _last_used_at)| {
+      let match_ranges = match_ranges_by_id.remove(&history.history_id);
+
+      ClipboardHistoryWithMetaData::from(history, link_metadata, auto_mask_words_list)
+        .with_usage(_use_count, _last_used_at)
+        .with_match_ranges(match_ranges)
+    })
+    .collect();
+
+  for history in &mut histories {
+    history.transform_image_path_for_frontend();
+  }
+
+  Ok(RegexSearchResult {
+    results: histories,
+    scanned_count: scan.scanned_count,
+    is_timed_out: scan.is_timed_out,
+  })
+}
+
 pub fn get_recent_image_hashes(limit: i64, hash: String) -> Result<Vec<ClipboardHistory>, Error> {
   let connection = &mut establish_pool_db_connection();
   clipboard_history
@@ -1060,13 +2084,91 @@ pub fn get_recent_image_hashes(limit: i64, hash: String) -> Result<Vec<Clipboard
     .load::<ClipboardHistory>(connection)
 }
 
-pub fn get_recent_value_hashes(limit: i64, hash: String) -> Result<Vec<ClipboardHistory>, Error> {
+/// Finds the most recently used image with the same perceptual hash, or one that differs by at
+/// most `max_distance` bits, so re-encoded or slightly scaled copies of an image are merged.
+/// Near matches only load the id and hash columns of the most recently used
+/// `MAX_NEAR_DUPLICATE_IMAGE_CANDIDATES` images and compare them in memory.
+pub fn find_similar_image_history(
+  hash: &str,
+  max_distance: u32,
+) -> Result<Option<ClipboardHistory>, Error> {
+  if let Some(exact_match) = get_recent_image_hashes(1, hash.to_string())?
+    .into_iter()
+    .next()
+  {
+    return Ok(Some(exact_match));
+  }
+
+  if max_distance == 0 {
+    return Ok(None);
+  }
+
+  let target_hash = match ImageHash::<ImageHashSize>::from_base64(hash) {
+    Ok(target_hash) => target_hash,
+    Err(_) => return Ok(None),
+  };
+
   let connection = &mut establish_pool_db_connection();
-  clipboard_history
-    .filter(value_hash.eq(hash))
+
+  let image_hashes = clipboard_history
+    .select((history_id, image_hash))
+    .filter(is_image.eq(true))
+    .filter(image_hash.is_not_null())
     .order(updated_date.desc())
-    .limit(limit)
-    .load::<ClipboardHistory>(connection)
+    .limit(MAX_NEAR_DUPLICATE_IMAGE_CANDIDATES)
+    .load::<(String, Option<String>)>(connection)?;
+
+  let similar_history_id = image_hashes
+    .into_iter()
+    .find_map(|(_history_id, stored_hash)| {
+      let stored_hash = ImageHash::<ImageHashSize>::from_base64(&stored_hash?).ok()?;
+      Some(_history_id).filter(|_| target_hash.dist(&stored_hash) <= max_distance)
+    });
+
+  match similar_history_id {
+    Some(_history_id) => clipboard_history
+      .find(_history_id)
+      .first::<ClipboardHistory>(connection)
+      .optional(),
+    None => Ok(None),
+  }
+}
+
+/// Finds the most recently used entry with the same value hash and kind, file lists only match
+/// file lists and text only matches text, even when the text is the same path. With the recent
+/// policy the entry only counts as a duplicate when it is among the most recently used entries,
+/// with the global policy any entry does.
+pub fn get_duplicate_history_by_value_hash(
+  hash: &str,
+  is_file_list: bool,
+  policy: DedupePolicy,
+) -> Result<Option<ClipboardHistory>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let mut matching_query = clipboard_history.filter(value_hash.eq(hash)).into_boxed();
+
+  matching_query = if is_file_list {
+    matching_query.filter(is_file.eq(true))
+  } else {
+    matching_query.filter(is_file.ne(true).or(is_file.is_null()))
+  };
+
+  let matching_history = matching_query
+    .order(updated_date.desc())
+    .first::<ClipboardHistory>(connection)
+    .optional()?;
+
+  match (matching_history, policy) {
+    (Some(existing_history), DedupePolicy::Recent(recent_count)) => {
+      let newer_count: i64 = clipboard_history
+        .filter(updated_date.gt(existing_history.updated_date))
+        .count()
+        .get_result(connection)?;
+
+      Ok(Some(existing_history).filter(|_| newer_count < recent_count))
+    }
+    (matching_history, _) => Ok(matching_history),
+  }
 }
 
 pub fn get_first_clipboard_item() -> Result<ClipboardHistory, Error> {
@@ -1100,13 +2202,11 @@ pub fn count_clipboard_histories() -> Result<i64, Error> {
   clipboard_history.count().get_result::<i64>(connection)
 }
 
-pub fn insert_clipboard_history(new_clipboard_history: &ClipboardHistory) -> String {
+pub fn insert_clipboard_history(new_clipboard_history: &ClipboardHistory) -> Result<usize, Error> {
   let connection = &mut establish_pool_db_connection();
-  let _ = diesel::insert_into(clipboard_history)
+  diesel::insert_into(clipboard_history)
     .values(new_clipboard_history)
-    .execute(connection);
-
-  "ok".to_string()
+    .execute(connection)
 }
 
 pub fn update_clipboard_history_by_id(
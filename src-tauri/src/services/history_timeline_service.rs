@@ -0,0 +1,75 @@
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{BigInt, Text};
+use serde::Serialize;
+
+use crate::db::establish_pool_db_connection;
+
+pub const TIMELINE_BY_DAY: &str = "day";
+pub const TIMELINE_BY_HOUR: &str = "hour";
+
+// Entries are grouped by their local day or hour, which is converted back to UTC for the bucket
+// start. Each bucket ends where the next local day or hour starts, so days with a daylight
+// saving time change are 23 or 25 hours long.
+const HISTORY_TIMELINE_SQL: &str = "SELECT \
+  CAST(strftime('%s', local_bucket, 'utc') AS INTEGER) * 1000 AS bucket_start, \
+  CAST(strftime('%s', local_bucket, ?, 'utc') AS INTEGER) * 1000 AS bucket_end, \
+  COUNT(*) AS count \
+  FROM ( \
+    SELECT strftime(?, updated_at / 1000, 'unixepoch', 'localtime') AS local_bucket \
+    FROM clipboard_history \
+    WHERE updated_at >= ? AND updated_at < ? \
+  ) \
+  GROUP BY local_bucket \
+  ORDER BY local_bucket DESC";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineGranularity {
+  Day,
+  Hour,
+}
+
+impl TimelineGranularity {
+  pub fn from_name(granularity: Option<&str>) -> Self {
+    match granularity {
+      Some(TIMELINE_BY_HOUR) => TimelineGranularity::Hour,
+      _ => TimelineGranularity::Day,
+    }
+  }
+}
+
+#[derive(QueryableByName, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryTimelineBucket {
+  /// Start of the local day or hour in milliseconds
+  #[diesel(sql_type = BigInt)]
+  pub bucket_start: i64,
+  /// Start of the next day or hour, a cursor with this `updatedAt` and no `historyId` lists the
+  /// history from the newest entry of the bucket
+  #[diesel(sql_type = BigInt)]
+  pub bucket_end: i64,
+  #[diesel(sql_type = BigInt)]
+  pub count: i64,
+}
+
+/// Counts history entries per local day or hour, newest first. Only buckets with entries are
+/// returned, `from` and `to` limit the range by `updated_at` with `to` excluded.
+pub fn get_history_timeline(
+  granularity: TimelineGranularity,
+  from: Option<i64>,
+  to: Option<i64>,
+) -> Result<Vec<HistoryTimelineBucket>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let (bucket_format, bucket_length) = match granularity {
+    TimelineGranularity::Day => ("%Y-%m-%d 00:00:00", "+1 day"),
+    TimelineGranularity::Hour => ("%Y-%m-%d %H:00:00", "+1 hour"),
+  };
+
+  diesel::sql_query(HISTORY_TIMELINE_SQL)
+    .bind::<Text, _>(bucket_length)
+    .bind::<Text, _>(bucket_format)
+    .bind::<BigInt, _>(from.unwrap_or(0))
+    .bind::<BigInt, _>(to.unwrap_or(i64::MAX))
+    .load::<HistoryTimelineBucket>(connection)
+}
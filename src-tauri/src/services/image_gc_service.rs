@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use diesel::prelude::*;
+use diesel::result::Error;
+use serde::Serialize;
+
+use crate::db::{self, establish_pool_db_connection};
+use crate::schema::{clipboard_history, items};
+use crate::services::trash_service;
+use crate::services::utils::delete_file_and_maybe_parent;
+
+/// Unused image files are only deleted once they are this old, so an image saved just before its
+/// history entry or item is written is not taken
+pub const ORPHAN_IMAGE_GRACE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageGcReport {
+  pub deleted_count: usize,
+  pub reclaimed_bytes: u64,
+  /// Unused files newer than the grace period, left for a later run
+  pub skipped_recent_count: usize,
+  pub failed_count: usize,
+}
+
+/// All files in the folder and its subfolders
+pub fn list_files_recursively(dir_path: &Path) -> Vec<PathBuf> {
+  let mut files = Vec::new();
+
+  let entries = match fs::read_dir(dir_path) {
+    Ok(entries) => entries,
+    Err(_) => return files,
+  };
+
+  for entry in entries.flatten() {
+    let path = entry.path();
+
+    if path.is_dir() {
+      files.extend(list_files_recursively(&path));
+    } else {
+      files.push(path);
+    }
+  }
+
+  files
+}
+
+/// Path compared with the referenced images. Links and `..` are resolved, and where file names
+/// are case insensitive case and separators are ignored, so a used image never looks unused.
+fn image_path_key(path: &Path) -> PathBuf {
+  let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+  if cfg!(any(target_os = "macos", target_os = "windows")) {
+    PathBuf::from(path.to_string_lossy().replace('\\', "/").to_lowercase())
+  } else {
+    path
+  }
+}
+
+/// Paths of the images used by history entries, items and history entries in the trash, as
+/// compared by `image_path_key`
+pub fn get_referenced_image_paths() -> Result<HashSet<PathBuf>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let history_image_paths = clipboard_history::table
+    .filter(clipboard_history::image_path_full_res.is_not_null())
+    .select(clipboard_history::image_path_full_res)
+    .load::<Option<String>>(connection)?;
+  let item_image_paths = items::table
+    .filter(items::image_path_full_res.is_not_null())
+    .select(items::image_path_full_res)
+    .load::<Option<String>>(connection)?;
+  let trashed_image_paths = trash_service::get_trashed_history_image_paths()?;
+
+  Ok(
+    history_image_paths
+      .into_iter()
+      .chain(item_image_paths)
+      .flatten()
+      .chain(trashed_image_paths)
+      .map(|image_path| image_path_key(Path::new(&db::to_absolute_image_path(&image_path))))
+      .collect(),
+  )
+}
+
+/// Files in the folders that are not in `referenced_image_paths`
+fn find_unreferenced_files(
+  image_dirs: &[PathBuf],
+  referenced_image_paths: &HashSet<PathBuf>,
+) -> Vec<PathBuf> {
+  image_dirs
+    .iter()
+    .flat_map(|image_dir| list_files_recursively(image_dir))
+    .filter(|file_path| !referenced_image_paths.contains(&image_path_key(file_path)))
+    .collect()
+}
+
+/// Files in the clip and clipboard image folders that nothing uses
+pub fn find_orphan_image_files() -> Result<Vec<PathBuf>, Error> {
+  let referenced_image_paths = get_referenced_image_paths()?;
+
+  Ok(find_unreferenced_files(
+    &[db::get_clip_images_dir(), db::get_clipboard_images_dir()],
+    &referenced_image_paths,
+  ))
+}
+
+/// Deletes files in the clip and clipboard image folders that no history entry, item or trash
+/// entry uses and that were last changed more than `grace_period` ago
+pub fn collect_orphan_image_files(grace_period: Duration) -> Result<ImageGcReport, Error> {
+  let mut report = ImageGcReport::default();
+  let now = SystemTime::now();
+
+  for file_path in find_orphan_image_files()? {
+    let metadata = match fs::metadata(&file_path) {
+      Ok(metadata) => metadata,
+      Err(_) => continue,
+    };
+
+    let is_past_grace_period = metadata
+      .modified()
+      .ok()
+      .and_then(|modified_at| now.duration_since(modified_at).ok())
+      .map_or(false, |age| age > grace_period);
+
+    if !is_past_grace_period {
+      report.skipped_recent_count += 1;
+      continue;
+    }
+
+    match delete_file_and_maybe_parent(&file_path) {
+      Ok(_) => {
+        report.deleted_count += 1;
+        report.reclaimed_bytes += metadata.len();
+      }
+      Err(e) => {
+        eprintln!("Error deleting image file {}: {}", file_path.display(), e);
+        report.failed_count += 1;
+      }
+    }
+  }
+
+  Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn referenced_image_survives_a_gc_pass() {
+    let image_dir = tempfile::tempdir().unwrap();
+    let used_path = image_dir.path().join("history").join("used.png");
+    let unused_path = image_dir.path().join("history").join("unused.png");
+    fs::create_dir_all(used_path.parent().unwrap()).unwrap();
+    fs::write(&used_path, b"used").unwrap();
+    fs::write(&unused_path, b"unused").unwrap();
+
+    // Referenced through a path that is not written the same as the file on disk
+    let referenced_path = image_dir
+      .path()
+      .join("history")
+      .join("..")
+      .join("history")
+      .join("used.png");
+    let referenced_image_paths = HashSet::from([image_path_key(&referenced_path)]);
+
+    let orphan_files =
+      find_unreferenced_files(&[image_dir.path().to_path_buf()], &referenced_image_paths);
+
+    assert_eq!(orphan_files, vec![unused_path]);
+  }
+}
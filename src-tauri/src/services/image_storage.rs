@@ -0,0 +1,148 @@
+use image::{DynamicImage, GenericImageView, ImageFormat, ImageOutputFormat};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use crate::db;
+use crate::models::{ClipboardHistory, Setting};
+
+pub const IMAGE_FORMAT_PNG: &str = "png";
+pub const IMAGE_FORMAT_WEBP: &str = "webp";
+pub const IMAGE_FORMAT_JPEG: &str = "jpeg";
+
+const DEFAULT_IMAGE_QUALITY: i32 = 85;
+
+/// Encoding of the full resolution image saved for a history entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoredImageFormat {
+  Png,
+  /// Lossless WebP, the bundled encoder has no lossy mode
+  WebP,
+  /// Lossy JPEG with a quality from 1 to 100, transparency is flattened
+  Jpeg,
+}
+
+impl StoredImageFormat {
+  /// Rows saved before the format was recorded are PNG
+  pub fn from_name(format_name: Option<&str>) -> Self {
+    match format_name {
+      Some(IMAGE_FORMAT_WEBP) => StoredImageFormat::WebP,
+      Some(IMAGE_FORMAT_JPEG) => StoredImageFormat::Jpeg,
+      _ => StoredImageFormat::Png,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      StoredImageFormat::Png => IMAGE_FORMAT_PNG,
+      StoredImageFormat::WebP => IMAGE_FORMAT_WEBP,
+      StoredImageFormat::Jpeg => IMAGE_FORMAT_JPEG,
+    }
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      StoredImageFormat::Png => "png",
+      StoredImageFormat::WebP => "webp",
+      StoredImageFormat::Jpeg => "jpg",
+    }
+  }
+
+  pub fn image_format(&self) -> ImageFormat {
+    match self {
+      StoredImageFormat::Png => ImageFormat::Png,
+      StoredImageFormat::WebP => ImageFormat::WebP,
+      StoredImageFormat::Jpeg => ImageFormat::Jpeg,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageStorageOptions {
+  pub format: StoredImageFormat,
+  pub quality: u8,
+  /// Longest side of the stored image, larger images are downscaled keeping the aspect ratio
+  pub max_dimension: Option<u32>,
+  /// Keep only the preview thumbnail stored in the database, no image file is written
+  pub is_thumbnail_only: bool,
+}
+
+impl ImageStorageOptions {
+  pub fn from_settings(settings_map: &HashMap<String, Setting>) -> Self {
+    ImageStorageOptions {
+      format: StoredImageFormat::from_name(
+        settings_map
+          .get("historyImageStorageFormat")
+          .and_then(|s| s.value_text.as_deref()),
+      ),
+      quality: settings_map
+        .get("historyImageStorageQuality")
+        .and_then(|s| s.value_int)
+        .unwrap_or(DEFAULT_IMAGE_QUALITY)
+        .clamp(1, 100) as u8,
+      max_dimension: settings_map
+        .get("historyImageMaxDimension")
+        .and_then(|s| s.value_int)
+        .filter(|max_dimension| *max_dimension > 0)
+        .map(|max_dimension| max_dimension as u32),
+      is_thumbnail_only: settings_map
+        .get("isHistoryImageThumbnailOnlyEnabled")
+        .and_then(|s| s.value_bool)
+        .unwrap_or(false),
+    }
+  }
+
+  /// Downscales the image to the maximum dimension when it is larger
+  pub fn fit_to_max_dimension(&self, image: DynamicImage) -> DynamicImage {
+    match self.max_dimension {
+      Some(max_dimension) if image.width() > max_dimension || image.height() > max_dimension => {
+        image.resize(
+          max_dimension,
+          max_dimension,
+          image::imageops::FilterType::Lanczos3,
+        )
+      }
+      _ => image,
+    }
+  }
+
+  pub fn encode(&self, image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(Vec::new());
+
+    let result = match self.format {
+      StoredImageFormat::Png => image.write_to(&mut buffer, ImageOutputFormat::Png),
+      StoredImageFormat::WebP => {
+        DynamicImage::ImageRgba8(image.to_rgba8()).write_to(&mut buffer, ImageOutputFormat::WebP)
+      }
+      StoredImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_to(&mut buffer, ImageOutputFormat::Jpeg(self.quality)),
+    };
+
+    result.map_err(|e| e.to_string())?;
+    Ok(buffer.into_inner())
+  }
+}
+
+/// Reads the stored image of a history entry with its format. Falls back to the PNG
+/// thumbnail for entries saved as thumbnail only or when the image file is missing.
+pub fn read_history_image(history: &ClipboardHistory) -> Option<(Vec<u8>, StoredImageFormat)> {
+  history
+    .image_path_full_res
+    .as_ref()
+    .and_then(|path| std::fs::read(db::to_absolute_image_path(path)).ok())
+    .map(|image_bytes| {
+      (
+        image_bytes,
+        StoredImageFormat::from_name(history.image_format.as_deref()),
+      )
+    })
+    .or_else(|| {
+      history
+        .image_data_low_res
+        .clone()
+        .map(|image_bytes| (image_bytes, StoredImageFormat::Png))
+    })
+}
+
+pub fn decode_image(image_bytes: &[u8], format: StoredImageFormat) -> Result<DynamicImage, String> {
+  image::load_from_memory_with_format(image_bytes, format.image_format()).map_err(|e| e.to_string())
+}
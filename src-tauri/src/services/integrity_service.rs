@@ -0,0 +1,257 @@
+use std::path::Path;
+
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::Text;
+use serde::Serialize;
+
+use crate::db::{self, establish_pool_db_connection};
+use crate::models::models::{CollectionClips, CollectionMenu};
+use crate::schema::{
+  clipboard_history, clipboard_history_files, collection_clips, collection_menu, collections,
+  items, link_metadata, tabs,
+};
+use crate::services::image_gc_service::{
+  self, find_orphan_image_files, ImageGcReport, ORPHAN_IMAGE_GRACE_PERIOD,
+};
+
+/// Result row of `PRAGMA integrity_check`, a single "ok" when the database is fine
+#[derive(QueryableByName)]
+struct IntegrityCheckRow {
+  #[diesel(sql_type = Text)]
+  integrity_check: String,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+  /// Messages of `PRAGMA integrity_check`
+  pub integrity_check: Vec<String>,
+  pub is_database_ok: bool,
+  /// Board placements of missing items, collections or tabs
+  pub orphan_collection_clips: Vec<CollectionClips>,
+  /// Menu placements of missing items or collections
+  pub orphan_collection_menu: Vec<CollectionMenu>,
+  /// Link metadata of missing history entries or items
+  pub orphan_link_metadata_ids: Vec<String>,
+  /// Tabs of missing collections
+  pub orphan_tab_ids: Vec<String>,
+  /// Copied files of missing history entries
+  pub orphan_history_file_ids: Vec<String>,
+  /// History entries whose image file is missing
+  pub dangling_history_image_ids: Vec<String>,
+  /// Items whose image file is missing
+  pub dangling_item_image_ids: Vec<String>,
+  /// Files in the image folders that no history entry, item or trash entry uses
+  pub orphan_image_files: Vec<String>,
+  /// The problems found were fixed. Repair is skipped when the integrity check fails, and is
+  /// not complete when unused image files could not be deleted.
+  pub is_repaired: bool,
+  /// Orphan image files removed by the repair, files newer than the grace period are kept
+  pub image_gc: Option<ImageGcReport>,
+}
+
+impl IntegrityReport {
+  pub fn has_problems(&self) -> bool {
+    !self.is_database_ok
+      || !self.orphan_collection_clips.is_empty()
+      || !self.orphan_collection_menu.is_empty()
+      || !self.orphan_link_metadata_ids.is_empty()
+      || !self.orphan_tab_ids.is_empty()
+      || !self.orphan_history_file_ids.is_empty()
+      || !self.dangling_history_image_ids.is_empty()
+      || !self.dangling_item_image_ids.is_empty()
+      || !self.orphan_image_files.is_empty()
+  }
+}
+
+fn image_file_exists(image_path: &str) -> bool {
+  Path::new(&db::to_absolute_image_path(image_path)).is_file()
+}
+
+/// Checks the database file, rows that point at missing rows and image files that are missing
+/// or not used. With `repair`, the rows are fixed in one transaction and unused image files are
+/// deleted.
+pub fn check_database_integrity(repair: bool) -> Result<IntegrityReport, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let integrity_check: Vec<String> = diesel::sql_query("PRAGMA integrity_check")
+    .load::<IntegrityCheckRow>(connection)?
+    .into_iter()
+    .map(|row| row.integrity_check)
+    .collect();
+  let is_database_ok = integrity_check.len() == 1 && integrity_check[0] == "ok";
+
+  let orphan_collection_clips = collection_clips::table
+    .filter(
+      collection_clips::item_id
+        .ne_all(items::table.select(items::item_id))
+        .or(
+          collection_clips::collection_id
+            .ne_all(collections::table.select(collections::collection_id)),
+        )
+        .or(collection_clips::tab_id.ne_all(tabs::table.select(tabs::tab_id))),
+    )
+    .load::<CollectionClips>(connection)?;
+
+  let orphan_collection_menu = collection_menu::table
+    .filter(
+      collection_menu::item_id
+        .ne_all(items::table.select(items::item_id))
+        .or(
+          collection_menu::collection_id
+            .ne_all(collections::table.select(collections::collection_id)),
+        ),
+    )
+    .load::<CollectionMenu>(connection)?;
+
+  let orphan_link_metadata_ids = link_metadata::table
+    .filter(
+      link_metadata::history_id
+        .is_not_null()
+        .and(
+          link_metadata::history_id
+            .assume_not_null()
+            .ne_all(clipboard_history::table.select(clipboard_history::history_id)),
+        )
+        .or(
+          link_metadata::item_id.is_not_null().and(
+            link_metadata::item_id
+              .assume_not_null()
+              .ne_all(items::table.select(items::item_id)),
+          ),
+        ),
+    )
+    .select(link_metadata::metadata_id)
+    .load::<String>(connection)?;
+
+  let orphan_tab_ids = tabs::table
+    .filter(tabs::collection_id.ne_all(collections::table.select(collections::collection_id)))
+    .select(tabs::tab_id)
+    .load::<String>(connection)?;
+
+  let orphan_history_file_ids = clipboard_history_files::table
+    .filter(
+      clipboard_history_files::history_id
+        .ne_all(clipboard_history::table.select(clipboard_history::history_id)),
+    )
+    .select(clipboard_history_files::file_id)
+    .load::<String>(connection)?;
+
+  let dangling_history_image_ids: Vec<String> = clipboard_history::table
+    .filter(clipboard_history::image_path_full_res.is_not_null())
+    .select((
+      clipboard_history::history_id,
+      clipboard_history::image_path_full_res.assume_not_null(),
+    ))
+    .load::<(String, String)>(connection)?
+    .into_iter()
+    .filter(|(_, image_path)| !image_file_exists(image_path))
+    .map(|(history_id, _)| history_id)
+    .collect();
+
+  let dangling_item_image_ids: Vec<String> = items::table
+    .filter(items::image_path_full_res.is_not_null())
+    .select((items::item_id, items::image_path_full_res.assume_not_null()))
+    .load::<(String, String)>(connection)?
+    .into_iter()
+    .filter(|(_, image_path)| !image_file_exists(image_path))
+    .map(|(item_id, _)| item_id)
+    .collect();
+
+  let orphan_image_files = find_orphan_image_files()?;
+
+  let mut report = IntegrityReport {
+    integrity_check,
+    is_database_ok,
+    orphan_collection_clips,
+    orphan_collection_menu,
+    orphan_link_metadata_ids,
+    orphan_tab_ids,
+    orphan_history_file_ids,
+    dangling_history_image_ids,
+    dangling_item_image_ids,
+    orphan_image_files: orphan_image_files
+      .iter()
+      .map(|file_path| file_path.to_string_lossy().into_owned())
+      .collect(),
+    is_repaired: false,
+    image_gc: None,
+  };
+
+  if !repair || !report.has_problems() || !report.is_database_ok {
+    return Ok(report);
+  }
+
+  connection.transaction::<_, Error, _>(|connection| {
+    for clip in report.orphan_collection_clips.iter() {
+      diesel::delete(
+        collection_clips::table
+          .filter(collection_clips::collection_id.eq(&clip.collection_id))
+          .filter(collection_clips::item_id.eq(&clip.item_id)),
+      )
+      .execute(connection)?;
+    }
+
+    for menu in report.orphan_collection_menu.iter() {
+      diesel::delete(
+        collection_menu::table
+          .filter(collection_menu::collection_id.eq(&menu.collection_id))
+          .filter(collection_menu::item_id.eq(&menu.item_id)),
+      )
+      .execute(connection)?;
+    }
+
+    diesel::delete(
+      link_metadata::table
+        .filter(link_metadata::metadata_id.eq_any(&report.orphan_link_metadata_ids)),
+    )
+    .execute(connection)?;
+
+    // Placements on the removed tabs go with them
+    diesel::delete(
+      collection_clips::table.filter(collection_clips::tab_id.eq_any(&report.orphan_tab_ids)),
+    )
+    .execute(connection)?;
+    diesel::delete(tabs::table.filter(tabs::tab_id.eq_any(&report.orphan_tab_ids)))
+      .execute(connection)?;
+
+    diesel::delete(
+      clipboard_history_files::table
+        .filter(clipboard_history_files::file_id.eq_any(&report.orphan_history_file_ids)),
+    )
+    .execute(connection)?;
+
+    diesel::update(
+      clipboard_history::table
+        .filter(clipboard_history::history_id.eq_any(&report.dangling_history_image_ids)),
+    )
+    .set((
+      clipboard_history::image_path_full_res.eq(None::<String>),
+      clipboard_history::image_size.eq(None::<i64>),
+    ))
+    .execute(connection)?;
+
+    diesel::update(items::table.filter(items::item_id.eq_any(&report.dangling_item_image_ids)))
+      .set(items::image_path_full_res.eq(None::<String>))
+      .execute(connection)?;
+
+    Ok(())
+  })?;
+
+  // Files are deleted once the rows are fixed, a failed transaction leaves them in place
+  report.image_gc = match image_gc_service::collect_orphan_image_files(ORPHAN_IMAGE_GRACE_PERIOD) {
+    Ok(image_gc_report) => Some(image_gc_report),
+    Err(e) => {
+      eprintln!("Error deleting orphan image files: {}", e);
+      None
+    }
+  };
+
+  report.is_repaired = report
+    .image_gc
+    .as_ref()
+    .map_or(false, |image_gc_report| image_gc_report.failed_count == 0);
+
+  Ok(report)
+}
@@ -0,0 +1,662 @@
+use chrono::Utc;
+use diesel::dsl::{max, sql};
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::BigInt;
+use nanoid::nanoid;
+use serde::Serialize;
+
+use crate::db::establish_pool_db_connection;
+use crate::models::models::{ItemRevision, UpdatedItemData};
+use crate::schema::{item_revisions, items};
+
+/// Older revisions of an item are deleted when a new one is saved
+const MAX_REVISIONS_PER_ITEM: i64 = 20;
+
+/// Edits this soon after the previous edit of an item belong to the same editing burst, so an
+/// editor that saves while typing keeps the content from before the burst instead of every
+/// keystroke
+const REVISION_EDIT_BURST_GAP_MS: i64 = 60 * 1000;
+
+/// A long editing burst still saves a revision this often
+const REVISION_COALESCE_INTERVAL_MS: i64 = 5 * 60 * 1000;
+
+/// Insertion order of revisions, orders revisions saved in the same millisecond
+const REVISION_ROWID_SQL: &str = "item_revisions.rowid";
+
+/// Largest line diff computed line by line, bigger changes are shown as replaced whole
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Item fields saved in a revision
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct ItemContent {
+  name: String,
+  value: Option<String>,
+  description: Option<String>,
+  request_options: Option<String>,
+  form_template_options: Option<String>,
+  item_options: Option<String>,
+}
+
+impl ItemContent {
+  fn from_revision(revision: &ItemRevision) -> Self {
+    ItemContent {
+      name: revision.name.clone(),
+      value: revision.value.clone(),
+      description: revision.description.clone(),
+      request_options: revision.request_options.clone(),
+      form_template_options: revision.form_template_options.clone(),
+      item_options: revision.item_options.clone(),
+    }
+  }
+
+  /// Content after the update, fields the update leaves out stay the same
+  fn with_updates(&self, updated_data: &UpdatedItemData) -> Self {
+    ItemContent {
+      name: updated_data
+        .name
+        .clone()
+        .unwrap_or_else(|| self.name.clone()),
+      value: updated_data.value.clone().or_else(|| self.value.clone()),
+      description: updated_data
+        .description
+        .clone()
+        .or_else(|| self.description.clone()),
+      request_options: updated_data
+        .request_options
+        .clone()
+        .or_else(|| self.request_options.clone()),
+      form_template_options: updated_data
+        .form_template_options
+        .clone()
+        .or_else(|| self.form_template_options.clone()),
+      item_options: updated_data
+        .item_options
+        .clone()
+        .or_else(|| self.item_options.clone()),
+    }
+  }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffChange {
+  Equal,
+  Insert,
+  Delete,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+  pub change: DiffChange,
+  /// Line number in the older value, starting at 1, none for inserted lines
+  pub old_line_number: Option<usize>,
+  /// Line number in the newer value, starting at 1, none for deleted lines
+  pub new_line_number: Option<usize>,
+  pub text: String,
+}
+
+fn select_item_content(
+  connection: &mut SqliteConnection,
+  item_id: &str,
+) -> Result<Option<ItemContent>, Error> {
+  items::table
+    .find(item_id)
+    .select((
+      items::name,
+      items::value,
+      items::description,
+      items::request_options,
+      items::form_template_options,
+      items::item_options,
+    ))
+    .first::<ItemContent>(connection)
+    .optional()
+}
+
+/// Saves the current content of an item as a revision before an update changes it. Runs on the
+/// connection of the update, so the revision is only kept when the update is.
+pub fn record_item_revision_for_update(
+  connection: &mut SqliteConnection,
+  item_id: &str,
+  updated_data: &UpdatedItemData,
+) -> Result<Option<ItemRevision>, Error> {
+  let is_content_updated = updated_data.name.is_some()
+    || updated_data.value.is_some()
+    || updated_data.description.is_some()
+    || updated_data.request_options.is_some()
+    || updated_data.form_template_options.is_some()
+    || updated_data.item_options.is_some();
+
+  if !is_content_updated {
+    return Ok(None);
+  }
+
+  save_revision_if_changed(connection, item_id, true, |current_content| {
+    current_content.with_updates(updated_data)
+  })
+}
+
+/// Saves the current content of an item as a revision before its value is replaced, on the
+/// connection of the update
+pub fn record_item_revision_for_value(
+  connection: &mut SqliteConnection,
+  item_id: &str,
+  new_value: Option<&String>,
+) -> Result<Option<ItemRevision>, Error> {
+  save_revision_if_changed(connection, item_id, true, |current_content| ItemContent {
+    value: new_value.cloned(),
+    ..current_content.clone()
+  })
+}
+
+/// Saves the current content as a revision when `next_content` changes it. With `is_coalesced`,
+/// nothing is saved while the item is edited in a burst, less than `REVISION_EDIT_BURST_GAP_MS`
+/// after its previous edit, and its newest revision is more recent than
+/// `REVISION_COALESCE_INTERVAL_MS`.
+fn save_revision_if_changed<F>(
+  connection: &mut SqliteConnection,
+  item_id: &str,
+  is_coalesced: bool,
+  next_content: F,
+) -> Result<Option<ItemRevision>, Error>
+where
+  F: FnOnce(&ItemContent) -> ItemContent,
+{
+  let current_content = match select_item_content(connection, item_id)? {
+    Some(current_content) => current_content,
+    None => return Ok(None),
+  };
+
+  if next_content(&current_content) == current_content {
+    return Ok(None);
+  }
+
+  let now = Utc::now().timestamp_millis();
+
+  if is_coalesced {
+    let newest_revision_at = item_revisions::table
+      .filter(item_revisions::item_id.eq(item_id))
+      .select(max(item_revisions::created_at))
+      .first::<Option<i64>>(connection)?;
+
+    let is_recently_saved = newest_revision_at.map_or(false, |created_at| {
+      now - created_at < REVISION_COALESCE_INTERVAL_MS
+    });
+
+    let item_updated_at = items::table
+      .find(item_id)
+      .select(items::updated_at)
+      .first::<i64>(connection)?;
+    let is_edit_burst = now - item_updated_at < REVISION_EDIT_BURST_GAP_MS;
+
+    if is_recently_saved && is_edit_burst {
+      return Ok(None);
+    }
+  }
+
+  let revision = ItemRevision {
+    revision_id: nanoid!().to_string(),
+    item_id: item_id.to_string(),
+    name: current_content.name,
+    value: current_content.value,
+    description: current_content.description,
+    request_options: current_content.request_options,
+    form_template_options: current_content.form_template_options,
+    item_options: current_content.item_options,
+    created_at: now,
+  };
+
+  diesel::insert_into(item_revisions::table)
+    .values(&revision)
+    .execute(connection)?;
+
+  let expired_revision_ids = item_revisions::table
+    .filter(item_revisions::item_id.eq(item_id))
+    .order((
+      item_revisions::created_at.desc(),
+      sql::<BigInt>(REVISION_ROWID_SQL).desc(),
+    ))
+    .select(item_revisions::revision_id)
+    .offset(MAX_REVISIONS_PER_ITEM)
+    .limit(-1)
+    .load::<String>(connection)?;
+
+  if !expired_revision_ids.is_empty() {
+    diesel::delete(
+      item_revisions::table.filter(item_revisions::revision_id.eq_any(expired_revision_ids)),
+    )
+    .execute(connection)?;
+  }
+
+  Ok(Some(revision))
+}
+
+/// Revisions of an item, newest first
+pub fn get_item_revisions(item_id: &str) -> Result<Vec<ItemRevision>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  item_revisions::table
+    .filter(item_revisions::item_id.eq(item_id))
+    .order((
+      item_revisions::created_at.desc(),
+      sql::<BigInt>(REVISION_ROWID_SQL).desc(),
+    ))
+    .load::<ItemRevision>(connection)
+}
+
+/// Line diff of the values of two revisions of the same item, or of a revision and the current
+/// value when `to_revision_id` is not given
+pub fn diff_item_revisions(
+  from_revision_id: &str,
+  to_revision_id: Option<&str>,
+) -> Result<Vec<DiffLine>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let from_revision = item_revisions::table
+    .find(from_revision_id)
+    .first::<ItemRevision>(connection)?;
+
+  let to_value = match to_revision_id {
+    Some(to_revision_id) => {
+      item_revisions::table
+        .find(to_revision_id)
+        .filter(item_revisions::item_id.eq(&from_revision.item_id))
+        .first::<ItemRevision>(connection)?
+        .value
+    }
+    None => items::table
+      .find(&from_revision.item_id)
+      .select(items::value)
+      .first::<Option<String>>(connection)?,
+  };
+
+  Ok(diff_lines(
+    from_revision.value.as_deref().unwrap_or_default(),
+    to_value.as_deref().unwrap_or_default(),
+  ))
+}
+
+/// Puts the content of a revision back on its item. The content it replaces is saved as a new
+/// revision first, so a restore can be undone.
+pub fn restore_item_revision(revision_id: &str) -> Result<String, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let revision = item_revisions::table
+    .find(revision_id)
+    .first::<ItemRevision>(connection)?;
+  let restored_content = ItemContent::from_revision(&revision);
+
+  connection.transaction::<_, Error, _>(|connection| {
+    save_revision_if_changed(connection, &revision.item_id, false, |_| {
+      restored_content.clone()
+    })?;
+
+    diesel::update(items::table.find(&revision.item_id))
+      .set((
+        items::name.eq(restored_content.name),
+        items::value.eq(restored_content.value),
+        items::description.eq(restored_content.description),
+        items::request_options.eq(restored_content.request_options),
+        items::form_template_options.eq(restored_content.form_template_options),
+        items::item_options.eq(restored_content.item_options),
+        items::updated_at.eq(Utc::now().timestamp_millis()),
+        items::updated_date.eq(Utc::now().naive_utc()),
+      ))
+      .execute(connection)
+  })?;
+
+  Ok("ok".to_string())
+}
+
+/// Removes revisions of items that no longer exist
+pub fn delete_orphan_item_revisions() -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::delete(
+    item_revisions::table
+      .filter(item_revisions::item_id.ne_all(items::table.select(items::item_id))),
+  )
+  .execute(connection)
+}
+
+/// Longest common subsequence diff of the lines, after skipping the lines both texts start and
+/// end with
+fn diff_lines(old_text: &str, new_text: &str) -> Vec<DiffLine> {
+  let old_lines: Vec<&str> = old_text.lines().collect();
+  let new_lines: Vec<&str> = new_text.lines().collect();
+
+  let prefix_len = old_lines
+    .iter()
+    .zip(new_lines.iter())
+    .take_while(|(old_line, new_line)| old_line == new_line)
+    .count();
+  let suffix_len = old_lines[prefix_len..]
+    .iter()
+    .rev()
+    .zip(new_lines[prefix_len..].iter().rev())
+    .take_while(|(old_line, new_line)| old_line == new_line)
+    .count();
+
+  let old_middle = &old_lines[prefix_len..old_lines.len() - suffix_len];
+  let new_middle = &new_lines[prefix_len..new_lines.len() - suffix_len];
+
+  let mut diff = Vec::with_capacity(old_lines.len().max(new_lines.len()));
+  let mut old_line_number = 0;
+  let mut new_line_number = 0;
+
+  let mut push_line = |change: DiffChange, text: &str| {
+    let (old_number, new_number) = match change {
+      DiffChange::Equal => {
+        old_line_number += 1;
+        new_line_number += 1;
+        (Some(old_line_number), Some(new_line_number))
+      }
+      DiffChange::Delete => {
+        old_line_number += 1;
+        (Some(old_line_number), None)
+      }
+      DiffChange::Insert => {
+        new_line_number += 1;
+        (None, Some(new_line_number))
+      }
+    };
+
+    diff.push(DiffLine {
+      change,
+      old_line_number: old_number,
+      new_line_number: new_number,
+      text: text.to_string(),
+    });
+  };
+
+  for line in &old_lines[..prefix_len] {
+    push_line(DiffChange::Equal, line);
+  }
+
+  for (change, line) in diff_middle_lines(old_middle, new_middle) {
+    push_line(change, line);
+  }
+
+  for line in &old_lines[old_lines.len() - suffix_len..] {
+    push_line(DiffChange::Equal, line);
+  }
+
+  diff
+}
+
+fn diff_middle_lines<'a>(
+  old_lines: &[&'a str],
+  new_lines: &[&'a str],
+) -> Vec<(DiffChange, &'a str)> {
+  let (old_len, new_len) = (old_lines.len(), new_lines.len());
+
+  if old_len * new_len > MAX_DIFF_CELLS {
+    return old_lines
+      .iter()
+      .map(|line| (DiffChange::Delete, *line))
+      .chain(new_lines.iter().map(|line| (DiffChange::Insert, *line)))
+      .collect();
+  }
+
+  // common_lengths[i][j] is the LCS length of old_lines[i..] and new_lines[j..]
+  let mut common_lengths = vec![vec![0u32; new_len + 1]; old_len + 1];
+
+  for i in (0..old_len).rev() {
+    for j in (0..new_len).rev() {
+      common_lengths[i][j] = if old_lines[i] == new_lines[j] {
+        common_lengths[i + 1][j + 1] + 1
+      } else {
+        common_lengths[i + 1][j].max(common_lengths[i][j + 1])
+      };
+    }
+  }
+
+  let mut changes = Vec::with_capacity(old_len + new_len);
+  let (mut i, mut j) = (0, 0);
+
+  while i < old_len && j < new_len {
+    if old_lines[i] == new_lines[j] {
+      changes.push((DiffChange::Equal, old_lines[i]));
+      i += 1;
+      j += 1;
+    } else if common_lengths[i + 1][j] >= common_lengths[i][j + 1] {
+      changes.push((DiffChange::Delete, old_lines[i]));
+      i += 1;
+    } else {
+      changes.push((DiffChange::Insert, new_lines[j]));
+      j += 1;
+    }
+  }
+
+  changes.extend(
+    old_lines[i..]
+      .iter()
+      .map(|line| (DiffChange::Delete, *line)),
+  );
+  changes.extend(
+    new_lines[j..]
+      .iter()
+      .map(|line| (DiffChange::Insert, *line)),
+  );
+
+  changes
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use diesel::connection::SimpleConnection;
+
+  use crate::db::establish_test_connection;
+
+  fn changes(diff: &[DiffLine]) -> Vec<(DiffChange, Option<usize>, Option<usize>, &str)> {
+    diff
+      .iter()
+      .map(|line| {
+        (
+          line.change,
+          line.old_line_number,
+          line.new_line_number,
+          line.text.as_str(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn diff_lines_marks_inserted_lines() {
+    let diff = diff_lines("a\nc", "a\nb\nc");
+
+    assert_eq!(
+      changes(&diff),
+      vec![
+        (DiffChange::Equal, Some(1), Some(1), "a"),
+        (DiffChange::Insert, None, Some(2), "b"),
+        (DiffChange::Equal, Some(2), Some(3), "c"),
+      ]
+    );
+  }
+
+  #[test]
+  fn diff_lines_marks_deleted_lines() {
+    let diff = diff_lines("a\nb\nc\nd", "a\nd");
+
+    assert_eq!(
+      changes(&diff),
+      vec![
+        (DiffChange::Equal, Some(1), Some(1), "a"),
+        (DiffChange::Delete, Some(2), None, "b"),
+        (DiffChange::Delete, Some(3), None, "c"),
+        (DiffChange::Equal, Some(4), Some(2), "d"),
+      ]
+    );
+  }
+
+  #[test]
+  fn diff_lines_shows_a_changed_line_as_deleted_and_inserted() {
+    let diff = diff_lines("a\nb\nc\nd\ne", "a\nB\nc\nd\nE");
+
+    assert_eq!(
+      changes(&diff),
+      vec![
+        (DiffChange::Equal, Some(1), Some(1), "a"),
+        (DiffChange::Delete, Some(2), None, "b"),
+        (DiffChange::Insert, None, Some(2), "B"),
+        (DiffChange::Equal, Some(3), Some(3), "c"),
+        (DiffChange::Equal, Some(4), Some(4), "d"),
+        (DiffChange::Delete, Some(5), None, "e"),
+        (DiffChange::Insert, None, Some(5), "E"),
+      ]
+    );
+  }
+
+  #[test]
+  fn diff_lines_of_empty_and_equal_texts() {
+    assert!(diff_lines("", "").is_empty());
+    assert_eq!(
+      changes(&diff_lines("", "a")),
+      vec![(DiffChange::Insert, None, Some(1), "a")]
+    );
+    assert!(diff_lines("a\nb", "a\nb")
+      .iter()
+      .all(|line| line.change == DiffChange::Equal));
+  }
+
+  #[test]
+  fn diff_lines_replaces_whole_middle_past_the_cell_limit() {
+    let old_text: Vec<String> = (0..2001).map(|n| format!("old {}", n)).collect();
+    let new_text: Vec<String> = (0..2001).map(|n| format!("new {}", n)).collect();
+    let old_text = format!("first\n{}\nlast", old_text.join("\n"));
+    let new_text = format!("first\n{}\nlast", new_text.join("\n"));
+
+    let diff = diff_lines(&old_text, &new_text);
+
+    assert_eq!(diff.len(), 2 + 2001 * 2);
+    assert_eq!(diff[0].change, DiffChange::Equal);
+    assert!(diff[1..2002]
+      .iter()
+      .all(|line| line.change == DiffChange::Delete));
+    assert!(diff[2002..4003]
+      .iter()
+      .all(|line| line.change == DiffChange::Insert));
+    assert_eq!(
+      changes(&diff[4003..]),
+      vec![(DiffChange::Equal, Some(2003), Some(2003), "last")]
+    );
+  }
+
+  fn connection_with_item() -> SqliteConnection {
+    let mut connection = establish_test_connection();
+    connection
+      .batch_execute(
+        "INSERT INTO items (item_id, name, value, is_deleted, is_folder, is_separator, is_board, \
+           is_clip, is_menu, is_disabled, created_at, updated_at, created_date, updated_date) \
+           VALUES ('item', 'Item', 'first', 0, 0, 0, 0, 1, 0, 0, 0, 0, '2025-01-01 00:00:00', \
+           '2025-01-01 00:00:00');",
+      )
+      .unwrap();
+    connection
+  }
+
+  fn set_item_value(connection: &mut SqliteConnection, item_value: &str) {
+    set_item_value_edited_ago(connection, item_value, 0);
+  }
+
+  fn set_item_value_edited_ago(
+    connection: &mut SqliteConnection,
+    item_value: &str,
+    edited_ago_ms: i64,
+  ) {
+    diesel::update(items::table.find("item"))
+      .set((
+        items::value.eq(item_value),
+        items::updated_at.eq(Utc::now().timestamp_millis() - edited_ago_ms),
+      ))
+      .execute(connection)
+      .unwrap();
+  }
+
+  fn revision_values(connection: &mut SqliteConnection) -> Vec<Option<String>> {
+    item_revisions::table
+      .filter(item_revisions::item_id.eq("item"))
+      .order((
+        item_revisions::created_at.desc(),
+        sql::<BigInt>(REVISION_ROWID_SQL).desc(),
+      ))
+      .select(item_revisions::value)
+      .load(connection)
+      .unwrap()
+  }
+
+  #[test]
+  fn rapid_saves_keep_the_content_from_before_the_edits() {
+    let connection = &mut connection_with_item();
+
+    for next_value in ["second", "third", "fourth"] {
+      let next_value = next_value.to_string();
+      record_item_revision_for_value(connection, "item", Some(&next_value)).unwrap();
+      set_item_value(connection, &next_value);
+    }
+
+    assert_eq!(revision_values(connection), vec![Some("first".to_string())]);
+  }
+
+  #[test]
+  fn edit_after_a_pause_saves_the_content_of_the_previous_edit() {
+    let connection = &mut connection_with_item();
+
+    record_item_revision_for_value(connection, "item", Some(&"second".to_string())).unwrap();
+    set_item_value_edited_ago(connection, "second", 4 * 60 * 1000);
+    record_item_revision_for_value(connection, "item", Some(&"third".to_string())).unwrap();
+
+    assert_eq!(
+      revision_values(connection),
+      vec![Some("second".to_string()), Some("first".to_string())]
+    );
+  }
+
+  #[test]
+  fn unchanged_content_saves_no_revision() {
+    let connection = &mut connection_with_item();
+
+    let revision =
+      record_item_revision_for_value(connection, "item", Some(&"first".to_string())).unwrap();
+
+    assert_eq!(revision, None);
+    assert!(revision_values(connection).is_empty());
+  }
+
+  #[test]
+  fn pruning_keeps_the_newest_revisions_saved_in_the_same_millisecond() {
+    let connection = &mut connection_with_item();
+
+    for n in 0..MAX_REVISIONS_PER_ITEM + 5 {
+      let next_value = format!("value {}", n);
+      save_revision_if_changed(connection, "item", false, |current_content| ItemContent {
+        value: Some(next_value.clone()),
+        ..current_content.clone()
+      })
+      .unwrap();
+      set_item_value(connection, &next_value);
+    }
+
+    diesel::update(item_revisions::table)
+      .set(item_revisions::created_at.eq(0))
+      .execute(connection)
+      .unwrap();
+    save_revision_if_changed(connection, "item", false, |current_content| ItemContent {
+      value: Some("last".to_string()),
+      ..current_content.clone()
+    })
+    .unwrap();
+
+    let values = revision_values(connection);
+
+    assert_eq!(values.len(), MAX_REVISIONS_PER_ITEM as usize);
+    assert_eq!(values[0], Some("value 24".to_string()));
+    assert!(values.contains(&Some("value 5".to_string())));
+    assert!(!values.contains(&Some("value 4".to_string())));
+  }
+}
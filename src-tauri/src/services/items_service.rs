@@ -2,24 +2,27 @@ use std::fs;
 use std::path::Path;
 
 use crate::db::{self};
-use crate::models::models::{CollectionMenu, UpdatedItemData};
+use crate::models::models::{ClipboardHistory, CollectionMenu, UpdatedItemData};
 use crate::models::Item;
 use crate::services::utils::debug_output;
 use image::ImageFormat;
 
-use crate::schema::collection_clips::dsl::collection_clips;
-use crate::schema::collection_clips::dsl::{self as collection_clips_dsl};
 use crate::schema::items::dsl::items;
 use crate::schema::items::{
   all_columns, command_request_last_run_at, command_request_output, image_data_url, image_hash,
   image_height, image_path_full_res, image_preview_height, image_scale, image_type, image_width,
-  is_clip, is_image, is_menu, is_pinned, item_id as item_id_field, pinned_order_number, updated_at,
-  updated_date, value,
+  is_clip, is_deleted, is_image, is_masked, is_menu, is_pinned, item_id as item_id_field,
+  pinned_order_number, updated_at, updated_date, value,
 };
 use std::io::Cursor;
 
 use crate::schema::collection_menu::dsl::{self as collection_menu_dsl, collection_menu};
 use crate::services::history_service::ensure_dir_exists;
+use crate::services::image_storage;
+use crate::services::item_revisions_service;
+use crate::services::regex_search_service::{
+  scan_rows_for_matches, RegexSearchResult, SearchMatchRange, MAX_SEARCHED_VALUE_CHARS,
+};
 use base64::engine::general_purpose::STANDARD_NO_PAD;
 use base64::Engine as _;
 use image::{self, imageops::FilterType, DynamicImage, GenericImageView, ImageOutputFormat};
@@ -27,8 +30,13 @@ use image::{self, imageops::FilterType, DynamicImage, GenericImageView, ImageOut
 use serde::{Deserialize, Serialize};
 
 use crate::db::establish_pool_db_connection;
+use diesel::connection::DefaultLoadingMode;
+use diesel::dsl::sql;
 use diesel::prelude::*;
 use diesel::result::Error;
+use diesel::sql_types::{Nullable, Text};
+use regex::Regex;
+use std::collections::HashMap;
 
 use super::utils::delete_file_and_maybe_parent;
 
@@ -93,6 +101,13 @@ pub struct ItemWithImage {
   pub image_path_full_res: Option<String>,
 }
 
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemRegexMatch {
+  pub item: Item,
+  pub match_ranges: Vec<SearchMatchRange>,
+}
+
 pub fn get_item_by_id(item_id: String) -> Result<Item, String> {
   let connection = &mut establish_pool_db_connection();
 
@@ -111,6 +126,59 @@ pub fn get_item_by_id(item_id: String) -> Result<Item, String> {
   }
 }
 
+/// Finds clips and menu items whose value matches the regex, most recently updated first.
+/// Masked and deleted items are not searched.
+pub fn find_items_by_regex(
+  regex: &Regex,
+  max_results: i64,
+) -> Result<RegexSearchResult<ItemRegexMatch>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let scan = {
+    let rows = items
+      .filter(value.is_not_null())
+      .filter(is_deleted.eq(false))
+      .filter(is_masked.is_null().or(is_masked.eq(false)))
+      .order(updated_at.desc())
+      .select((
+        item_id_field,
+        sql::<Nullable<Text>>(&format!(
+          "substr(items.value, 1, {})",
+          MAX_SEARCHED_VALUE_CHARS
+        )),
+      ))
+      .load_iter::<(String, Option<String>), DefaultLoadingMode>(connection)?;
+
+    scan_rows_for_matches(rows, regex, max_results.max(0) as usize)?
+  };
+
+  let matched_item_ids: Vec<String> = scan.matches.iter().map(|(id, _)| id.clone()).collect();
+  let mut match_ranges_by_id: HashMap<String, Vec<SearchMatchRange>> =
+    scan.matches.into_iter().collect();
+
+  let matched_items = items
+    .filter(item_id_field.eq_any(&matched_item_ids))
+    .select(all_columns)
+    .order(updated_at.desc())
+    .load::<Item>(connection)?;
+
+  let results = matched_items
+    .into_iter()
+    .filter_map(|mut item| {
+      let match_ranges = match_ranges_by_id.remove(&item.item_id)?;
+      item.transform_image_path_for_frontend();
+
+      Some(ItemRegexMatch { item, match_ranges })
+    })
+    .collect();
+
+  Ok(RegexSearchResult {
+    results,
+    scanned_count: scan.scanned_count,
+    is_timed_out: scan.is_timed_out,
+  })
+}
+
 pub fn update_item_is_menu_by_id(item_id: String, is_menu_value: bool) -> String {
   let connection = &mut establish_pool_db_connection();
 
@@ -132,40 +200,49 @@ pub fn update_item_by_id(item_id: String, updated_data: UpdatedItemData) -> Stri
     || updated_data.is_text.is_some()
     || updated_data.is_image.is_some();
 
-  if updated_data.is_command.is_some()
-    || updated_data.is_web_scraping.is_some()
-    || updated_data.is_web_request.is_some()
-  {
-    let _ = diesel::update(items.find(item_id))
-      .set((
-        updated_data,
-        command_request_output.eq::<Option<String>>(None),
-        command_request_last_run_at.eq::<Option<i64>>(None),
-      ))
-      .execute(connection);
-  } else if updated_data.command_request_output.is_some() {
-    let _ = diesel::update(items.find(item_id))
-      .set((
-        updated_data,
-        command_request_last_run_at.eq(chrono::Utc::now().timestamp_millis()),
-      ))
-      .execute(connection);
-  } else if should_update_timestamps {
-    let _ = diesel::update(items.find(item_id))
-      .set((
-        updated_data,
-        updated_at.eq(chrono::Utc::now().timestamp_millis()),
-        updated_date.eq(chrono::Utc::now().naive_utc()),
-      ))
-      .execute(connection);
-  } else {
-    // debug_output(|| {
-    // println!("Updated_data: {:?}", updated_data);
-    // });
+  // The revision of the replaced content is saved with the update or not at all
+  let result = connection.transaction::<_, Error, _>(|connection| {
+    item_revisions_service::record_item_revision_for_update(connection, &item_id, &updated_data)?;
 
-    let _ = diesel::update(items.find(item_id))
-      .set(updated_data)
-      .execute(connection);
+    if updated_data.is_command.is_some()
+      || updated_data.is_web_scraping.is_some()
+      || updated_data.is_web_request.is_some()
+    {
+      diesel::update(items.find(&item_id))
+        .set((
+          updated_data,
+          command_request_output.eq::<Option<String>>(None),
+          command_request_last_run_at.eq::<Option<i64>>(None),
+        ))
+        .execute(connection)
+    } else if updated_data.command_request_output.is_some() {
+      diesel::update(items.find(&item_id))
+        .set((
+          updated_data,
+          command_request_last_run_at.eq(chrono::Utc::now().timestamp_millis()),
+        ))
+        .execute(connection)
+    } else if should_update_timestamps {
+      diesel::update(items.find(&item_id))
+        .set((
+          updated_data,
+          updated_at.eq(chrono::Utc::now().timestamp_millis()),
+          updated_date.eq(chrono::Utc::now().naive_utc()),
+        ))
+        .execute(connection)
+    } else {
+      // debug_output(|| {
+      // println!("Updated_data: {:?}", updated_data);
+      // });
+
+      diesel::update(items.find(&item_id))
+        .set(updated_data)
+        .execute(connection)
+    }
+  });
+
+  if let Err(e) = result {
+    eprintln!("Error updating item: {}", e);
   }
 
   "ok".to_string()
@@ -173,39 +250,48 @@ pub fn update_item_by_id(item_id: String, updated_data: UpdatedItemData) -> Stri
 
 pub fn update_item_value_by_id(item_id: String, value_text: Option<String>) -> String {
   let connection = &mut establish_pool_db_connection();
-  let _ = diesel::update(items.find(item_id))
-    .set((
-      value.eq(value_text),
-      updated_at.eq(chrono::Utc::now().timestamp_millis()),
-      updated_date.eq(chrono::Utc::now().naive_utc()),
-    ))
-    .execute(connection);
+
+  let result = connection.transaction::<_, Error, _>(|connection| {
+    item_revisions_service::record_item_revision_for_value(
+      connection,
+      &item_id,
+      value_text.as_ref(),
+    )?;
+
+    diesel::update(items.find(&item_id))
+      .set((
+        value.eq(&value_text),
+        updated_at.eq(chrono::Utc::now().timestamp_millis()),
+        updated_date.eq(chrono::Utc::now().naive_utc()),
+      ))
+      .execute(connection)
+  });
+
+  if let Err(e) = result {
+    eprintln!("Error updating item value: {}", e);
+  }
 
   "ok".to_string()
 }
 
 pub fn update_item_image_by_id(
   item_id: &str,
+  history_item: &ClipboardHistory,
   history_item_image_data_url: Option<String>,
-  history_item_image_height: Option<i32>,
-  history_item_image_width: Option<i32>,
-  history_item_image_preview_height: Option<i32>,
-  history_item_image_hash: Option<String>,
-  image_path: &str,
 ) -> Result<String, String> {
-  match save_item_image_from_history_item(item_id, image_path) {
-    Ok(clip_image_file_name) => {
+  match save_item_image_from_history_item(item_id, history_item) {
+    Ok((clip_image_file_name, clip_image_type)) => {
       let connection = &mut establish_pool_db_connection();
 
       let _ = diesel::update(items.find(item_id))
         .set((
           image_path_full_res.eq(clip_image_file_name),
           image_data_url.eq(history_item_image_data_url),
-          image_height.eq(history_item_image_height),
-          image_width.eq(history_item_image_width),
-          image_preview_height.eq(history_item_image_preview_height),
-          image_hash.eq(history_item_image_hash),
-          image_type.eq("png"),
+          image_height.eq(history_item.image_height),
+          image_width.eq(history_item.image_width),
+          image_preview_height.eq(history_item.image_preview_height),
+          image_hash.eq(&history_item.image_hash),
+          image_type.eq(clip_image_type),
           updated_at.eq(chrono::Utc::now().timestamp_millis()),
           updated_date.eq(chrono::Utc::now().naive_utc()),
         ))
@@ -219,13 +305,28 @@ pub fn update_item_image_by_id(
 
 pub fn update_items_by_ids(item_ids: &[String], updated_data: UpdatedItemData) -> String {
   let connection = &mut establish_pool_db_connection();
-  let _ = diesel::update(items.filter(item_id_field.eq_any(item_ids)))
-    .set((
-      updated_data,
-      updated_at.eq(chrono::Utc::now().timestamp_millis()),
-      updated_date.eq(chrono::Utc::now().naive_utc()),
-    ))
-    .execute(connection);
+
+  let result = connection.transaction::<_, Error, _>(|connection| {
+    for updated_item_id in item_ids {
+      item_revisions_service::record_item_revision_for_update(
+        connection,
+        updated_item_id,
+        &updated_data,
+      )?;
+    }
+
+    diesel::update(items.filter(item_id_field.eq_any(item_ids)))
+      .set((
+        updated_data,
+        updated_at.eq(chrono::Utc::now().timestamp_millis()),
+        updated_date.eq(chrono::Utc::now().naive_utc()),
+      ))
+      .execute(connection)
+  });
+
+  if let Err(e) = result {
+    eprintln!("Error updating items: {}", e);
+  }
 
   "ok".to_string()
 }
@@ -279,108 +380,6 @@ pub fn delete_image_by_item_by_id(item_id: String) -> String {
   "ok".to_string()
 }
 
-pub fn delete_item_by_id(item_id: String, collection_id: String) -> String {
-  let connection = &mut establish_pool_db_connection();
-
-  let item_with_images_to_delete = match items
-    .find(&item_id)
-    .select((is_image, image_path_full_res))
-    .first::<ItemWithImage>(connection)
-  {
-    Ok(item) => item,
-    Err(e) => {
-      debug_output(|| {
-        eprintln!("Error loading item: {}", e);
-      });
-      return format!("Error loading item: {}", e);
-    }
-  };
-
-  if let Some(ref path) = item_with_images_to_delete.image_path_full_res {
-    match delete_file_and_maybe_parent(&Path::new(path)) {
-      Ok(_) => {
-        debug_output(|| {
-          println!("Successfully deleted image file: {}", path);
-        });
-      }
-      Err(e) => {
-        debug_output(|| {
-          eprintln!("Error deleting image file {}: {}", path, e);
-        });
-      }
-    }
-  }
-
-  let _ = diesel::delete(items.find(&item_id)).execute(connection);
-
-  let _ = diesel::delete(
-    collection_clips
-      .filter(collection_clips_dsl::collection_id.eq(&collection_id))
-      .filter(collection_clips_dsl::item_id.eq(&item_id)),
-  )
-  .execute(connection);
-
-  let _ = diesel::delete(
-    collection_menu
-      .filter(collection_menu_dsl::collection_id.eq(&collection_id))
-      .filter(collection_menu_dsl::item_id.eq(&item_id)),
-  )
-  .execute(connection);
-
-  "ok".to_string()
-}
-
-pub fn delete_items_by_ids(item_ids: &[String], collection_id: String) -> String {
-  let connection = &mut establish_pool_db_connection();
-
-  let items_with_images: Vec<ItemWithImage> = match items
-    .filter(item_id_field.eq_any(item_ids))
-    .select((is_image, image_path_full_res))
-    .load::<ItemWithImage>(connection)
-  {
-    Ok(result_items) => result_items,
-    Err(e) => {
-      debug_output(|| {
-        eprintln!("Error loading items: {}", e);
-      });
-      return format!("Error loading items: {}", e);
-    }
-  };
-
-  for item in items_with_images.iter() {
-    if let Some(ref path) = item.image_path_full_res {
-      if let Err(e) = delete_file_and_maybe_parent(Path::new(path)) {
-        eprintln!("Error deleting image file {}: {}", path, e);
-      }
-    }
-  }
-
-  let _ = diesel::delete(items.filter(item_id_field.eq_any(item_ids)))
-    .execute(connection)
-    .map_err(|e| {
-      debug_output(|| {
-        eprintln!("Error deleting items: {}", e);
-      });
-      e.to_string()
-    });
-
-  let _ = diesel::delete(
-    collection_clips
-      .filter(collection_clips_dsl::collection_id.eq(&collection_id))
-      .filter(collection_clips_dsl::item_id.eq_any(item_ids)),
-  )
-  .execute(connection);
-
-  let _ = diesel::delete(
-    collection_menu
-      .filter(collection_menu_dsl::collection_id.eq(&collection_id))
-      .filter(collection_menu_dsl::item_id.eq_any(item_ids)),
-  )
-  .execute(connection);
-
-  "ok".to_string()
-}
-
 pub fn delete_menu_item_by_id(item_id: String, collection_id: String) -> Result<String, Error> {
   let connection = &mut establish_pool_db_connection();
 
@@ -590,14 +589,14 @@ pub fn delete_menu_items_by_ids(
   Ok("ok".to_string())
 }
 
-pub fn create_item(new_item: &Item) -> String {
+pub fn create_item(new_item: &Item) -> Result<String, Error> {
   let connection = &mut establish_pool_db_connection();
 
-  let _ = diesel::insert_into(items)
+  diesel::insert_into(items)
     .values(new_item)
-    .execute(connection);
+    .execute(connection)?;
 
-  new_item.item_id.clone()
+  Ok(new_item.item_id.clone())
 }
 
 pub fn update_pinned_items_by_ids(item_ids: &[String], pinned: bool) -> String {
@@ -774,23 +773,29 @@ pub fn unpin_all_items_clips() -> String {
   "ok".to_string()
 }
 
+/// Copies the image of a history entry to the clip images folder in the format it was saved in.
+/// Entries saved as thumbnail only copy the PNG thumbnail. Returns the relative path and image type.
 pub fn save_item_image_from_history_item(
   item_id: &str,
-  history_image_path: &str,
-) -> Result<String, String> {
+  history_item: &ClipboardHistory,
+) -> Result<(String, String), String> {
   let folder_name = &item_id[..3];
 
   let folder_path = db::get_clip_images_dir().join(folder_name);
   ensure_dir_exists(&folder_path);
 
-  let clip_image_file_name = folder_path.join(format!("{}.png", &item_id));
+  let (image_data, image_format) =
+    image_storage::read_history_image(history_item).ok_or_else(|| {
+      debug_output(|| {
+        println!(
+          "Error reading image of history item {}",
+          history_item.history_id
+        );
+      });
+      "History image not found".to_string()
+    })?;
 
-  let image_data = fs::read(history_image_path).map_err(|e| {
-    debug_output(|| {
-      println!("Error reading image file: {}", e);
-    });
-    e.to_string()
-  })?;
+  let clip_image_file_name = folder_path.join(format!("{}.{}", &item_id, image_format.extension()));
 
   fs::write(&clip_image_file_name, &image_data).map_err(|e| {
     debug_output(|| {
@@ -807,7 +812,7 @@ pub fn save_item_image_from_history_item(
     .to_str()
     .map(|path| db::to_relative_image_path(path))
     .unwrap_or_default();
-  Ok(relative_path)
+  Ok((relative_path, image_format.extension().to_string()))
 }
 
 pub fn upload_image_file_to_item_id(
@@ -1,11 +1,28 @@
+pub mod capture_rules_service;
 pub mod collections_service;
+pub mod history_dedupe;
+pub mod history_pause_service;
+pub mod history_query_service;
+pub mod history_quota_service;
+pub mod history_search_service;
 pub mod history_service;
+pub mod history_timeline_service;
+pub mod image_gc_service;
+pub mod image_storage;
+pub mod integrity_service;
+pub mod item_revisions_service;
 pub mod items_service;
 pub mod link_metadata_service;
+pub mod regex_search_service;
 pub mod request_service;
+pub mod secret_detectors;
 pub mod settings_service;
 pub mod shell_service;
 pub mod tabs_service;
+pub mod tags_service;
 pub mod translations;
+pub mod trash_service;
+pub mod url_cleaner;
+pub mod usage_service;
 pub mod user_settings_service;
 pub mod utils;
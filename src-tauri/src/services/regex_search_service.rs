@@ -0,0 +1,132 @@
+use diesel::result::Error;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Longest pattern accepted for a search
+const MAX_PATTERN_CHARS: usize = 1000;
+
+/// Memory limits for the compiled pattern, patterns such as `\w{1000}{1000}` fail to compile
+/// instead of using gigabytes. Matching itself is linear in the searched text.
+const REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+const REGEX_DFA_SIZE_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Only the start of each value is searched, in characters
+pub const MAX_SEARCHED_VALUE_CHARS: i32 = 256 * 1024;
+
+/// Matches highlighted in one entry, the entry is still found when it has more
+const MAX_MATCH_RANGES: usize = 100;
+
+const SEARCH_TIME_LIMIT: Duration = Duration::from_secs(2);
+
+/// Range of a match in UTF-16 code units, the way the frontend indexes strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SearchMatchRange {
+  pub start: usize,
+  pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegexSearchResult<T> {
+  pub results: Vec<T>,
+  pub scanned_count: usize,
+  /// The search stopped at the time limit, older entries were not searched
+  pub is_timed_out: bool,
+}
+
+pub struct RegexScan {
+  /// Matching ids with their match ranges, in the order they were scanned
+  pub matches: Vec<(String, Vec<SearchMatchRange>)>,
+  pub scanned_count: usize,
+  pub is_timed_out: bool,
+}
+
+/// Compiles a search pattern with multi-line anchors and the size limits above
+pub fn compile_search_regex(pattern: &str, is_case_insensitive: bool) -> Result<Regex, String> {
+  if pattern.trim().is_empty() {
+    return Err("Search pattern is empty".to_string());
+  }
+
+  if pattern.chars().count() > MAX_PATTERN_CHARS {
+    return Err(format!(
+      "Search pattern is longer than {} characters",
+      MAX_PATTERN_CHARS
+    ));
+  }
+
+  RegexBuilder::new(pattern)
+    .case_insensitive(is_case_insensitive)
+    .multi_line(true)
+    .size_limit(REGEX_SIZE_LIMIT)
+    .dfa_size_limit(REGEX_DFA_SIZE_LIMIT)
+    .build()
+    .map_err(|e| e.to_string())
+}
+
+/// Non-empty matches of the regex in the text as UTF-16 ranges
+pub fn find_match_ranges(regex: &Regex, text: &str) -> Vec<SearchMatchRange> {
+  let mut match_ranges = Vec::new();
+  let mut byte_offset = 0;
+  let mut utf16_offset = 0;
+
+  for found in regex
+    .find_iter(text)
+    .filter(|found| !found.is_empty())
+    .take(MAX_MATCH_RANGES)
+  {
+    utf16_offset += text[byte_offset..found.start()].encode_utf16().count();
+    let start = utf16_offset;
+    utf16_offset += found.as_str().encode_utf16().count();
+    byte_offset = found.end();
+
+    match_ranges.push(SearchMatchRange {
+      start,
+      end: utf16_offset,
+    });
+  }
+
+  match_ranges
+}
+
+/// Runs the regex over streamed `(id, value)` rows until `max_results` rows matched or the
+/// time limit is reached, so a search never holds the whole history in memory
+pub fn scan_rows_for_matches<I>(
+  rows: I,
+  regex: &Regex,
+  max_results: usize,
+) -> Result<RegexScan, Error>
+where
+  I: Iterator<Item = Result<(String, Option<String>), Error>>,
+{
+  let started_at = Instant::now();
+  let mut scan = RegexScan {
+    matches: Vec::new(),
+    scanned_count: 0,
+    is_timed_out: false,
+  };
+
+  for row in rows {
+    if scan.matches.len() >= max_results {
+      break;
+    }
+
+    if started_at.elapsed() > SEARCH_TIME_LIMIT {
+      scan.is_timed_out = true;
+      break;
+    }
+
+    let (row_id, row_value) = row?;
+    scan.scanned_count += 1;
+
+    if let Some(row_value) = row_value {
+      let match_ranges = find_match_ranges(regex, &row_value);
+
+      if !match_ranges.is_empty() {
+        scan.matches.push((row_id, match_ranges));
+      }
+    }
+  }
+
+  Ok(scan)
+}
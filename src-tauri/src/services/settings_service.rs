@@ -10,6 +10,14 @@ use crate::models::Setting;
 
 use crate::schema::settings::dsl::*;
 
+#[derive(Clone, serde::Serialize)]
+pub struct SettingUpdatePayload {
+  pub name: String,
+  pub value_bool: Option<bool>,
+  pub value_string: Option<String>,
+  pub value_number: Option<i32>,
+}
+
 pub fn get_all_settings(
   app_handle: Option<tauri::AppHandle>,
 ) -> Result<Mutex<HashMap<String, Setting>>, Error> {
@@ -0,0 +1,240 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::db::establish_pool_db_connection;
+use crate::models::models::{HistoryTag, ItemTag, Tag};
+use crate::schema::{clipboard_history, history_tags, item_tags, items, tags};
+
+/// Colors of the board and clip color picker
+pub const TAG_COLORS: [&str; 16] = [
+  "slate", "sky", "stone", "teal", "indigo", "green", "yellow", "blue", "purple", "rose", "lime",
+  "emerald", "amber", "cyan", "fuchsia", "pink",
+];
+
+const MAX_TAG_NAME_CHARS: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTag {
+  pub name: String,
+  pub color: Option<String>,
+}
+
+pub fn validate_tag(tag: &Tag) -> Result<(), String> {
+  let tag_name = tag.name.trim();
+
+  if tag_name.is_empty() {
+    return Err("Tag name is required".to_string());
+  }
+
+  if tag_name.chars().count() > MAX_TAG_NAME_CHARS {
+    return Err(format!(
+      "Tag name is longer than {} characters",
+      MAX_TAG_NAME_CHARS
+    ));
+  }
+
+  if let Some(tag_color) = &tag.color {
+    if !TAG_COLORS.contains(&tag_color.as_str()) {
+      return Err(format!("Unknown tag color: {}", tag_color));
+    }
+  }
+
+  // Names are unique ignoring ASCII case, the same as the NOCASE index on the name
+  let is_name_taken = get_tags()
+    .map_err(|e| e.to_string())?
+    .iter()
+    .any(|existing_tag| {
+      existing_tag.tag_id != tag.tag_id && existing_tag.name.eq_ignore_ascii_case(tag_name)
+    });
+
+  if is_name_taken {
+    return Err(format!("Tag {} already exists", tag_name));
+  }
+
+  Ok(())
+}
+
+pub fn get_tags() -> Result<Vec<Tag>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  tags::table.order(tags::name.asc()).load::<Tag>(connection)
+}
+
+pub fn create_tag(new_tag: &Tag) -> Result<String, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::insert_into(tags::table)
+    .values(new_tag)
+    .execute(connection)?;
+
+  Ok(new_tag.tag_id.clone())
+}
+
+pub fn update_tag(updated_tag: &Tag) -> Result<String, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let mut updated_tag = updated_tag.clone();
+  updated_tag.name = updated_tag.name.trim().to_string();
+  updated_tag.updated_at = Utc::now().timestamp_millis();
+
+  diesel::update(tags::table.find(&updated_tag.tag_id))
+    .set(&updated_tag)
+    .execute(connection)?;
+
+  Ok("ok".to_string())
+}
+
+/// Deletes the tag and removes it from all history entries and items
+pub fn delete_tag_by_id(tag_id_value: &String) -> Result<String, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  connection.transaction::<_, Error, _>(|connection| {
+    diesel::delete(history_tags::table.filter(history_tags::tag_id.eq(tag_id_value)))
+      .execute(connection)?;
+    diesel::delete(item_tags::table.filter(item_tags::tag_id.eq(tag_id_value)))
+      .execute(connection)?;
+    diesel::delete(tags::table.find(tag_id_value)).execute(connection)?;
+
+    Ok(())
+  })?;
+
+  Ok("ok".to_string())
+}
+
+/// Adds every tag to every history entry, tags an entry already has and ids that do not exist
+/// are skipped
+pub fn tag_history_by_ids(history_ids: &[String], tag_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+  let now = Utc::now().timestamp_millis();
+
+  let existing_history_ids = clipboard_history::table
+    .filter(clipboard_history::history_id.eq_any(history_ids))
+    .select(clipboard_history::history_id)
+    .load::<String>(connection)?;
+  let tag_ids = find_existing_tag_ids(connection, tag_ids)?;
+
+  let new_history_tags: Vec<HistoryTag> = existing_history_ids
+    .iter()
+    .flat_map(|history_id| {
+      tag_ids.iter().map(move |tag_id| HistoryTag {
+        history_id: history_id.clone(),
+        tag_id: tag_id.clone(),
+        created_at: now,
+      })
+    })
+    .collect();
+
+  diesel::insert_or_ignore_into(history_tags::table)
+    .values(&new_history_tags)
+    .execute(connection)
+}
+
+fn find_existing_tag_ids(
+  connection: &mut SqliteConnection,
+  tag_ids: &[String],
+) -> Result<Vec<String>, Error> {
+  tags::table
+    .filter(tags::tag_id.eq_any(tag_ids))
+    .select(tags::tag_id)
+    .load::<String>(connection)
+}
+
+pub fn untag_history_by_ids(history_ids: &[String], tag_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::delete(
+    history_tags::table
+      .filter(history_tags::history_id.eq_any(history_ids))
+      .filter(history_tags::tag_id.eq_any(tag_ids)),
+  )
+  .execute(connection)
+}
+
+/// Adds every tag to every item, tags an item already has and ids that do not exist are skipped
+pub fn tag_items_by_ids(item_ids: &[String], tag_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+  let now = Utc::now().timestamp_millis();
+
+  let existing_item_ids = items::table
+    .filter(items::item_id.eq_any(item_ids))
+    .select(items::item_id)
+    .load::<String>(connection)?;
+  let tag_ids = find_existing_tag_ids(connection, tag_ids)?;
+
+  let new_item_tags: Vec<ItemTag> = existing_item_ids
+    .iter()
+    .flat_map(|item_id| {
+      tag_ids.iter().map(move |tag_id| ItemTag {
+        item_id: item_id.clone(),
+        tag_id: tag_id.clone(),
+        created_at: now,
+      })
+    })
+    .collect();
+
+  diesel::insert_or_ignore_into(item_tags::table)
+    .values(&new_item_tags)
+    .execute(connection)
+}
+
+pub fn untag_items_by_ids(item_ids: &[String], tag_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::delete(
+    item_tags::table
+      .filter(item_tags::item_id.eq_any(item_ids))
+      .filter(item_tags::tag_id.eq_any(tag_ids)),
+  )
+  .execute(connection)
+}
+
+pub fn get_tags_by_history_ids(history_ids: &[String]) -> Result<Vec<HistoryTag>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  history_tags::table
+    .filter(history_tags::history_id.eq_any(history_ids))
+    .load::<HistoryTag>(connection)
+}
+
+pub fn get_tags_by_item_ids(item_ids: &[String]) -> Result<Vec<ItemTag>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  item_tags::table
+    .filter(item_tags::item_id.eq_any(item_ids))
+    .load::<ItemTag>(connection)
+}
+
+pub fn delete_history_tags_by_history_ids(history_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::delete(history_tags::table.filter(history_tags::history_id.eq_any(history_ids)))
+    .execute(connection)
+}
+
+/// Removes tags of history entries and items that no longer exist, and links to deleted tags
+pub fn delete_orphan_tag_links() -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let deleted_history_tags = diesel::delete(
+    history_tags::table.filter(
+      history_tags::history_id
+        .ne_all(clipboard_history::table.select(clipboard_history::history_id))
+        .or(history_tags::tag_id.ne_all(tags::table.select(tags::tag_id))),
+    ),
+  )
+  .execute(connection)?;
+
+  let deleted_item_tags = diesel::delete(
+    item_tags::table.filter(
+      item_tags::item_id
+        .ne_all(items::table.select(items::item_id))
+        .or(item_tags::tag_id.ne_all(tags::table.select(tags::tag_id))),
+    ),
+  )
+  .execute(connection)?;
+
+  Ok(deleted_history_tags + deleted_item_tags)
+}
@@ -0,0 +1,838 @@
+use std::path::Path;
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{Duration, Utc};
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{Nullable, Text};
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{self, establish_pool_db_connection};
+use crate::models::models::{
+  ClipboardHistory, ClipboardHistoryFile, CollectionClips, CollectionMenu, HistoryTag,
+  LinkMetadata, Tabs, TrashEntry, UsageStats,
+};
+use crate::models::Collection;
+use crate::schema::{
+  clipboard_history, clipboard_history_files, collection_clips, collection_menu, collections,
+  history_tags, items, link_metadata, tabs, trash, usage_stats,
+};
+use crate::services::history_service::{self, create_history_image_preview};
+use crate::services::image_storage;
+use crate::services::utils::delete_file_and_maybe_parent;
+
+pub const TRASH_HISTORY: &str = "history";
+pub const TRASH_ITEM: &str = "item";
+pub const TRASH_COLLECTION: &str = "collection";
+
+/// Days a deleted entry stays in the trash when `trashRetentionDays` is not set
+pub const DEFAULT_TRASH_RETENTION_DAYS: i32 = 30;
+
+/// Longest title shown for a history entry in the trash, in characters
+const MAX_TRASH_TITLE_CHARS: usize = 200;
+
+/// History entry with everything that belongs to it, image files stay on disk until the trash is
+/// purged. The preview of `image_data_low_res` is left out of `history` and made again from the
+/// image file on restore, only entries saved as thumbnail keep it in `image_preview` as base64.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashedHistory {
+  history: ClipboardHistory,
+  #[serde(default)]
+  image_preview: Option<String>,
+  link_metadata: Vec<LinkMetadata>,
+  files: Vec<ClipboardHistoryFile>,
+  tags: Vec<HistoryTag>,
+  usage: Vec<UsageStats>,
+}
+
+/// Placement of an item on the boards and in the menu of a collection, the item row itself stays
+/// in `items` with `is_deleted` set
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashedItem {
+  collection_clips: Vec<CollectionClips>,
+  collection_menu: Vec<CollectionMenu>,
+}
+
+/// Collection with its tabs and placements. `item_ids` are the items deleted with it, which stay
+/// in `items` with `is_deleted` set.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashedCollection {
+  collection: Collection,
+  tabs: Vec<Tabs>,
+  collection_clips: Vec<CollectionClips>,
+  collection_menu: Vec<CollectionMenu>,
+  item_ids: Vec<String>,
+}
+
+/// Trash entry without its saved data, for browsing the trash
+#[derive(Queryable, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashListEntry {
+  pub trash_id: String,
+  pub record_type: String,
+  pub record_id: String,
+  pub title: Option<String>,
+  pub deleted_at: i64,
+}
+
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashRestoreReport {
+  pub restored_count: usize,
+  /// Items that stay in the trash because the board, tab or collection they were on is gone
+  pub not_restored_trash_ids: Vec<String>,
+}
+
+fn to_json_error(e: serde_json::Error) -> Error {
+  Error::SerializationError(Box::new(e))
+}
+
+fn history_trash_title(history: &ClipboardHistory) -> Option<String> {
+  if history.is_masked == Some(true) || history.has_masked_words == Some(true) {
+    return history.title.clone();
+  }
+
+  history
+    .title
+    .clone()
+    .or_else(|| history.value_preview.clone())
+    .or_else(|| history.value.clone())
+    .map(|title| title.chars().take(MAX_TRASH_TITLE_CHARS).collect())
+}
+
+/// Entries that expire or hold a detected secret or masked value are deleted for good instead of
+/// being kept in the trash, where nothing would expire them
+fn is_history_kept_out_of_trash(history: &ClipboardHistory) -> bool {
+  history.expires_at.is_some()
+    || history.is_masked == Some(true)
+    || history
+      .detected_secrets
+      .as_deref()
+      .is_some_and(|secrets| !secrets.is_empty())
+}
+
+fn new_trash_entry<T: Serialize>(
+  record_type: &str,
+  record_id: &str,
+  title: Option<String>,
+  data: &T,
+  deleted_at: i64,
+) -> Result<TrashEntry, Error> {
+  Ok(TrashEntry {
+    trash_id: nanoid!().to_string(),
+    record_type: record_type.to_string(),
+    record_id: record_id.to_string(),
+    title,
+    data: serde_json::to_string(data).map_err(to_json_error)?,
+    deleted_at,
+  })
+}
+
+/// Moves history entries to the trash with their link metadata, files, tags and usage. Expiring
+/// and sensitive entries are deleted instead.
+pub fn trash_clipboard_history_by_ids(history_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+  let now = Utc::now().timestamp_millis();
+
+  let (trashed_count, deleted_ids) = connection.transaction::<_, Error, _>(|connection| {
+    let (deleted_histories, histories): (Vec<ClipboardHistory>, Vec<ClipboardHistory>) =
+      clipboard_history::table
+        .filter(clipboard_history::history_id.eq_any(history_ids))
+        .load::<ClipboardHistory>(connection)?
+        .into_iter()
+        .partition(is_history_kept_out_of_trash);
+
+    let deleted_history_ids: Vec<String> = deleted_histories
+      .into_iter()
+      .map(|history| history.history_id)
+      .collect();
+
+    let mut trash_entries = Vec::with_capacity(histories.len());
+
+    for mut history in histories {
+      let history_id = history.history_id.clone();
+      let image_preview = history.image_data_low_res.take().and_then(|image_data| {
+        history
+          .image_path_full_res
+          .is_none()
+          .then(|| general_purpose::STANDARD.encode(image_data))
+      });
+      let trashed_history = TrashedHistory {
+        image_preview,
+        link_metadata: link_metadata::table
+          .filter(link_metadata::history_id.eq(&history_id))
+          .load::<LinkMetadata>(connection)?,
+        files: clipboard_history_files::table
+          .filter(clipboard_history_files::history_id.eq(&history_id))
+          .load::<ClipboardHistoryFile>(connection)?,
+        tags: history_tags::table
+          .filter(history_tags::history_id.eq(&history_id))
+          .load::<HistoryTag>(connection)?,
+        usage: usage_stats::table
+          .filter(usage_stats::history_id.eq(&history_id))
+          .load::<UsageStats>(connection)?,
+        history,
+      };
+
+      trash_entries.push(new_trash_entry(
+        TRASH_HISTORY,
+        &history_id,
+        history_trash_title(&trashed_history.history),
+        &trashed_history,
+        now,
+      )?);
+    }
+
+    let trashed_history_ids: Vec<&String> =
+      trash_entries.iter().map(|entry| &entry.record_id).collect();
+
+    diesel::delete(
+      link_metadata::table.filter(link_metadata::history_id.eq_any(&trashed_history_ids)),
+    )
+    .execute(connection)?;
+    diesel::delete(
+      clipboard_history_files::table
+        .filter(clipboard_history_files::history_id.eq_any(&trashed_history_ids)),
+    )
+    .execute(connection)?;
+    diesel::delete(
+      history_tags::table.filter(history_tags::history_id.eq_any(&trashed_history_ids)),
+    )
+    .execute(connection)?;
+    diesel::delete(usage_stats::table.filter(usage_stats::history_id.eq_any(&trashed_history_ids)))
+      .execute(connection)?;
+    diesel::delete(
+      clipboard_history::table.filter(clipboard_history::history_id.eq_any(&trashed_history_ids)),
+    )
+    .execute(connection)?;
+
+    let trashed_count = diesel::insert_into(trash::table)
+      .values(&trash_entries)
+      .execute(connection)?;
+
+    Ok((trashed_count, deleted_history_ids))
+  })?;
+
+  if !deleted_ids.is_empty() {
+    history_service::delete_clipboard_history_by_ids(&deleted_ids);
+  }
+
+  Ok(trashed_count + deleted_ids.len())
+}
+
+/// Moves items to the trash and takes them off the boards and menus of every collection they are
+/// placed in
+pub fn trash_items_by_ids(item_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  trash_items(connection, item_ids, Utc::now().timestamp_millis())
+}
+
+fn trash_items(
+  connection: &mut SqliteConnection,
+  item_ids: &[String],
+  deleted_at: i64,
+) -> Result<usize, Error> {
+  connection.transaction::<_, Error, _>(|connection| {
+    let trashed_items = items::table
+      .filter(items::item_id.eq_any(item_ids))
+      .filter(items::is_deleted.eq(false))
+      .select((items::item_id, items::name))
+      .load::<(String, String)>(connection)?;
+
+    let mut trash_entries = Vec::with_capacity(trashed_items.len());
+
+    for (item_id, item_name) in trashed_items.iter() {
+      let trashed_item = TrashedItem {
+        collection_clips: collection_clips::table
+          .filter(collection_clips::item_id.eq(item_id))
+          .load::<CollectionClips>(connection)?,
+        collection_menu: collection_menu::table
+          .filter(collection_menu::item_id.eq(item_id))
+          .load::<CollectionMenu>(connection)?,
+      };
+
+      trash_entries.push(new_trash_entry(
+        TRASH_ITEM,
+        item_id,
+        Some(item_name.clone()),
+        &trashed_item,
+        deleted_at,
+      )?);
+    }
+
+    let trashed_item_ids: Vec<&String> = trashed_items.iter().map(|(item_id, _)| item_id).collect();
+
+    diesel::delete(
+      collection_clips::table.filter(collection_clips::item_id.eq_any(&trashed_item_ids)),
+    )
+    .execute(connection)?;
+    diesel::delete(
+      collection_menu::table.filter(collection_menu::item_id.eq_any(&trashed_item_ids)),
+    )
+    .execute(connection)?;
+    diesel::update(items::table.filter(items::item_id.eq_any(&trashed_item_ids)))
+      .set(items::is_deleted.eq(true))
+      .execute(connection)?;
+
+    diesel::insert_into(trash::table)
+      .values(&trash_entries)
+      .execute(connection)
+  })
+}
+
+/// Moves a collection with its tabs to the trash. When `delete_all_items` is set, its clips and
+/// menu items go to the trash with it, except items also placed in another collection.
+pub fn trash_collection_by_id(collection_id: &str, delete_all_items: bool) -> Result<(), Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  trash_collection(
+    connection,
+    collection_id,
+    delete_all_items,
+    Utc::now().timestamp_millis(),
+  )
+}
+
+fn trash_collection(
+  connection: &mut SqliteConnection,
+  collection_id: &str,
+  delete_all_items: bool,
+  deleted_at: i64,
+) -> Result<(), Error> {
+  connection.transaction::<_, Error, _>(|connection| {
+    let collection = match collections::table
+      .find(collection_id)
+      .first::<Collection>(connection)
+      .optional()?
+    {
+      Some(collection) => collection,
+      None => return Ok(()),
+    };
+
+    let collection_clips_rows = collection_clips::table
+      .filter(collection_clips::collection_id.eq(collection_id))
+      .load::<CollectionClips>(connection)?;
+    let collection_menu_rows = collection_menu::table
+      .filter(collection_menu::collection_id.eq(collection_id))
+      .load::<CollectionMenu>(connection)?;
+
+    let item_ids = if delete_all_items {
+      let mut placed_item_ids: Vec<String> = collection_clips_rows
+        .iter()
+        .map(|clip| clip.item_id.clone())
+        .chain(collection_menu_rows.iter().map(|menu| menu.item_id.clone()))
+        .collect();
+      placed_item_ids.sort();
+      placed_item_ids.dedup();
+
+      let shared_item_ids: Vec<String> = collection_clips::table
+        .filter(collection_clips::item_id.eq_any(&placed_item_ids))
+        .filter(collection_clips::collection_id.ne(collection_id))
+        .select(collection_clips::item_id)
+        .union(
+          collection_menu::table
+            .filter(collection_menu::item_id.eq_any(&placed_item_ids))
+            .filter(collection_menu::collection_id.ne(collection_id))
+            .select(collection_menu::item_id),
+        )
+        .load::<String>(connection)?;
+
+      items::table
+        .filter(items::item_id.eq_any(&placed_item_ids))
+        .filter(items::item_id.ne_all(&shared_item_ids))
+        .filter(items::is_deleted.eq(false))
+        .select(items::item_id)
+        .load::<String>(connection)?
+    } else {
+      Vec::new()
+    };
+
+    let trashed_collection = TrashedCollection {
+      tabs: tabs::table
+        .filter(tabs::collection_id.eq(collection_id))
+        .load::<Tabs>(connection)?,
+      collection_clips: collection_clips_rows,
+      collection_menu: collection_menu_rows,
+      item_ids,
+      collection,
+    };
+
+    let trash_entry = new_trash_entry(
+      TRASH_COLLECTION,
+      collection_id,
+      Some(trashed_collection.collection.title.clone()),
+      &trashed_collection,
+      deleted_at,
+    )?;
+
+    diesel::update(items::table.filter(items::item_id.eq_any(&trashed_collection.item_ids)))
+      .set(items::is_deleted.eq(true))
+      .execute(connection)?;
+    diesel::delete(
+      collection_clips::table.filter(collection_clips::collection_id.eq(collection_id)),
+    )
+    .execute(connection)?;
+    diesel::delete(collection_menu::table.filter(collection_menu::collection_id.eq(collection_id)))
+      .execute(connection)?;
+    diesel::delete(tabs::table.filter(tabs::collection_id.eq(collection_id)))
+      .execute(connection)?;
+    diesel::delete(collections::table.find(collection_id)).execute(connection)?;
+
+    diesel::insert_into(trash::table)
+      .values(&trash_entry)
+      .execute(connection)?;
+
+    Ok(())
+  })
+}
+
+/// Trash entries of one record type, or all of them, most recently deleted first
+pub fn get_trash_entries(record_type: Option<&str>) -> Result<Vec<TrashListEntry>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let mut query = trash::table
+    .select((
+      trash::trash_id,
+      trash::record_type,
+      trash::record_id,
+      trash::title,
+      trash::deleted_at,
+    ))
+    .order(trash::deleted_at.desc())
+    .into_boxed();
+
+  if let Some(record_type) = record_type {
+    query = query.filter(trash::record_type.eq(record_type));
+  }
+
+  query.load::<TrashListEntry>(connection)
+}
+
+/// Image files of history entries in the trash, they are kept until the trash is purged
+pub fn get_trashed_history_image_paths() -> Result<Vec<String>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let image_paths = trash::table
+    .filter(trash::record_type.eq(TRASH_HISTORY))
+    .select(sql::<Nullable<Text>>(
+      "json_extract(trash.data, '$.history.imagePathFullRes')",
+    ))
+    .load::<Option<String>>(connection)?;
+
+  Ok(image_paths.into_iter().flatten().collect())
+}
+
+/// Puts trash entries back where they were deleted from
+pub fn restore_trash_entries_by_ids(trash_ids: &[String]) -> Result<TrashRestoreReport, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  restore_trash_entries(connection, trash_ids)
+}
+
+fn restore_trash_entries(
+  connection: &mut SqliteConnection,
+  trash_ids: &[String],
+) -> Result<TrashRestoreReport, Error> {
+  connection.transaction::<_, Error, _>(|connection| {
+    let trash_entries = trash::table
+      .filter(trash::trash_id.eq_any(trash_ids))
+      .order(trash::deleted_at.desc())
+      .load::<TrashEntry>(connection)?;
+
+    let mut report = TrashRestoreReport::default();
+
+    // Collections go first, so items deleted from them later can be put back on their boards
+    let (collection_entries, other_entries): (Vec<TrashEntry>, Vec<TrashEntry>) = trash_entries
+      .into_iter()
+      .partition(|entry| entry.record_type == TRASH_COLLECTION);
+
+    for trash_entry in collection_entries.iter().chain(other_entries.iter()) {
+      let is_restored = match trash_entry.record_type.as_str() {
+        TRASH_HISTORY => restore_history(connection, trash_entry)?,
+        TRASH_ITEM => restore_item(connection, trash_entry)?,
+        TRASH_COLLECTION => restore_collection(connection, trash_entry)?,
+        _ => false,
+      };
+
+      if is_restored {
+        diesel::delete(trash::table.find(&trash_entry.trash_id)).execute(connection)?;
+        report.restored_count += 1;
+      } else {
+        report
+          .not_restored_trash_ids
+          .push(trash_entry.trash_id.clone());
+      }
+    }
+
+    Ok(report)
+  })
+}
+
+fn restore_history(
+  connection: &mut SqliteConnection,
+  trash_entry: &TrashEntry,
+) -> Result<bool, Error> {
+  let trashed_history: TrashedHistory =
+    serde_json::from_str(&trash_entry.data).map_err(to_json_error)?;
+  let mut history = trashed_history.history;
+
+  // Entries trashed before the preview was left out still have it in `history`
+  if history.image_data_low_res.is_none() {
+    history.image_data_low_res = match trashed_history.image_preview {
+      Some(image_preview) => general_purpose::STANDARD.decode(image_preview).ok(),
+      None if history.image_path_full_res.is_some() => image_storage::read_history_image(&history)
+        .and_then(|(image_bytes, format)| image_storage::decode_image(&image_bytes, format).ok())
+        .map(|image| create_history_image_preview(&image)),
+      None => None,
+    };
+  }
+
+  diesel::insert_into(clipboard_history::table)
+    .values(&history)
+    .execute(connection)?;
+  diesel::insert_into(link_metadata::table)
+    .values(&trashed_history.link_metadata)
+    .execute(connection)?;
+  diesel::insert_into(clipboard_history_files::table)
+    .values(&trashed_history.files)
+    .execute(connection)?;
+  diesel::insert_or_ignore_into(history_tags::table)
+    .values(&trashed_history.tags)
+    .execute(connection)?;
+  diesel::insert_into(usage_stats::table)
+    .values(&trashed_history.usage)
+    .execute(connection)?;
+
+  Ok(true)
+}
+
+fn restore_item(
+  connection: &mut SqliteConnection,
+  trash_entry: &TrashEntry,
+) -> Result<bool, Error> {
+  let trashed_item: TrashedItem = serde_json::from_str(&trash_entry.data).map_err(to_json_error)?;
+
+  let existing_tab_ids = tabs::table
+    .filter(
+      tabs::tab_id.eq_any(
+        trashed_item
+          .collection_clips
+          .iter()
+          .map(|clip| &clip.tab_id)
+          .collect::<Vec<_>>(),
+      ),
+    )
+    .select(tabs::tab_id)
+    .load::<String>(connection)?;
+  let existing_collection_ids = collections::table
+    .filter(
+      collections::collection_id.eq_any(
+        trashed_item
+          .collection_menu
+          .iter()
+          .map(|menu| &menu.collection_id)
+          .collect::<Vec<_>>(),
+      ),
+    )
+    .select(collections::collection_id)
+    .load::<String>(connection)?;
+
+  let restored_clips: Vec<&CollectionClips> = trashed_item
+    .collection_clips
+    .iter()
+    .filter(|clip| existing_tab_ids.contains(&clip.tab_id))
+    .collect();
+  let restored_menu: Vec<&CollectionMenu> = trashed_item
+    .collection_menu
+    .iter()
+    .filter(|menu| existing_collection_ids.contains(&menu.collection_id))
+    .collect();
+
+  let was_placed =
+    !trashed_item.collection_clips.is_empty() || !trashed_item.collection_menu.is_empty();
+
+  if was_placed && restored_clips.is_empty() && restored_menu.is_empty() {
+    return Ok(false);
+  }
+
+  diesel::insert_or_ignore_into(collection_clips::table)
+    .values(restored_clips)
+    .execute(connection)?;
+  diesel::insert_or_ignore_into(collection_menu::table)
+    .values(restored_menu)
+    .execute(connection)?;
+  diesel::update(items::table.find(&trash_entry.record_id))
+    .set(items::is_deleted.eq(false))
+    .execute(connection)?;
+
+  Ok(true)
+}
+
+fn restore_collection(
+  connection: &mut SqliteConnection,
+  trash_entry: &TrashEntry,
+) -> Result<bool, Error> {
+  let trashed_collection: TrashedCollection =
+    serde_json::from_str(&trash_entry.data).map_err(to_json_error)?;
+
+  // Only one collection is selected at a time
+  let selected_count = collections::table
+    .filter(collections::is_selected.eq(true))
+    .count()
+    .get_result::<i64>(connection)?;
+  let mut collection = trashed_collection.collection;
+  collection.is_selected = collection.is_selected && selected_count == 0;
+
+  diesel::insert_into(collections::table)
+    .values(&collection)
+    .execute(connection)?;
+  diesel::insert_into(tabs::table)
+    .values(&trashed_collection.tabs)
+    .execute(connection)?;
+  diesel::insert_or_ignore_into(collection_clips::table)
+    .values(&trashed_collection.collection_clips)
+    .execute(connection)?;
+  diesel::insert_or_ignore_into(collection_menu::table)
+    .values(&trashed_collection.collection_menu)
+    .execute(connection)?;
+  diesel::update(items::table.filter(items::item_id.eq_any(&trashed_collection.item_ids)))
+    .set(items::is_deleted.eq(false))
+    .execute(connection)?;
+
+  Ok(true)
+}
+
+/// Deletes trash entries for good, with the items and image files they hold
+pub fn delete_trash_entries_by_ids(trash_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let trash_entries = trash::table
+    .filter(trash::trash_id.eq_any(trash_ids))
+    .load::<TrashEntry>(connection)?;
+
+  purge_trash_entries(connection, trash_entries)
+}
+
+pub fn empty_trash() -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let trash_entries = trash::table.load::<TrashEntry>(connection)?;
+
+  purge_trash_entries(connection, trash_entries)
+}
+
+/// Deletes trash entries deleted more than `retention_days` ago
+pub fn delete_expired_trash(retention_days: i32) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+  let deleted_before =
+    (Utc::now() - Duration::days(retention_days.max(0) as i64)).timestamp_millis();
+
+  let trash_entries = trash::table
+    .filter(trash::deleted_at.lt(deleted_before))
+    .load::<TrashEntry>(connection)?;
+
+  purge_trash_entries(connection, trash_entries)
+}
+
+fn purge_trash_entries(
+  connection: &mut SqliteConnection,
+  trash_entries: Vec<TrashEntry>,
+) -> Result<usize, Error> {
+  let mut image_paths: Vec<String> = Vec::new();
+  let mut purged_item_ids: Vec<String> = Vec::new();
+
+  for trash_entry in trash_entries.iter() {
+    match trash_entry.record_type.as_str() {
+      TRASH_HISTORY => {
+        let trashed_history: TrashedHistory =
+          serde_json::from_str(&trash_entry.data).map_err(to_json_error)?;
+        image_paths.extend(trashed_history.history.image_path_full_res);
+      }
+      TRASH_ITEM => purged_item_ids.push(trash_entry.record_id.clone()),
+      TRASH_COLLECTION => {
+        let trashed_collection: TrashedCollection =
+          serde_json::from_str(&trash_entry.data).map_err(to_json_error)?;
+        purged_item_ids.extend(trashed_collection.item_ids);
+      }
+      _ => {}
+    }
+  }
+
+  let purged_trash_ids: Vec<&String> = trash_entries.iter().map(|entry| &entry.trash_id).collect();
+
+  let purged_count = connection.transaction::<_, Error, _>(|connection| {
+    // Restored items are no longer deleted and stay
+    let purged_item_ids = items::table
+      .filter(items::item_id.eq_any(&purged_item_ids))
+      .filter(items::is_deleted.eq(true))
+      .select(items::item_id)
+      .load::<String>(connection)?;
+
+    image_paths.extend(
+      items::table
+        .filter(items::item_id.eq_any(&purged_item_ids))
+        .select(items::image_path_full_res)
+        .load::<Option<String>>(connection)?
+        .into_iter()
+        .flatten(),
+    );
+
+    diesel::delete(items::table.filter(items::item_id.eq_any(&purged_item_ids)))
+      .execute(connection)?;
+    diesel::delete(
+      collection_clips::table.filter(collection_clips::item_id.eq_any(&purged_item_ids)),
+    )
+    .execute(connection)?;
+    diesel::delete(
+      collection_menu::table.filter(collection_menu::item_id.eq_any(&purged_item_ids)),
+    )
+    .execute(connection)?;
+
+    diesel::delete(trash::table.filter(trash::trash_id.eq_any(&purged_trash_ids)))
+      .execute(connection)
+  })?;
+
+  for path in image_paths {
+    let path = db::to_absolute_image_path(&path);
+    if let Err(e) = delete_file_and_maybe_parent(Path::new(&path)) {
+      eprintln!("Error deleting image file {}: {}", path, e);
+    }
+  }
+
+  Ok(purged_count)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use diesel::connection::SimpleConnection;
+
+  use crate::db::establish_test_connection;
+
+  /// Item `shared` is on a board of both collections and in the menu of `c2`, item `single` is
+  /// only on the board of `c1`
+  fn connection_with_shared_item() -> SqliteConnection {
+    let mut connection = establish_test_connection();
+    connection
+      .batch_execute(
+        "INSERT INTO collections (collection_id, title, is_default, is_enabled, is_selected, \
+           created_at, updated_at, created_date, updated_date) VALUES \
+           ('c1', 'One', 0, 1, 0, 0, 0, '2025-01-01 00:00:00', '2025-01-01 00:00:00'), \
+           ('c2', 'Two', 0, 1, 0, 0, 0, '2025-01-01 00:00:00', '2025-01-01 00:00:00'); \
+         INSERT INTO tabs (tab_id, collection_id, tab_name, tab_is_active, tab_is_hidden, \
+           tab_order_number, tab_layout_split, tab_is_protected) VALUES \
+           ('t1', 'c1', 'Tab', 1, 0, 0, 1, 0), ('t2', 'c2', 'Tab', 1, 0, 0, 1, 0); \
+         INSERT INTO items (item_id, name, value, is_deleted, is_folder, is_separator, is_board, \
+           is_clip, is_menu, is_disabled, created_at, updated_at, created_date, updated_date) \
+           VALUES \
+           ('shared', 'Shared', 'a', 0, 0, 0, 0, 1, 0, 0, 0, 0, '2025-01-01 00:00:00', \
+             '2025-01-01 00:00:00'), \
+           ('single', 'Single', 'b', 0, 0, 0, 0, 1, 0, 0, 0, 0, '2025-01-01 00:00:00', \
+             '2025-01-01 00:00:00'); \
+         INSERT INTO collection_clips (collection_id, item_id, tab_id, parent_id, order_number) \
+           VALUES ('c1', 'shared', 't1', NULL, 0), ('c2', 'shared', 't2', NULL, 0), \
+           ('c1', 'single', 't1', NULL, 1); \
+         INSERT INTO collection_menu (collection_id, item_id, parent_id, order_number) \
+           VALUES ('c2', 'shared', NULL, 0);",
+      )
+      .unwrap();
+    connection
+  }
+
+  fn placement_count(connection: &mut SqliteConnection, item_id: &str) -> i64 {
+    let clips_count: i64 = collection_clips::table
+      .filter(collection_clips::item_id.eq(item_id))
+      .count()
+      .get_result(connection)
+      .unwrap();
+    let menu_count: i64 = collection_menu::table
+      .filter(collection_menu::item_id.eq(item_id))
+      .count()
+      .get_result(connection)
+      .unwrap();
+    clips_count + menu_count
+  }
+
+  fn is_item_deleted(connection: &mut SqliteConnection, item_id: &str) -> Option<bool> {
+    items::table
+      .find(item_id)
+      .select(items::is_deleted)
+      .first::<bool>(connection)
+      .optional()
+      .unwrap()
+  }
+
+  #[test]
+  fn trashed_item_leaves_every_collection_and_comes_back_to_all() {
+    let connection = &mut connection_with_shared_item();
+
+    assert_eq!(
+      trash_items(connection, &["shared".to_string()], 0).unwrap(),
+      1
+    );
+    assert_eq!(placement_count(connection, "shared"), 0);
+    assert_eq!(is_item_deleted(connection, "shared"), Some(true));
+
+    let trash_ids: Vec<String> = trash::table
+      .select(trash::trash_id)
+      .load(connection)
+      .unwrap();
+    let report = restore_trash_entries(connection, &trash_ids).unwrap();
+
+    assert_eq!(report.restored_count, 1);
+    assert_eq!(placement_count(connection, "shared"), 3);
+    assert_eq!(is_item_deleted(connection, "shared"), Some(false));
+  }
+
+  #[test]
+  fn purging_a_trashed_item_leaves_no_placements() {
+    let connection = &mut connection_with_shared_item();
+
+    trash_items(connection, &["shared".to_string()], 0).unwrap();
+    let trash_entries = trash::table.load::<TrashEntry>(connection).unwrap();
+    purge_trash_entries(connection, trash_entries).unwrap();
+
+    assert_eq!(is_item_deleted(connection, "shared"), None);
+    assert_eq!(placement_count(connection, "shared"), 0);
+    assert_eq!(is_item_deleted(connection, "single"), Some(false));
+  }
+
+  #[test]
+  fn trashed_collection_keeps_items_placed_in_another_collection() {
+    let connection = &mut connection_with_shared_item();
+
+    trash_collection(connection, "c1", true, 0).unwrap();
+
+    assert_eq!(is_item_deleted(connection, "single"), Some(true));
+    assert_eq!(is_item_deleted(connection, "shared"), Some(false));
+    assert_eq!(placement_count(connection, "shared"), 2);
+  }
+
+  #[test]
+  fn expiring_and_secret_history_stays_out_of_trash() {
+    let plain = ClipboardHistory::default();
+    let expiring = ClipboardHistory {
+      expires_at: Some(0),
+      ..Default::default()
+    };
+    let with_secret = ClipboardHistory {
+      detected_secrets: Some(r#"["aws_access_key"]"#.to_string()),
+      ..Default::default()
+    };
+    let masked = ClipboardHistory {
+      is_masked: Some(true),
+      ..Default::default()
+    };
+
+    assert!(!is_history_kept_out_of_trash(&plain));
+    assert!(is_history_kept_out_of_trash(&expiring));
+    assert!(is_history_kept_out_of_trash(&with_secret));
+    assert!(is_history_kept_out_of_trash(&masked));
+  }
+}
@@ -0,0 +1,316 @@
+use crate::models::Setting;
+use std::collections::HashMap;
+use std::ops::Range;
+use url::{form_urlencoded, Url};
+
+/// Query parameters removed from links on every domain, a trailing `*` matches a prefix
+const TRACKING_PARAMS: [&str; 26] = [
+  "utm_*",
+  "fbclid",
+  "gclid",
+  "gclsrc",
+  "dclid",
+  "gbraid",
+  "wbraid",
+  "msclkid",
+  "yclid",
+  "twclid",
+  "ttclid",
+  "li_fat_id",
+  "igshid",
+  "mc_cid",
+  "mc_eid",
+  "_hsenc",
+  "_hsmi",
+  "__hssc",
+  "__hstc",
+  "__hsfp",
+  "hsCtaTracking",
+  "mkt_tok",
+  "oly_anon_id",
+  "oly_enc_id",
+  "vero_id",
+  "_gl",
+];
+
+/// Query parameters that are only tracking noise on specific domains
+const DOMAIN_TRACKING_PARAMS: [(&str, &[&str]); 6] = [
+  ("youtube.com", &["si", "feature", "pp"]),
+  ("youtu.be", &["si", "feature"]),
+  ("open.spotify.com", &["si", "context"]),
+  ("twitter.com", &["s", "t", "ref_src", "ref_url"]),
+  ("x.com", &["s", "t", "ref_src", "ref_url"]),
+  (
+    "amazon.com",
+    &["ref", "ref_", "pf_rd_*", "pd_rd_*", "content-id"],
+  ),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlCleanerRule {
+  /// Domain the rule applies to, including its subdomains, `None` for every domain
+  pub domain: Option<String>,
+  pub params: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UrlCleanerOptions {
+  pub is_enabled: bool,
+  pub keep_original: bool,
+  pub user_rules: Vec<UrlCleanerRule>,
+}
+
+impl UrlCleanerOptions {
+  /// User rules come from `urlCleanerRules`, one rule per line: `domain.com: param, other_*`,
+  /// or `*: param` to remove a parameter on every domain
+  pub fn from_settings(settings_map: &HashMap<String, Setting>) -> Self {
+    UrlCleanerOptions {
+      is_enabled: settings_map
+        .get("isUrlCleaningEnabled")
+        .and_then(|s| s.value_bool)
+        .unwrap_or(true),
+      keep_original: settings_map
+        .get("isUrlCleaningKeepOriginalEnabled")
+        .and_then(|s| s.value_bool)
+        .unwrap_or(true),
+      user_rules: settings_map
+        .get("urlCleanerRules")
+        .and_then(|s| s.value_text.as_ref())
+        .map_or(Vec::new(), |rules_text| parse_url_cleaner_rules(rules_text)),
+    }
+  }
+
+  /// Returns the link without tracking parameters, or `None` when there was nothing to remove
+  pub fn clean_url(&self, link: &str) -> Option<String> {
+    if !self.is_enabled {
+      return None;
+    }
+
+    let mut url = Url::parse(link).ok()?;
+
+    let host = url
+      .host_str()
+      .map(|host| host.trim_start_matches("www.").to_lowercase())
+      .unwrap_or_default();
+
+    // The kept parameters are copied as they were written, decoding and encoding them again
+    // could change their escapes
+    let query_segments: Vec<&str> = url.query()?.split('&').collect();
+    let kept_segments: Vec<&str> = query_segments
+      .iter()
+      .copied()
+      .filter(|segment| {
+        form_urlencoded::parse(segment.as_bytes())
+          .next()
+          .map_or(true, |(param, _)| !self.is_tracking_param(&host, &param))
+      })
+      .collect();
+
+    if kept_segments.len() == query_segments.len() {
+      return None;
+    }
+
+    if kept_segments.is_empty() {
+      url.set_query(None);
+    } else {
+      url.set_query(Some(&kept_segments.join("&")));
+    }
+
+    let mut cleaned_url = url.to_string();
+
+    // Url adds a slash after a bare domain, keep the link as it was written
+    let link_without_query = link.split(['?', '#']).next().unwrap_or(link);
+    if cleaned_url.ends_with('/') && !link_without_query.ends_with('/') {
+      cleaned_url.pop();
+    }
+
+    Some(cleaned_url)
+  }
+
+  /// Replaces the links at `link_spans`, byte ranges of the text in order, with their cleaned
+  /// versions. Returns the cleaned text and all links, or `None` when no link had tracking
+  /// parameters.
+  pub fn clean_links_in_text(
+    &self,
+    text: &str,
+    link_spans: &[Range<usize>],
+  ) -> Option<(String, Vec<String>)> {
+    let mut cleaned_text = String::with_capacity(text.len());
+    let mut cleaned_links = Vec::with_capacity(link_spans.len());
+    let mut copied_until = 0;
+    let mut is_changed = false;
+
+    for link_span in link_spans {
+      let link = &text[link_span.clone()];
+      let cleaned_link = match self.clean_url(link) {
+        Some(cleaned_link) => {
+          is_changed = true;
+          cleaned_link
+        }
+        None => link.to_string(),
+      };
+
+      cleaned_text.push_str(&text[copied_until..link_span.start]);
+      cleaned_text.push_str(&cleaned_link);
+      copied_until = link_span.end;
+      cleaned_links.push(cleaned_link);
+    }
+
+    cleaned_text.push_str(&text[copied_until..]);
+
+    Some((cleaned_text, cleaned_links)).filter(|_| is_changed)
+  }
+
+  fn is_tracking_param(&self, host: &str, param: &str) -> bool {
+    let is_domain_match = |domain: &str| host == domain || host.ends_with(&format!(".{}", domain));
+
+    TRACKING_PARAMS
+      .iter()
+      .any(|pattern| is_param_match(pattern, param))
+      || DOMAIN_TRACKING_PARAMS.iter().any(|(domain, params)| {
+        is_domain_match(domain) && params.iter().any(|pattern| is_param_match(pattern, param))
+      })
+      || self.user_rules.iter().any(|rule| {
+        rule.domain.as_deref().map_or(true, is_domain_match)
+          && rule
+            .params
+            .iter()
+            .any(|pattern| is_param_match(pattern, param))
+      })
+  }
+}
+
+fn is_param_match(pattern: &str, param: &str) -> bool {
+  match pattern.strip_suffix('*') {
+    Some(prefix) => param.starts_with(prefix),
+    None => pattern == param,
+  }
+}
+
+pub fn parse_url_cleaner_rules(rules_text: &str) -> Vec<UrlCleanerRule> {
+  rules_text
+    .lines()
+    .filter_map(|line| {
+      let (domain, params) = line.split_once(':')?;
+      let domain = domain.trim().trim_start_matches("www.").to_lowercase();
+      let params: Vec<String> = params
+        .split(',')
+        .map(|param| param.trim().to_string())
+        .filter(|param| !param.is_empty())
+        .collect();
+
+      if domain.is_empty() || params.is_empty() {
+        return None;
+      }
+
+      Some(UrlCleanerRule {
+        domain: Some(domain).filter(|domain| domain != "*"),
+        params,
+      })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cleaner() -> UrlCleanerOptions {
+    UrlCleanerOptions {
+      is_enabled: true,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn removes_tracking_params_and_keeps_others() {
+    assert_eq!(
+      cleaner().clean_url(
+        "https://example.com/page?id=42&utm_source=news&utm_medium=email&fbclid=abc&q=a%20b"
+      ),
+      Some("https://example.com/page?id=42&q=a%20b".to_string())
+    );
+  }
+
+  #[test]
+  fn removes_the_query_when_only_tracking_params_were_left() {
+    assert_eq!(
+      cleaner().clean_url("https://example.com?utm_campaign=spring&gclid=1"),
+      Some("https://example.com".to_string())
+    );
+    assert_eq!(
+      cleaner().clean_url("https://example.com/a/?fbclid=abc#top"),
+      Some("https://example.com/a/#top".to_string())
+    );
+  }
+
+  #[test]
+  fn leaves_links_without_tracking_params_alone() {
+    assert_eq!(cleaner().clean_url("https://example.com/?id=1&utm=2"), None);
+    assert_eq!(cleaner().clean_url("https://example.com/page"), None);
+    assert_eq!(cleaner().clean_url("not a link?utm_source=x"), None);
+
+    let disabled = UrlCleanerOptions::default();
+    assert_eq!(
+      disabled.clean_url("https://example.com/?utm_source=x"),
+      None
+    );
+  }
+
+  #[test]
+  fn domain_params_are_only_removed_on_their_domain() {
+    assert_eq!(
+      cleaner().clean_url("https://www.youtube.com/watch?v=abc&si=xyz"),
+      Some("https://www.youtube.com/watch?v=abc".to_string())
+    );
+    assert_eq!(
+      cleaner().clean_url("https://example.com/watch?v=abc&si=xyz"),
+      None
+    );
+  }
+
+  #[test]
+  fn user_rules_remove_params_on_their_domain_or_everywhere() {
+    let options = UrlCleanerOptions {
+      is_enabled: true,
+      user_rules: parse_url_cleaner_rules("shop.com: ref, aff_*\n*: session\ninvalid line"),
+      ..Default::default()
+    };
+
+    assert_eq!(options.user_rules.len(), 2);
+    assert_eq!(
+      options.clean_url("https://m.shop.com/item?id=1&ref=home&aff_id=7"),
+      Some("https://m.shop.com/item?id=1".to_string())
+    );
+    assert_eq!(
+      options.clean_url("https://other.com/?ref=home&session=9"),
+      Some("https://other.com/?ref=home".to_string())
+    );
+  }
+
+  #[test]
+  fn replaces_links_in_text_by_their_spans() {
+    let text = "see https://a.com/?utm_source=x and https://b.com/?id=1";
+    let first = text.find("https://a.com").unwrap();
+    let second = text.find("https://b.com").unwrap();
+    let link_spans = [
+      first..first + "https://a.com/?utm_source=x".len(),
+      second..text.len(),
+    ];
+
+    assert_eq!(
+      cleaner().clean_links_in_text(text, &link_spans),
+      Some((
+        "see https://a.com/ and https://b.com/?id=1".to_string(),
+        vec![
+          "https://a.com/".to_string(),
+          "https://b.com/?id=1".to_string()
+        ]
+      ))
+    );
+    assert_eq!(
+      cleaner().clean_links_in_text("https://b.com/?id=1", &[0..19]),
+      None
+    );
+  }
+}
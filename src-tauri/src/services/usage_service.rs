@@ -0,0 +1,175 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::Error;
+use nanoid::nanoid;
+
+use crate::db::establish_pool_db_connection;
+use crate::models::models::UsageStats;
+use crate::schema::usage_stats::dsl::*;
+use crate::schema::{clipboard_history, items};
+
+pub const SORT_BY_RECENT: &str = "recent";
+pub const SORT_BY_FRECENCY: &str = "frecency";
+pub const SORT_BY_RELEVANCE: &str = "relevance";
+
+/// A use counts half as much after this long
+const FRECENCY_HALF_LIFE_MS: f64 = 7.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistorySortOrder {
+  /// Most recently copied first
+  Recent,
+  /// Most frequently and recently used first, entries never used follow by recency
+  Frecency,
+  /// Best full-text match first, lists without a search query fall back to recent
+  Relevance,
+}
+
+impl HistorySortOrder {
+  pub fn from_name(sort_by: Option<&str>) -> Self {
+    match sort_by {
+      Some(SORT_BY_FRECENCY) => HistorySortOrder::Frecency,
+      Some(SORT_BY_RELEVANCE) => HistorySortOrder::Relevance,
+      _ => HistorySortOrder::Recent,
+    }
+  }
+}
+
+/// The frecency score is the sum of all uses, each halving in weight every half life.
+/// It is stored as `log2(score at t) + t / half life`, which stays the same as time passes,
+/// so rows can be sorted by the stored rank without recomputing decayed scores.
+fn next_frecency_rank(previous_rank: Option<f64>, now: i64) -> f64 {
+  let now_rank = now as f64 / FRECENCY_HALF_LIFE_MS;
+  let decayed_score = previous_rank.map_or(0.0, |rank| (rank - now_rank).exp2());
+
+  (decayed_score + 1.0).log2() + now_rank
+}
+
+pub fn record_history_usage(history_id_value: &str) -> Result<UsageStats, Error> {
+  record_usage(Some(history_id_value), None)
+}
+
+pub fn record_item_usage(item_id_value: &str) -> Result<UsageStats, Error> {
+  record_usage(None, Some(item_id_value))
+}
+
+fn record_usage(
+  history_id_value: Option<&str>,
+  item_id_value: Option<&str>,
+) -> Result<UsageStats, Error> {
+  let connection = &mut establish_pool_db_connection();
+  let now = Utc::now().timestamp_millis();
+
+  connection.transaction::<_, Error, _>(|connection| {
+    let existing_usage = find_usage(connection, history_id_value, item_id_value)?;
+
+    match existing_usage {
+      Some(existing_usage) => {
+        let updated_rank = next_frecency_rank(Some(existing_usage.frecency_rank), now);
+
+        diesel::update(usage_stats.find(&existing_usage.usage_id))
+          .set((
+            use_count.eq(use_count + 1),
+            last_used_at.eq(now),
+            frecency_rank.eq(updated_rank),
+          ))
+          .execute(connection)?;
+
+        Ok(UsageStats {
+          use_count: existing_usage.use_count + 1,
+          last_used_at: now,
+          frecency_rank: updated_rank,
+          ..existing_usage
+        })
+      }
+      None => {
+        let new_usage = UsageStats {
+          usage_id: nanoid!().to_string(),
+          history_id: history_id_value.map(String::from),
+          item_id: item_id_value.map(String::from),
+          use_count: 1,
+          last_used_at: now,
+          frecency_rank: next_frecency_rank(None, now),
+          created_at: now,
+        };
+
+        diesel::insert_into(usage_stats)
+          .values(&new_usage)
+          .execute(connection)?;
+
+        Ok(new_usage)
+      }
+    }
+  })
+}
+
+fn find_usage(
+  connection: &mut SqliteConnection,
+  history_id_value: Option<&str>,
+  item_id_value: Option<&str>,
+) -> Result<Option<UsageStats>, Error> {
+  match (history_id_value, item_id_value) {
+    (Some(history_id_value), _) => usage_stats
+      .filter(history_id.eq(history_id_value))
+      .first::<UsageStats>(connection)
+      .optional(),
+    (None, Some(item_id_value)) => usage_stats
+      .filter(item_id.eq(item_id_value))
+      .first::<UsageStats>(connection)
+      .optional(),
+    (None, None) => Ok(None),
+  }
+}
+
+pub fn get_usage_stats_by_history_ids(history_ids: &[String]) -> Result<Vec<UsageStats>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  usage_stats
+    .filter(history_id.eq_any(history_ids))
+    .order(frecency_rank.desc())
+    .load::<UsageStats>(connection)
+}
+
+pub fn get_usage_stats_by_item_ids(item_ids: &[String]) -> Result<Vec<UsageStats>, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  usage_stats
+    .filter(item_id.eq_any(item_ids))
+    .order(frecency_rank.desc())
+    .load::<UsageStats>(connection)
+}
+
+pub fn delete_usage_by_history_ids(history_ids: &[String]) -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  diesel::delete(usage_stats.filter(history_id.eq_any(history_ids))).execute(connection)
+}
+
+/// Removes usage of history entries and clips that no longer exist
+pub fn delete_orphan_usage_stats() -> Result<usize, Error> {
+  let connection = &mut establish_pool_db_connection();
+
+  let deleted_history_usage = diesel::delete(
+    usage_stats.filter(
+      history_id.is_not_null().and(
+        history_id
+          .assume_not_null()
+          .ne_all(clipboard_history::table.select(clipboard_history::history_id)),
+      ),
+    ),
+  )
+  .execute(connection)?;
+
+  let deleted_item_usage = diesel::delete(
+    usage_stats.filter(
+      item_id.is_not_null().and(
+        item_id
+          .assume_not_null()
+          .ne_all(items::table.select(items::item_id)),
+      ),
+    ),
+  )
+  .execute(connection)?;
+
+  Ok(deleted_history_usage + deleted_item_usage)
+}
@@ -15,6 +15,7 @@ use winreg::RegKey;
 use tld;
 use url::Url;
 
+use crate::db;
 use crate::menu::AssociatedItemTree;
 use crate::models::Setting;
 
@@ -80,10 +81,13 @@ pub fn delete_file_and_maybe_parent(file_path: &Path) -> Result<(), std::io::Err
   // Try deleting the file first
   fs::remove_file(file_path)?;
 
-  // Check parent directory
+  // Check parent directory, the image folders themselves are never removed
   if let Some(parent) = file_path.parent() {
+    let is_image_root =
+      parent == db::get_clip_images_dir() || parent == db::get_clipboard_images_dir();
+
     // Check if directory is empty
-    if parent.read_dir()?.next().is_none() {
+    if !is_image_root && parent.read_dir()?.next().is_none() {
       fs::remove_dir(parent)?;
     }
   }